@@ -0,0 +1,15 @@
+-- Create webhook_deliveries table
+CREATE TABLE webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    delivery_id VARCHAR NOT NULL UNIQUE, -- X-GitHub-Delivery header
+    event_type VARCHAR NOT NULL, -- X-GitHub-Event header, e.g., 'push', 'ping'
+    payload TEXT NOT NULL, -- Raw JSON body as delivered
+    outcome VARCHAR NOT NULL DEFAULT 'received', -- e.g., 'received', 'processed', 'ignored', 'failed'
+    outcome_message TEXT,
+    replay_count INTEGER NOT NULL DEFAULT 0,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_webhook_deliveries_received_at ON webhook_deliveries(received_at DESC);
+CREATE INDEX idx_webhook_deliveries_event_type ON webhook_deliveries(event_type);
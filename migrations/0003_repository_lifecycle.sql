@@ -0,0 +1,8 @@
+-- Track repositories by their GitHub id so renames and transfers keep the same row
+ALTER TABLE repositories ADD COLUMN github_id BIGINT UNIQUE;
+
+-- Deleted repositories and their documents are tombstoned instead of removed
+ALTER TABLE repositories ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE documents ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_documents_not_deleted ON documents(repository_id) WHERE deleted_at IS NULL;
@@ -0,0 +1,14 @@
+-- Headings and frontmatter tags are extracted when documents are saved.
+-- Existing rows get them the next time their repository is processed.
+ALTER TABLE documents ADD COLUMN headings TEXT NOT NULL DEFAULT '';
+ALTER TABLE documents ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Full-text search vector weighted by title (A), headings (B) and body (C)
+ALTER TABLE documents ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(headings, '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(content, '')), 'C')
+) STORED;
+
+CREATE INDEX idx_documents_search_vector ON documents USING GIN(search_vector);
+CREATE INDEX idx_documents_tags ON documents USING GIN(tags);
@@ -0,0 +1,5 @@
+-- Organization-wide jobs (e.g., 'scan_organization') have no repository
+ALTER TABLE processing_jobs ALTER COLUMN repository_id DROP NOT NULL;
+
+-- Workers claim the oldest pending job first
+CREATE INDEX idx_processing_jobs_pending ON processing_jobs(created_at) WHERE status = 'pending';
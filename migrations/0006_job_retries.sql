@@ -0,0 +1,10 @@
+-- Failed jobs are retried with exponential backoff until they run out of attempts,
+-- then marked 'dead'
+ALTER TABLE processing_jobs
+    ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN run_after TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- Workers claim the pending job that became due first
+DROP INDEX idx_processing_jobs_pending;
+CREATE INDEX idx_processing_jobs_pending ON processing_jobs(run_after) WHERE status = 'pending';
+CREATE INDEX idx_processing_jobs_dead ON processing_jobs(updated_at DESC) WHERE status = 'dead';
@@ -0,0 +1,14 @@
+-- History of scheduled scans and processing runs. The unique key makes sure a schedule
+-- fires only once per occurrence, however many instances run the scheduler.
+CREATE TABLE schedule_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    schedule_name VARCHAR NOT NULL,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    jobs_enqueued INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT,
+    UNIQUE (schedule_name, scheduled_for)
+);
+
+CREATE INDEX idx_schedule_runs_started_at ON schedule_runs(started_at DESC);
@@ -0,0 +1,11 @@
+-- Per-repository progress reported by running jobs, streamed by /api/jobs/{id}/events
+CREATE TABLE job_events (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES processing_jobs(id) ON DELETE CASCADE,
+    repository VARCHAR NOT NULL,
+    status VARCHAR NOT NULL, -- 'processing', 'success', 'error', 'skipped'
+    message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_job_events_job_id ON job_events(job_id, id);
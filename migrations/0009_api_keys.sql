@@ -0,0 +1,12 @@
+-- API keys for the REST API. Only a SHA-256 hash of each key is stored; the key itself
+-- is shown once when it is created.
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR NOT NULL,
+    key_prefix VARCHAR NOT NULL, -- first characters of the key, to recognise it in listings
+    key_hash VARCHAR NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL, -- 'docs:read', 'jobs:write', 'admin'
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
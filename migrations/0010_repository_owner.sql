@@ -0,0 +1,7 @@
+-- Repositories of several organizations are stored side by side, so keep the owner of
+-- each one. Existing rows take it from their full name.
+ALTER TABLE repositories ADD COLUMN owner VARCHAR;
+UPDATE repositories SET owner = split_part(full_name, '/', 1);
+ALTER TABLE repositories ALTER COLUMN owner SET NOT NULL;
+
+CREATE INDEX idx_repositories_owner ON repositories(owner);
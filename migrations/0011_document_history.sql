@@ -0,0 +1,5 @@
+-- The latest commit touching each document and how many people have worked on it.
+-- Existing rows get them the next time their repository is processed.
+ALTER TABLE documents ADD COLUMN last_commit_sha VARCHAR(40);
+ALTER TABLE documents ADD COLUMN last_author VARCHAR;
+ALTER TABLE documents ADD COLUMN contributors INTEGER NOT NULL DEFAULT 0;
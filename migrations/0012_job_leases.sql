@@ -0,0 +1,5 @@
+-- Workers refresh heartbeat_at while they run a job. A running job whose heartbeat is
+-- older than the lease belongs to a worker that stopped, and is claimed again.
+ALTER TABLE processing_jobs ADD COLUMN heartbeat_at TIMESTAMPTZ;
+
+CREATE INDEX idx_processing_jobs_running ON processing_jobs(heartbeat_at) WHERE status = 'running';
@@ -0,0 +1,3 @@
+-- Why a schedule queued nothing for an occurrence, e.g. because the job it queued last
+-- time has not finished yet
+ALTER TABLE schedule_runs ADD COLUMN skipped_reason TEXT;
@@ -0,0 +1,12 @@
+-- Browsers signed in to the documentation site. The cookie holds a random session ID, of
+-- which only a SHA-256 hash is stored, bound to the hash of the key that signed in. A
+-- session ends when it expires, when the browser signs out, or when its key is revoked.
+CREATE TABLE site_sessions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_hash VARCHAR NOT NULL UNIQUE,
+    key_hash VARCHAR NOT NULL, -- an API key, or the admin token
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_site_sessions_expires_at ON site_sessions(expires_at);
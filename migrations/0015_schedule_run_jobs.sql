@@ -0,0 +1,3 @@
+-- The job an occurrence of a schedule queued, so the next occurrence can wait for that job
+-- alone rather than for every job of the same type
+ALTER TABLE schedule_runs ADD COLUMN job_id UUID REFERENCES processing_jobs(id) ON DELETE SET NULL;
@@ -1,15 +1,26 @@
 use clap::{Parser, Subcommand};
 use documents::ApplicationConfig;
+use documents::commands::api_keys::{ApiKeysArgs, ApiKeysCommand};
 use documents::commands::export_fragments::{ExportFragmentsArgs, ExportFragmentsCommand};
 use documents::commands::health_checks::{HealthArgs, run as health_check};
-use documents::commands::list_all::ListAllCommand;
+use documents::commands::http_cache::{HttpCacheArgs, HttpCacheCommand};
+use documents::commands::jobs::{JobsArgs, JobsCommand};
+use documents::commands::list_all::{ListAllArgs, ListAllCommand};
+use documents::commands::mcp::{McpArgs, McpCommand};
+use documents::commands::openapi::{OpenApiArgs, OpenApiCommand};
 use documents::commands::process_organization::{ProcessOrganizationArgs, ProcessOrganizationCommand};
 use documents::commands::process_repository::{ProcessRepositoryArgs, ProcessRepositoryCommand};
 use documents::commands::scan_organization::{ScanOrgArgs, ScanOrgCommand};
+use documents::commands::schedules::{SchedulesArgs, SchedulesCommand};
+use documents::commands::search::{SearchArgs, SearchCommand};
 use documents::commands::serve_webhook::{ServeWebhookArgs, ServeWebhookCommand};
 use documents::commands::validate_repository_configuration::{ValidateConfigArgs, ValidateConfigCommand};
-use documents::github::{Client, GitHubClient};
+use documents::commands::webhook_deliveries::{WebhookDeliveriesArgs, WebhookDeliveriesCommand};
+use documents::commands::worker::{WorkerArgs, WorkerCommand};
+use documents::github::GitHubClients;
+use std::path::PathBuf;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
@@ -18,20 +29,48 @@ use tracing_subscriber::util::SubscriberInitExt;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Only work with this organization instead of every configured one
+    #[arg(long = "org", global = true)]
+    organization: Option<String>,
+
+    /// Cache GitHub REST responses in this directory and revalidate them with conditional
+    /// requests, instead of the directory in GITHUB_HTTP_CACHE
+    #[arg(long = "http-cache", global = true, value_name = "DIR")]
+    http_cache: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Create, list and revoke API keys for the REST API
+    ApiKeys(ApiKeysArgs),
     ExportFragments(ExportFragmentsArgs),
-    ListAll,
+    /// Inspect or clear the on-disk cache of GitHub responses
+    HttpCache(HttpCacheArgs),
+    /// Inspect queued jobs and requeue dead ones
+    Jobs(JobsArgs),
+    /// List the organization's repositories and whether they have documents.toml
+    ListAll(ListAllArgs),
+    /// Serve the organization's documents to LLM tools over the Model Context Protocol (stdio)
+    Mcp(McpArgs),
+    /// Write the OpenAPI document of the REST API to a file
+    Openapi(OpenApiArgs),
     /// Process all repositories in an organization for documents.toml configuration files with content
     ProcessOrganization(ProcessOrganizationArgs),
     ProcessRepository(ProcessRepositoryArgs),
     /// Scan all repositories in an organization for documents.toml configuration files
     ScanOrganization(ScanOrgArgs),
+    /// Show the configured scan and processing schedules and their run history
+    Schedules(SchedulesArgs),
+    /// Full-text search over processed documents
+    Search(SearchArgs),
     Serve(ServeWebhookArgs),
     ValidateConfig(ValidateConfigArgs),
     HealthCheck(HealthArgs),
+    /// List recorded GitHub webhook deliveries or replay one of them
+    WebhookDeliveries(WebhookDeliveriesArgs),
+    /// Run queued scan, processing and webhook jobs
+    Worker(WorkerArgs),
 }
 
 // Load configuration from the environment or file
@@ -49,6 +88,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         _ => "info".to_string(),
     };
 
+    // The MCP stdio transport owns stdout, so its logs go to stderr
+    let log_writer = if matches!(cli.command, Some(Commands::Mcp(_))) {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
     let _ = tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| {
             format!(
@@ -58,26 +104,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             )
             .into()
         }))
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(log_writer),
+        )
         .try_init();
 
     // Load configuration
-    let config = ApplicationConfig::from_env()?;
+    let config = ApplicationConfig::from_env()?
+        .with_organization(cli.organization.as_deref())?
+        .with_http_cache(cli.http_cache);
 
-    // Initialize GitHub client
-    let github = GitHubClient::new(&config).await?;
+    // Initialize a GitHub client for each organization
+    let github = GitHubClients::new(&config).await?;
 
     match cli.command {
+        Some(Commands::ApiKeys(args)) => {
+            let command = ApiKeysCommand::new(args);
+            command.execute(&config).await?;
+        }
         Some(Commands::ExportFragments(args)) => {
             let command = ExportFragmentsCommand::new(args);
             command.execute(&github).await?;
         }
-        Some(Commands::ListAll) => {
-            ListAllCommand::execute(&github).await?;
+        Some(Commands::HttpCache(args)) => {
+            let command = HttpCacheCommand::new(args);
+            command.execute(&config).await?;
+        }
+        Some(Commands::Jobs(args)) => {
+            let command = JobsCommand::new(args);
+            command.execute(&config).await?;
+        }
+        Some(Commands::ListAll(args)) => {
+            for client in github.iter() {
+                ListAllCommand::execute(&args, client).await?;
+            }
+        }
+        Some(Commands::Mcp(args)) => {
+            let command = McpCommand::new(args);
+            command.execute(&config).await?;
+        }
+        Some(Commands::Openapi(args)) => {
+            let command = OpenApiCommand::new(args);
+            command.execute().await?;
         }
         Some(Commands::ProcessOrganization(args)) => {
             let command = ProcessOrganizationCommand::new(args);
-            command.execute(&github).await?;
+            for client in github.iter() {
+                command.execute(client).await?;
+            }
         }
         Some(Commands::ProcessRepository(args)) => {
             let command = ProcessRepositoryCommand::new(args);
@@ -85,35 +161,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
         Some(Commands::ScanOrganization(args)) => {
             let command = ScanOrgCommand::new(args);
-            command.execute(&github).await?;
+            for client in github.iter() {
+                command.execute(client).await?;
+            }
+        }
+        Some(Commands::Schedules(args)) => {
+            let command = SchedulesCommand::new(args);
+            command.execute(&config).await?;
+        }
+        Some(Commands::Search(args)) => {
+            let command = SearchCommand::new(args);
+            command.execute(&config).await?;
         }
         Some(Commands::Serve(args)) => {
-            ServeWebhookCommand::execute(args).await?;
+            ServeWebhookCommand::execute(args, &config, &github).await?;
         }
         Some(Commands::ValidateConfig(args)) => {
             let command = ValidateConfigCommand::new(args);
             command.execute(&github).await?
         }
         Some(Commands::HealthCheck(args)) => {
-            use documents::Console;
-            let console = Console::new(false);
-            
-            // Test authentication
-            let spinner = console.create_spinner("Testing GitHub authentication...");
-            match github.current_user().await {
-                Ok(user) => {
-                    console.finish_progress_success(&spinner, "GitHub authentication successful");
-                    console.health_status("GitHub Authentication", true, Some(&format!("Authenticated as: {}", user)));
-                    console.health_status("GitHub Organization", true, Some(&format!("Using organization: {}", github.organization)));
-                }
-                Err(e) => {
-                    console.finish_progress_error(&spinner, "GitHub authentication failed");
-                    console.health_status("GitHub Authentication", false, Some(&format!("Error: {}", e)));
-                    return Err(e.into());
-                }
-            }
-
-            health_check(args).await?;
+            health_check(args, &config, &github).await?;
+        }
+        Some(Commands::WebhookDeliveries(args)) => {
+            let command = WebhookDeliveriesCommand::new(args);
+            command.execute(&config, &github).await?;
+        }
+        Some(Commands::Worker(args)) => {
+            let command = WorkerCommand::new(args);
+            command.execute(&config, &github).await?;
         }
         None => {
             let _ = tracing_subscriber::fmt::try_init();
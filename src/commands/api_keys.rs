@@ -0,0 +1,111 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::database::Database;
+use crate::web::auth::{Scope, generate_api_key};
+use clap::{Args, Subcommand};
+use uuid::Uuid;
+
+#[derive(Args, Debug)]
+pub struct ApiKeysArgs {
+    #[command(subcommand)]
+    action: ApiKeysAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum ApiKeysAction {
+    /// Create a key and print it; it cannot be shown again
+    Create {
+        #[arg(long, help = "Name describing who or what uses the key")]
+        name: String,
+        #[arg(
+            long = "scope",
+            required = true,
+            help = "Scope granted to the key (docs:read, jobs:write or admin); repeat for several"
+        )]
+        scopes: Vec<Scope>,
+    },
+    /// List keys with their scopes and when they were last used
+    List,
+    /// Revoke a key so it is no longer accepted
+    Revoke {
+        /// ID of the key
+        key_id: Uuid,
+    },
+}
+
+pub struct ApiKeysCommand {
+    action: ApiKeysAction,
+}
+
+impl ApiKeysCommand {
+    pub fn new(args: ApiKeysArgs) -> Self {
+        Self {
+            action: args.action,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let database = Database::new(&config.database_url).await?;
+
+        match &self.action {
+            ApiKeysAction::Create { name, scopes } => {
+                let mut scopes: Vec<String> = scopes.iter().map(Scope::to_string).collect();
+                scopes.sort();
+                scopes.dedup();
+
+                let generated = generate_api_key();
+                let key = database
+                    .create_api_key(name, &generated.prefix, &generated.hash, &scopes)
+                    .await?;
+
+                console.success(&format!(
+                    "Created API key {} '{}' with scopes {}",
+                    key.id,
+                    key.name,
+                    key.scopes.join(", ")
+                ));
+                console.info("Store this key now, it will not be shown again:");
+                println!("  {}", generated.key);
+            }
+            ApiKeysAction::List => {
+                let keys = database.list_api_keys().await?;
+
+                if keys.is_empty() {
+                    console.info("No API keys found");
+                    return Ok(());
+                }
+
+                console.header(&format!("API keys ({}):", keys.len()));
+                for key in &keys {
+                    let status = match (key.revoked_at, key.last_used_at) {
+                        (Some(revoked_at), _) => {
+                            format!("revoked {}", revoked_at.format("%Y-%m-%d %H:%M"))
+                        }
+                        (None, Some(used_at)) => {
+                            format!("last used {}", used_at.format("%Y-%m-%d %H:%M"))
+                        }
+                        (None, None) => "never used".to_string(),
+                    };
+                    println!(
+                        "  {}  {}…  {:<20} {:<28} {}",
+                        key.id,
+                        key.key_prefix,
+                        key.name,
+                        key.scopes.join(","),
+                        status
+                    );
+                }
+            }
+            ApiKeysAction::Revoke { key_id } => match database.revoke_api_key(*key_id).await? {
+                Some(key) => console.success(&format!("Revoked API key {} '{}'", key.id, key.name)),
+                None => console.warning(&format!("No active API key with id {}", key_id)),
+            },
+        }
+
+        Ok(())
+    }
+}
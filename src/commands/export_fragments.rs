@@ -1,5 +1,5 @@
 use crate::OutputFormat;
-use crate::github::{Client, GitHubClient, GitHubError};
+use crate::github::{Client, GitHubClients, GitHubError};
 use crate::processing::{DocumentFragment, RepositoryProcessor};
 use crate::web::AppError;
 use crate::Console;
@@ -8,8 +8,11 @@ use std::path::PathBuf;
 
 #[derive(Args, Debug)]
 pub struct ExportFragmentsArgs {
-    /// GitHub repository to export fragments from
+    /// GitHub repository to export fragments from, as `owner/name` or a name in the first
+    /// configured organization
     repository: String,
+    #[arg(long = "ref", help = "Branch, tag or commit SHA to export instead of the default branch")]
+    git_ref: Option<String>,
     #[arg(long, short, help = "Output directory for generated fragments")]
     output: Option<PathBuf>,
     #[arg(long, help = "Output format", value_enum, default_value = "files")]
@@ -28,6 +31,7 @@ pub struct ExportFragmentsArgs {
 
 pub struct ExportFragmentsCommand {
     repository: String,
+    git_ref: Option<String>,
     output: Option<PathBuf>,
     format: OutputFormat,
     include_metadata: bool,
@@ -39,6 +43,7 @@ impl ExportFragmentsCommand {
     pub fn new(args: ExportFragmentsArgs) -> Self {
         Self {
             repository: args.repository,
+            git_ref: args.git_ref,
             output: args.output,
             format: args.format,
             include_metadata: args.include_metadata,
@@ -47,7 +52,8 @@ impl ExportFragmentsCommand {
         }
     }
 
-    pub async fn execute(&self, client: &GitHubClient) -> Result<(), AppError> {
+    pub async fn execute(&self, github: &GitHubClients) -> Result<(), AppError> {
+        let (client, repository) = github.resolve(&self.repository)?;
         client.handle_rate_limits().await?;
         
         let console = Console::new(false);
@@ -72,7 +78,7 @@ impl ExportFragmentsCommand {
 
         // Step 1: Get repository configuration
         let config_spinner = console.create_spinner("Fetching repository configuration...");
-        match client.get_project_config(self.repository.as_str()).await {
+        match client.get_project_config(repository, self.git_ref.as_deref()).await {
             Ok(config) => {
                 console.finish_progress_success(&config_spinner, "Configuration retrieved");
                 tracing::info!("Configuration retrieved for repository: {}", self.repository);
@@ -80,7 +86,8 @@ impl ExportFragmentsCommand {
                 // Step 2: Process repository
                 let process_spinner = console.create_spinner("Processing repository documents...");
                 let processor =
-                    RepositoryProcessor::new(client.clone(), config, self.repository.clone());
+                    RepositoryProcessor::new(client.clone(), config, repository.to_string())
+                        .with_ref(self.git_ref.clone());
 
                 match processor.process(false).await { // Don't pass verbose to avoid duplicate output
                     Ok(result) => {
@@ -1,3 +1,5 @@
+use crate::github::GitHubClients;
+use crate::health::{self, ComponentHealth};
 use crate::{ApplicationConfig, Console, Database};
 use clap::Args;
 use tracing::{error, info};
@@ -7,70 +9,94 @@ pub struct HealthArgs {
     /// Check database connectivity
     #[arg(long)]
     pub database: bool,
-    /// Check all system components
+    /// Check all system components, as `/health/ready` does
     #[arg(long)]
     pub all: bool,
 }
 
-#[allow(unused_assignments)] // There aren't any unused assignments, but this is to avoid warnings
-pub async fn run(args: HealthArgs) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn run(
+    args: HealthArgs,
+    config: &ApplicationConfig,
+    github: &GitHubClients,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let console = Console::new(false); // Health checks don't need verbose mode
-    let config = ApplicationConfig::from_env()?;
 
     console.header("System Health Check");
 
-    let mut all_healthy = true;
+    let spinner = console.create_spinner("Checking system components...");
+    info!("Checking system components...");
 
-    if args.database || args.all {
-        let spinner = console.create_spinner("Checking database connectivity...");
-        info!("Checking database connectivity...");
-
-        match Database::new(&config.database_url).await {
-            Ok(db) => match db.health_check().await {
-                Ok(_) => {
-                    console.finish_progress_success(&spinner, "Database connection established");
-                    console.health_status("Database", true, Some("Connection successful, queries working"));
-                    info!("Database is healthy.");
-                }
-                Err(e) => {
-                    console.finish_progress_error(&spinner, "Database health check failed");
-                    console.health_status("Database", false, Some(&format!("Health check failed: {}", e)));
-                    error!("Database health check failed: {}", e);
-                    all_healthy = false;
-                    return Err(e.into());
-                }
-            },
+    let components = if args.all || args.database {
+        let database = match Database::new(&config.database_url).await {
+            Ok(database) => database,
             Err(e) => {
                 console.finish_progress_error(&spinner, "Failed to connect to database");
-                console.health_status("Database", false, Some(&format!("Connection failed: {}", e)));
+                console.health_status(
+                    "Database",
+                    false,
+                    Some(&format!("Connection failed: {}", e)),
+                );
                 error!("Failed to connect to the database: {}", e);
-                all_healthy = false;
                 return Err(e.into());
             }
+        };
+
+        if args.all {
+            let github = health::check_github(github.as_slice());
+            health::check_all(&database, github, &config.output_dir)
+                .await
+                .components
+        } else {
+            vec![
+                health::check_github(github.as_slice()).await,
+                health::check_database(&database).await,
+            ]
         }
-    }
+    } else {
+        vec![health::check_github(github.as_slice()).await]
+    };
 
-    if args.all {
-        // TODO: Add checks for other components like GitHub API, etc.
-        let spinner = console.create_spinner("Checking other system components...");
-        info!("Checking other system components...");
-        
-        // Simulate some checking time
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        
+    let unhealthy: Vec<&ComponentHealth> = components.iter().filter(|c| !c.healthy).collect();
+    if unhealthy.is_empty() {
         console.finish_progress_success(&spinner, "System components checked");
-        console.health_status("Configuration", true, Some("Environment variables loaded"));
-        console.health_status("Runtime", true, Some("Tokio async runtime operational"));
-        
-        info!("All system components are healthy.");
+    } else {
+        console.finish_progress_error(&spinner, "Some system components are down");
+    }
+
+    for component in &components {
+        console.health_status(
+            &display_name(component.name),
+            component.healthy,
+            Some(&component.details),
+        );
+        if !component.healthy {
+            error!(
+                "{} health check failed: {}",
+                component.name, component.details
+            );
+        }
     }
 
     // Summary
-    if all_healthy {
+    if unhealthy.is_empty() {
         console.success("All checked components are healthy");
+        Ok(())
     } else {
         console.error("Some components are unhealthy");
+        let names: Vec<&str> = unhealthy.iter().map(|c| c.name).collect();
+        Err(format!("Unhealthy components: {}", names.join(", ")).into())
     }
+}
 
-    Ok(())
+fn display_name(name: &str) -> String {
+    match name {
+        "github" => "GitHub".to_string(),
+        other => {
+            let mut chars = other.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    }
 }
@@ -0,0 +1,82 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::github::HttpCache;
+use clap::{Args, Subcommand};
+
+#[derive(Args, Debug)]
+pub struct HttpCacheArgs {
+    #[command(subcommand)]
+    action: HttpCacheAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum HttpCacheAction {
+    /// List the cached responses with their size and when they were stored
+    Inspect,
+    /// Remove every cached response
+    Clear,
+}
+
+pub struct HttpCacheCommand {
+    action: HttpCacheAction,
+}
+
+impl HttpCacheCommand {
+    pub fn new(args: HttpCacheArgs) -> Self {
+        Self {
+            action: args.action,
+        }
+    }
+
+    /// Inspect or clear the cache in `GITHUB_HTTP_CACHE`, or the directory given with
+    /// `--http-cache`.
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let Some(dir) = &config.github_http_cache else {
+            console
+                .warning("No HTTP cache is configured; set GITHUB_HTTP_CACHE or pass --http-cache");
+            return Ok(());
+        };
+        let cache = HttpCache::new(dir);
+
+        match &self.action {
+            HttpCacheAction::Inspect => {
+                let entries = cache.entries().await?;
+                if entries.is_empty() {
+                    console.info(&format!("{} is empty", cache.dir().display()));
+                    return Ok(());
+                }
+
+                let size: u64 = entries.iter().map(|entry| entry.size).sum();
+                console.header(&format!(
+                    "{} responses in {} ({} bytes):",
+                    entries.len(),
+                    cache.dir().display(),
+                    size
+                ));
+                for entry in &entries {
+                    println!(
+                        "  {}  {:>9}  {}  {}",
+                        entry.stored_at.format("%Y-%m-%d %H:%M:%S"),
+                        entry.size,
+                        entry.route,
+                        entry.etag.as_deref().unwrap_or("-")
+                    );
+                }
+            }
+            HttpCacheAction::Clear => {
+                let count = cache.clear().await?;
+                console.success(&format!(
+                    "Removed {} responses from {}",
+                    count,
+                    cache.dir().display()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::database::Database;
+use clap::{Args, Subcommand};
+use uuid::Uuid;
+
+#[derive(Args, Debug)]
+pub struct JobsArgs {
+    #[command(subcommand)]
+    action: JobsAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum JobsAction {
+    /// List the most recently updated jobs
+    List {
+        #[arg(
+            long,
+            help = "Only list jobs with this status (pending, running, completed, dead or cancelled)"
+        )]
+        status: Option<String>,
+        #[arg(long, default_value = "20", help = "Maximum number of jobs to list")]
+        limit: i64,
+    },
+    /// Show a job with its parameters and last error
+    Show {
+        /// ID of the job
+        job_id: Uuid,
+    },
+    /// Give dead jobs a fresh set of attempts
+    Requeue {
+        /// ID of the dead job to requeue
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        job_id: Option<Uuid>,
+        #[arg(long, help = "Requeue every dead job")]
+        all: bool,
+        #[arg(
+            long,
+            requires = "all",
+            help = "Only requeue dead jobs of this type (e.g., 'process_repository')"
+        )]
+        job_type: Option<String>,
+    },
+}
+
+pub struct JobsCommand {
+    action: JobsAction,
+}
+
+impl JobsCommand {
+    pub fn new(args: JobsArgs) -> Self {
+        Self {
+            action: args.action,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let database = Database::new(&config.database_url).await?;
+
+        match &self.action {
+            JobsAction::List { status, limit } => {
+                let jobs = database.list_jobs(status.as_deref(), *limit).await?;
+
+                if jobs.is_empty() {
+                    console.info("No jobs found");
+                    return Ok(());
+                }
+
+                console.header(&format!("Jobs ({}):", jobs.len()));
+                for job in &jobs {
+                    println!(
+                        "  {}  {}  {:<18} {:<9} attempt {}/{}  {}",
+                        job.updated_at.format("%Y-%m-%d %H:%M:%S"),
+                        job.id,
+                        job.job_type,
+                        job.status,
+                        job.attempts,
+                        config.max_attempts(&job.job_type),
+                        job.error_message.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            JobsAction::Show { job_id } => {
+                let job = database.get_processing_job(*job_id).await?;
+
+                console.header(&format!("Job {}", job.id));
+                println!("  Type:        {}", job.job_type);
+                println!("  Status:      {}", job.status);
+                println!(
+                    "  Attempts:    {}/{}",
+                    job.attempts,
+                    config.max_attempts(&job.job_type)
+                );
+                println!(
+                    "  Parameters:  {}",
+                    job.parameters.as_deref().unwrap_or("-")
+                );
+                println!("  Created:     {}", job.created_at);
+                println!("  Run after:   {}", job.run_after);
+                if let Some(started_at) = job.started_at {
+                    println!("  Started:     {}", started_at);
+                }
+                if let Some(completed_at) = job.completed_at {
+                    println!("  Completed:   {}", completed_at);
+                }
+                if let Some(error) = &job.error_message {
+                    println!("  Last error:  {}", error);
+                }
+            }
+            JobsAction::Requeue {
+                job_id: Some(job_id),
+                ..
+            } => match database.requeue_dead_job(*job_id).await? {
+                Some(job) => console.success(&format!("Requeued {} job {}", job.job_type, job.id)),
+                None => {
+                    let job = database.get_processing_job(*job_id).await?;
+                    console.warning(&format!(
+                        "Job {} is {}, only dead jobs can be requeued",
+                        job.id, job.status
+                    ));
+                }
+            },
+            JobsAction::Requeue { job_type, .. } => {
+                let count = database.requeue_dead_jobs(job_type.as_deref()).await?;
+                console.success(&format!("Requeued {} dead jobs", count));
+            }
+        }
+
+        Ok(())
+    }
+}
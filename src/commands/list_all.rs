@@ -1,10 +1,19 @@
+use crate::commands::repository_filter::RepositoryFilterArgs;
 use crate::github::{Client, GitHubClient, GitHubError};
 use crate::Console;
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ListAllArgs {
+    #[command(flatten)]
+    filter: RepositoryFilterArgs,
+}
 
 pub struct ListAllCommand;
 
 impl ListAllCommand {
-    pub async fn execute(client: &GitHubClient) -> Result<(), GitHubError> {
+    pub async fn execute(args: &ListAllArgs, client: &GitHubClient) -> Result<(), GitHubError> {
+        let client = &args.filter.client(client);
         client.handle_rate_limits().await?;
         
         let console = Console::new(false);
@@ -33,7 +42,7 @@ impl ListAllCommand {
         for repository in repositories {
             progress.inc(1);
             
-            match client.get_project_config(repository.as_str()).await {
+            match client.get_project_config(repository.as_str(), None).await {
                 Ok(_) => {
                     with_config.push(repository);
                 }
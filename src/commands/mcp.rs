@@ -0,0 +1,31 @@
+use crate::ApplicationConfig;
+use crate::database::Database;
+use crate::mcp::{McpServer, stdio};
+use clap::Args;
+use tracing::info;
+
+#[derive(Args, Debug)]
+pub struct McpArgs {}
+
+pub struct McpCommand;
+
+impl McpCommand {
+    pub fn new(_args: McpArgs) -> Self {
+        Self
+    }
+
+    /// Serve MCP over stdio. The streamable HTTP transport is served by `serve` at `/mcp`.
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let database = Database::new(&config.database_url).await?;
+        let server = McpServer::new(database, config.organization_names());
+
+        info!("Serving MCP over stdio");
+        stdio::serve(&server).await?;
+        info!("MCP client disconnected");
+
+        Ok(())
+    }
+}
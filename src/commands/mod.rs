@@ -1,8 +1,18 @@
+pub mod api_keys;
 pub mod export_fragments;
 pub mod health_checks;
+pub mod http_cache;
+pub mod jobs;
 pub mod list_all;
+pub mod mcp;
+pub mod openapi;
 pub mod process_organization;
 pub mod process_repository;
+pub mod repository_filter;
 pub mod scan_organization;
+pub mod schedules;
+pub mod search;
 pub mod serve_webhook;
 pub mod validate_repository_configuration;
+pub mod webhook_deliveries;
+pub mod worker;
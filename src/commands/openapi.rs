@@ -0,0 +1,41 @@
+use crate::Console;
+use crate::web::openapi::openapi;
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct OpenApiArgs {
+    #[arg(
+        long,
+        short,
+        default_value = "openapi.json",
+        help = "File to write the OpenAPI document to"
+    )]
+    output: PathBuf,
+}
+
+pub struct OpenApiCommand {
+    output: PathBuf,
+}
+
+impl OpenApiCommand {
+    pub fn new(args: OpenApiArgs) -> Self {
+        Self {
+            output: args.output,
+        }
+    }
+
+    /// Write the OpenAPI document served at `/api/openapi.json` to a file.
+    pub async fn execute(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+
+        let document = openapi().to_pretty_json()?;
+        tokio::fs::write(&self.output, document + "\n").await?;
+
+        console.success(&format!(
+            "Wrote the OpenAPI document to {}",
+            self.output.display()
+        ));
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::commands::repository_filter::RepositoryFilterArgs;
 use crate::github::{Client, GitHubClient};
 use crate::processing::{RepositoryProcessor, OutputHandler};
 use crate::web::AppError;
@@ -22,6 +23,9 @@ pub struct ProcessOrganizationArgs {
     /// Verbose progress reporting
     #[arg(long, help = "Verbose progress reporting")]
     pub verbose: bool,
+
+    #[command(flatten)]
+    pub filter: RepositoryFilterArgs,
 }
 
 /// Command to process all repositories in an organization that have documents.toml configuration files
@@ -33,6 +37,7 @@ pub struct ProcessOrganizationCommand {
     output: Option<PathBuf>,
     format: OutputFormat,
     verbose: bool,
+    filter: RepositoryFilterArgs,
 }
 
 impl ProcessOrganizationCommand {
@@ -46,6 +51,7 @@ impl ProcessOrganizationCommand {
             output: args.output,
             format: args.format,
             verbose: args.verbose,
+            filter: args.filter,
         }
     }
 
@@ -65,6 +71,7 @@ impl ProcessOrganizationCommand {
     ///
     /// * `Result<(), AppError>` - Ok if the command executed successfully, Err otherwise
     pub async fn execute(&self, client: &GitHubClient) -> Result<(), AppError> {
+        let client = &self.filter.client(client);
         client.handle_rate_limits().await?;
         
         let console = Console::new(self.verbose);
@@ -136,6 +143,7 @@ impl ProcessOrganizationCommand {
                                 let output_dir = self.output
                                     .clone()
                                     .unwrap_or_else(|| PathBuf::from("output"))
+                                    .join(&client.organization)
                                     .join(&repo_file.repo_name);
                                 
                                 // Use shared OutputHandler for consistent output handling
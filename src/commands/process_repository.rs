@@ -1,5 +1,5 @@
 use crate::OutputFormat;
-use crate::github::{Client, GitHubClient, GitHubError};
+use crate::github::{Client, GitHubClients, GitHubError};
 use crate::processing::{RepositoryProcessor, OutputHandler};
 use crate::web::AppError;
 use crate::Console;
@@ -8,9 +8,13 @@ use std::path::PathBuf;
 
 #[derive(Args, Debug)]
 pub struct ProcessRepositoryArgs {
-    /// GitHub repository to process
+    /// GitHub repository to process, as `owner/name` or a name in the first configured organization
     pub repository: String,
 
+    /// Branch, tag or commit SHA to read documents from
+    #[arg(long = "ref", help = "Branch, tag or commit SHA to process instead of the default branch")]
+    pub git_ref: Option<String>,
+
     /// Output directory for generated fragments
     #[arg(long, short, help = "Output directory for generated fragments")]
     pub output: Option<PathBuf>,
@@ -30,6 +34,7 @@ pub struct ProcessRepositoryArgs {
 
 pub struct ProcessRepositoryCommand {
     repository: String,
+    git_ref: Option<String>,
     output: Option<PathBuf>,
     format: OutputFormat,
     force: bool,
@@ -40,6 +45,7 @@ impl ProcessRepositoryCommand {
     pub fn new(args: ProcessRepositoryArgs) -> Self {
         Self {
             repository: args.repository,
+            git_ref: args.git_ref,
             output: args.output,
             format: args.format,
             force: args.force,
@@ -47,7 +53,8 @@ impl ProcessRepositoryCommand {
         }
     }
 
-    pub async fn execute(&self, client: &GitHubClient) -> Result<(), AppError> {
+    pub async fn execute(&self, github: &GitHubClients) -> Result<(), AppError> {
+        let (client, repository) = github.resolve(&self.repository)?;
         client.handle_rate_limits().await?;
         
         let console = Console::new(self.verbose);
@@ -59,6 +66,9 @@ impl ProcessRepositoryCommand {
         // Header message
         console.header(&format!("Processing repository: {}", self.repository));
 
+        if let Some(git_ref) = &self.git_ref {
+            console.verbose(&format!("Ref: {}", git_ref));
+        }
         console.verbose(&format!("Output directory: {}", output_dir.display()));
         console.verbose(&format!("Output format: {:?}", self.format));
         console.verbose(&format!("Force reprocessing: {}", self.force));
@@ -66,7 +76,7 @@ impl ProcessRepositoryCommand {
         // Step 1: Fetch configuration
         let spinner = console.create_spinner("Fetching repository configuration...");
         
-        match client.get_project_config(self.repository.as_str()).await {
+        match client.get_project_config(repository, self.git_ref.as_deref()).await {
             Ok(config) => {
                 console.finish_progress_success(&spinner, "Configuration found");
                 tracing::info!("Found configuration for repository: {}", self.repository);
@@ -78,11 +88,13 @@ impl ProcessRepositoryCommand {
                 // Step 2: Process repository
                 let process_spinner = console.create_spinner("Processing documents...");
                 let processor =
-                    RepositoryProcessor::new(client.clone(), config, self.repository.clone());
+                    RepositoryProcessor::new(client.clone(), config, repository.to_string())
+                        .with_ref(self.git_ref.clone());
 
                 match processor.process(self.verbose).await {
                     Ok(result) => {
                         console.finish_progress_success(&process_spinner, "Documents processed");
+                        console.verbose(&format!("Read from commit {}", result.commit_sha));
                         
                         // Step 3: Save results
                         let save_spinner = console.create_spinner("Saving results...");
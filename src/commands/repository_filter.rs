@@ -0,0 +1,90 @@
+use crate::github::{GitHubClient, RepositoryFilter, Visibility};
+use clap::Args;
+
+/// Flags narrowing the repositories a command lists or scans, on top of the configured
+/// repository filter.
+#[derive(Args, Debug, Default)]
+pub struct RepositoryFilterArgs {
+    #[arg(long, help = "Leave out archived repositories")]
+    pub skip_archived: bool,
+    #[arg(long, help = "Leave out forked repositories")]
+    pub skip_forks: bool,
+    #[arg(
+        long = "visibility",
+        help = "Only include repositories with this visibility (public, private or internal); repeat for several"
+    )]
+    pub visibility: Vec<Visibility>,
+    #[arg(
+        long = "topic",
+        help = "Only include repositories with this topic; repeat to allow several"
+    )]
+    pub topics: Vec<String>,
+    #[arg(
+        long = "exclude-topic",
+        help = "Leave out repositories with this topic; repeat for several"
+    )]
+    pub excluded_topics: Vec<String>,
+}
+
+impl RepositoryFilterArgs {
+    /// The configured filter with these flags applied. Visibilities and topics given on the
+    /// command line replace the configured ones; excluded topics are added to them.
+    pub fn apply(&self, filter: &RepositoryFilter) -> RepositoryFilter {
+        let mut filter = filter.clone();
+        filter.skip_archived |= self.skip_archived;
+        filter.skip_forks |= self.skip_forks;
+        if !self.visibility.is_empty() {
+            filter.visibility = self.visibility.clone();
+        }
+        if !self.topics.is_empty() {
+            filter.topics = self.topics.clone();
+        }
+        filter
+            .excluded_topics
+            .extend(self.excluded_topics.iter().cloned());
+        filter
+    }
+
+    /// A copy of `client` that lists and scans the repositories these flags select.
+    pub fn client(&self, client: &GitHubClient) -> GitHubClient {
+        client
+            .clone()
+            .with_repository_filter(self.apply(&client.repository_filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_overrides_configured_filter() {
+        let configured = RepositoryFilter {
+            skip_archived: true,
+            skip_forks: false,
+            visibility: vec![Visibility::Public],
+            topics: vec![],
+            excluded_topics: vec!["no-docs".to_string()],
+        };
+
+        assert_eq!(RepositoryFilterArgs::default().apply(&configured), configured);
+
+        let args = RepositoryFilterArgs {
+            skip_forks: true,
+            visibility: vec![Visibility::Private, Visibility::Internal],
+            topics: vec!["documentation".to_string()],
+            excluded_topics: vec!["deprecated".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            args.apply(&configured),
+            RepositoryFilter {
+                skip_archived: true,
+                skip_forks: true,
+                visibility: vec![Visibility::Private, Visibility::Internal],
+                topics: vec!["documentation".to_string()],
+                excluded_topics: vec!["no-docs".to_string(), "deprecated".to_string()],
+            }
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::commands::repository_filter::RepositoryFilterArgs;
 use crate::github::{Client, GitHubClient, GitHubError};
 use crate::Console;
 use clap::Args;
@@ -11,6 +12,8 @@ pub struct ScanOrgArgs {
     /// Whether to show verbose output
     #[arg(short, long, help = "Show verbose output including repositories without the config file")]
     verbose: bool,
+    #[command(flatten)]
+    filter: RepositoryFilterArgs,
 }
 
 /// Command to scan all repositories in an organization for documents.toml configuration files
@@ -25,6 +28,8 @@ pub struct ScanOrgArgs {
 pub struct ScanOrgCommand {
     /// Whether to show verbose output
     verbose: bool,
+    /// Which repositories to scan
+    filter: RepositoryFilterArgs,
 }
 
 impl ScanOrgCommand {
@@ -36,6 +41,7 @@ impl ScanOrgCommand {
     pub fn new(args: ScanOrgArgs) -> Self {
         Self {
             verbose: args.verbose,
+            filter: args.filter,
         }
     }
 
@@ -58,6 +64,7 @@ impl ScanOrgCommand {
     ///
     /// * `Result<(), GitHubError>` - Ok if the command executed successfully, Err otherwise
     pub async fn execute(&self, client: &GitHubClient) -> Result<(), GitHubError> {
+        let client = &self.filter.client(client);
         client.handle_rate_limits().await?;
         
         let console = Console::new(self.verbose);
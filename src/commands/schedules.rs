@@ -0,0 +1,105 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::database::Database;
+use crate::jobs::Schedule;
+use chrono::Utc;
+use clap::{Args, Subcommand};
+
+#[derive(Args, Debug)]
+pub struct SchedulesArgs {
+    #[command(subcommand)]
+    action: SchedulesAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum SchedulesAction {
+    /// List the configured schedules with their next and last runs
+    List,
+    /// List past runs of the schedules
+    History {
+        #[arg(
+            long,
+            help = "Only list runs of this schedule (scan_organization or process_organization)"
+        )]
+        schedule: Option<String>,
+        #[arg(long, default_value = "20", help = "Maximum number of runs to list")]
+        limit: i64,
+    },
+}
+
+pub struct SchedulesCommand {
+    action: SchedulesAction,
+}
+
+impl SchedulesCommand {
+    pub fn new(args: SchedulesArgs) -> Self {
+        Self {
+            action: args.action,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let database = Database::new(&config.database_url).await?;
+
+        match &self.action {
+            SchedulesAction::List => {
+                let schedules = Schedule::configured(config);
+                if schedules.is_empty() {
+                    console.info("No schedules configured; set SCAN_SCHEDULE or PROCESS_SCHEDULE");
+                    return Ok(());
+                }
+
+                console.header(&format!("Schedules ({}):", schedules.len()));
+                for schedule in &schedules {
+                    let name = schedule.task.name();
+                    let last_run = database.list_schedule_runs(Some(name), 1).await?;
+                    println!(
+                        "  {:<22} '{}'  next: {}  last: {}",
+                        name,
+                        schedule.expression,
+                        schedule
+                            .next_after(Utc::now())
+                            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "never".to_string()),
+                        last_run
+                            .first()
+                            .map(|run| run.scheduled_for.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "never".to_string())
+                    );
+                }
+            }
+            SchedulesAction::History { schedule, limit } => {
+                let runs = database
+                    .list_schedule_runs(schedule.as_deref(), *limit)
+                    .await?;
+
+                if runs.is_empty() {
+                    console.info("No scheduled runs recorded");
+                    return Ok(());
+                }
+
+                console.header(&format!("Schedule runs ({}):", runs.len()));
+                for run in &runs {
+                    let status = match (&run.error_message, &run.skipped_reason, run.completed_at) {
+                        (Some(error), _, _) => format!("failed: {}", error),
+                        (None, Some(reason), _) => format!("skipped: {}", reason),
+                        (None, None, Some(_)) => format!("queued {} jobs", run.jobs_enqueued),
+                        (None, None, None) => "running".to_string(),
+                    };
+                    println!(
+                        "  {}  {:<22} {}",
+                        run.scheduled_for.format("%Y-%m-%d %H:%M:%S"),
+                        run.schedule_name,
+                        status
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::config::parse_list;
+use crate::database::Database;
+use clap::Args;
+use console::style;
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Search query; supports "exact phrases", `or` and -excluded words
+    query: String,
+    #[arg(long, short, help = "Only search documents of this repository")]
+    repository: Option<String>,
+    #[arg(
+        long,
+        help = "Only include documents with all of these frontmatter tags (comma-separated)"
+    )]
+    tags: Option<String>,
+    #[arg(long, default_value = "10", help = "Maximum number of results")]
+    limit: i64,
+}
+
+pub struct SearchCommand {
+    query: String,
+    repository: Option<String>,
+    tags: Vec<String>,
+    limit: i64,
+}
+
+impl SearchCommand {
+    pub fn new(args: SearchArgs) -> Self {
+        Self {
+            tags: args.tags.as_deref().map(parse_list).unwrap_or_default(),
+            query: args.query,
+            repository: args.repository,
+            limit: args.limit,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let database = Database::new(&config.database_url).await?;
+
+        let results = database
+            .search_documents(
+                &self.query,
+                self.repository.as_deref(),
+                &self.tags,
+                self.limit,
+            )
+            .await?;
+
+        if results.is_empty() {
+            console.warning(&format!("No documents match \"{}\"", self.query));
+            return Ok(());
+        }
+
+        console.header(&format!(
+            "{} results for \"{}\":",
+            results.len(),
+            self.query
+        ));
+        for result in &results {
+            println!(
+                "\n  {} {}",
+                style(&result.title).bold(),
+                style(format!("{}/{}", result.repository, result.file_path)).dim()
+            );
+            println!("    {}", terminal_snippet(&result.snippet));
+        }
+
+        Ok(())
+    }
+}
+
+/// Render an HTML search snippet for the terminal, showing matches in bold.
+fn terminal_snippet(snippet: &str) -> String {
+    let snippet = snippet.replace('\n', " ");
+    let mut output = String::new();
+
+    for (index, part) in snippet.split("<mark>").enumerate() {
+        let (matched, rest) = match part.split_once("</mark>") {
+            Some((matched, rest)) if index > 0 => (matched, rest),
+            _ => ("", part),
+        };
+        if !matched.is_empty() {
+            output.push_str(
+                &style(html_escape::decode_html_entities(matched))
+                    .bold()
+                    .yellow()
+                    .to_string(),
+            );
+        }
+        output.push_str(&html_escape::decode_html_entities(rest));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_snippet_decodes_entities() {
+        console::set_colors_enabled(false);
+        assert_eq!(
+            terminal_snippet("a &lt;b&gt; <mark>fox</mark> jumps\nover"),
+            "a <b> fox jumps over"
+        );
+    }
+}
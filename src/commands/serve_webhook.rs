@@ -1,9 +1,17 @@
+use crate::ApplicationConfig;
+use crate::database::Database;
+use crate::github::GitHubClients;
+use crate::jobs::Scheduler;
+use crate::web::AppState;
 use clap::Args;
+use tokio::sync::watch;
 
 #[derive(Args, Debug)]
 pub struct ServeWebhookArgs {
-    #[arg(long, default_value = "3000")]
-    pub port: u16,
+    #[arg(long, help = "Address to bind to, overriding SERVER_HOST")]
+    pub host: Option<String>,
+    #[arg(long, help = "Port to listen on, overriding SERVER_PORT")]
+    pub port: Option<u16>,
     #[arg(long, default_value = "info")]
     pub log_level: String,
 }
@@ -12,18 +20,38 @@ pub struct ServeWebhookCommand;
 impl ServeWebhookCommand {
     pub async fn execute(
         args: ServeWebhookArgs,
+        config: &ApplicationConfig,
+        github: &GitHubClients,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        tracing::info!("Starting documentation webhook server");
-        tracing::info!("Server will listen on http://0.0.0.0:{}", args.port);
-        tracing::info!(
-            "Health check available at http://0.0.0.0:{}/health",
-            args.port
-        );
-        tracing::info!(
-            "GitHub webhook endpoint at http://0.0.0.0:{}/webhooks/github",
-            args.port
+        let mut config = config.clone();
+        if let Some(host) = args.host {
+            config.server_host = host;
+        }
+        if let Some(port) = args.port {
+            config.server_port = port;
+        }
+
+        let base_url = format!(
+            "{}://{}:{}",
+            if config.tls.is_some() { "https" } else { "http" },
+            config.server_host,
+            config.server_port
         );
-        crate::web::start_server(args.port).await?;
+        tracing::info!("Starting documentation webhook server");
+        tracing::info!("Server will listen on {}", base_url);
+        tracing::info!("Health check available at {}/health", base_url);
+        tracing::info!("GitHub webhook endpoint at {}/webhooks/github", base_url);
+        let database = Database::new(&config.database_url).await?;
+        let state = AppState::new(config, database, github.clone());
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let scheduler = Scheduler::new(state.clone());
+        let scheduler = tokio::spawn(async move { scheduler.run(shutdown_rx).await });
+
+        crate::web::start_server(state).await?;
+
+        shutdown_tx.send(true)?;
+        scheduler.await?;
         Ok(())
     }
 }
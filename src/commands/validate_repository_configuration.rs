@@ -1,5 +1,5 @@
 use crate::count_document_paths;
-use crate::github::{Client, GitHubClient, GitHubError};
+use crate::github::{Client, GitHubClients, GitHubError};
 use crate::processing::ConfigValidator;
 use crate::web::AppError;
 use crate::Console;
@@ -7,7 +7,8 @@ use clap::Args;
 
 #[derive(Args, Debug)]
 pub struct ValidateConfigArgs {
-    /// GitHub repository to validate configuration for
+    /// GitHub repository to validate configuration for, as `owner/name` or a name in the first
+    /// configured organization
     repository: String,
     #[arg(
         short,
@@ -38,7 +39,8 @@ impl ValidateConfigCommand {
         }
     }
 
-    pub async fn execute(&self, client: &GitHubClient) -> Result<(), AppError> {
+    pub async fn execute(&self, github: &GitHubClients) -> Result<(), AppError> {
+        let (client, repository) = github.resolve(&self.repository)?;
         let console = Console::new(false);
         
         console.header(&format!("Validating configuration for repository: {}", self.repository));
@@ -47,7 +49,7 @@ impl ValidateConfigCommand {
 
         // Step 1: Fetch the configuration file from GitHub
         let spinner = console.create_spinner("Fetching configuration file...");
-        let config = match client.get_project_config(self.repository.as_str()).await {
+        let config = match client.get_project_config(repository, None).await {
             Ok(config) => {
                 console.finish_progress_success(&spinner, "Configuration file found");
                 config
@@ -74,7 +76,7 @@ impl ValidateConfigCommand {
             let base_path = self.base_dir.as_deref().unwrap_or(".");
             console.verbose(&format!("Will check file existence relative to: {}", base_path));
             tracing::info!("Checking file existence in repository relative to: {}", base_path);
-            validator = validator.with_github_file_check(&client, &self.repository, &base_path);
+            validator = validator.with_github_file_check(client, repository, base_path);
         }
 
         // Step 3: Validate configuration
@@ -0,0 +1,101 @@
+use crate::ApplicationConfig;
+use crate::Console;
+use crate::database::Database;
+use crate::github::GitHubClients;
+use crate::web::AppState;
+use crate::web::webhook::WebhookOutcome;
+use crate::web::webhook::deliveries::replay;
+use clap::{Args, Subcommand};
+
+#[derive(Args, Debug)]
+pub struct WebhookDeliveriesArgs {
+    #[command(subcommand)]
+    action: WebhookDeliveriesAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum WebhookDeliveriesAction {
+    /// List the most recently received webhook deliveries
+    List {
+        #[arg(long, help = "Only list deliveries of this event type (e.g., 'push')")]
+        event: Option<String>,
+        #[arg(
+            long,
+            default_value = "20",
+            help = "Maximum number of deliveries to list"
+        )]
+        limit: i64,
+    },
+    /// Run a recorded delivery through the webhook handler again
+    Replay {
+        /// X-GitHub-Delivery id of the delivery to replay
+        delivery_id: String,
+    },
+}
+
+pub struct WebhookDeliveriesCommand {
+    action: WebhookDeliveriesAction,
+}
+
+impl WebhookDeliveriesCommand {
+    pub fn new(args: WebhookDeliveriesArgs) -> Self {
+        Self {
+            action: args.action,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+        github: &GitHubClients,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let console = Console::new(false);
+        let database = Database::new(&config.database_url).await?;
+
+        match &self.action {
+            WebhookDeliveriesAction::List { event, limit } => {
+                let deliveries = database
+                    .list_webhook_deliveries(event.as_deref(), *limit)
+                    .await?;
+
+                if deliveries.is_empty() {
+                    console.info("No webhook deliveries recorded");
+                    return Ok(());
+                }
+
+                console.header(&format!("Webhook deliveries ({}):", deliveries.len()));
+                for delivery in &deliveries {
+                    println!(
+                        "  {}  {:<8} {:<10} {}  {}",
+                        delivery.received_at.format("%Y-%m-%d %H:%M:%S"),
+                        delivery.event_type,
+                        delivery.outcome,
+                        delivery.delivery_id,
+                        delivery.outcome_message.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            WebhookDeliveriesAction::Replay { delivery_id } => {
+                let state = AppState::new(config.clone(), database, github.clone());
+                let spinner =
+                    console.create_spinner(&format!("Replaying delivery {}...", delivery_id));
+
+                match replay(&state, delivery_id).await {
+                    Ok(WebhookOutcome::Processed(message)) => {
+                        console.finish_progress_success(&spinner, &message);
+                    }
+                    Ok(WebhookOutcome::Ignored(message)) => {
+                        spinner.finish_and_clear();
+                        console.warning(&format!("Delivery ignored: {}", message));
+                    }
+                    Err(e) => {
+                        console.finish_progress_error(&spinner, &format!("Replay failed: {}", e));
+                        return Err(e.into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
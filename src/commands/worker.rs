@@ -0,0 +1,103 @@
+use crate::ApplicationConfig;
+use crate::database::Database;
+use crate::github::GitHubClients;
+use crate::jobs::{self, Job, Scheduler, Worker};
+use crate::web::AppState;
+use clap::Args;
+use std::time::Duration;
+use tokio::sync::watch;
+
+#[derive(Args, Debug)]
+pub struct WorkerArgs {
+    #[arg(
+        long,
+        default_value = "4",
+        help = "Number of jobs to run at the same time"
+    )]
+    pub concurrency: usize,
+    #[arg(
+        long,
+        default_value = "5",
+        help = "Seconds to wait before checking an empty queue again"
+    )]
+    pub poll_interval: u64,
+    #[arg(long, help = "Queue a scan of each organization before starting")]
+    pub scan: bool,
+    #[arg(
+        long,
+        help = "Exit once the queue is empty instead of waiting for new jobs"
+    )]
+    pub once: bool,
+}
+
+pub struct WorkerCommand {
+    args: WorkerArgs,
+}
+
+impl WorkerCommand {
+    pub fn new(args: WorkerArgs) -> Self {
+        Self { args }
+    }
+
+    pub async fn execute(
+        &self,
+        config: &ApplicationConfig,
+        github: &GitHubClients,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let database = Database::new(&config.database_url).await?;
+        if self.args.scan {
+            for organization in config.organization_names() {
+                let scan = Job::ScanOrganization {
+                    organization: Some(organization),
+                };
+                jobs::enqueue(&database, &scan, None).await?;
+            }
+        }
+
+        let state = AppState::new(config.clone(), database, github.clone());
+        let scheduler = Scheduler::new(state.clone());
+        let worker = Worker::new(state, Duration::from_secs(self.args.poll_interval));
+
+        if self.args.once {
+            let mut completed = 0;
+            let mut failed = 0;
+            while let Some(run) = worker.run_next().await? {
+                match run.result {
+                    Ok(_) => completed += 1,
+                    Err(_) => failed += 1,
+                }
+            }
+            tracing::info!(
+                "Queue is empty: {} jobs completed, {} failed",
+                completed,
+                failed
+            );
+            return Ok(());
+        }
+
+        let concurrency = self.args.concurrency.max(1);
+        tracing::info!("Starting {} workers", concurrency);
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut handles: Vec<_> = (0..concurrency)
+            .map(|_| {
+                let worker = worker.clone();
+                let shutdown = shutdown_rx.clone();
+                tokio::spawn(async move { worker.run(shutdown).await })
+            })
+            .collect();
+        handles.push(tokio::spawn(
+            async move { scheduler.run(shutdown_rx).await },
+        ));
+
+        tokio::signal::ctrl_c().await?;
+        tracing::info!("Shutting down, waiting for running jobs to finish");
+        shutdown_tx.send(true)?;
+
+        for handle in handles {
+            handle.await?;
+        }
+
+        Ok(())
+    }
+}
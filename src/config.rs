@@ -1,13 +1,115 @@
+use crate::github::{RepositoryFilter, Visibility};
 use dotenvy::dotenv;
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Attempts a queued job gets before it is marked dead, unless `JOB_MAX_ATTEMPTS`
+/// sets a different number for its job type.
+pub const DEFAULT_JOB_MAX_ATTEMPTS: i32 = 3;
+
+/// How long a request may take before the server answers 503, unless `REQUEST_TIMEOUT_SECS`
+/// sets a different limit.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Certificate and private key the server terminates TLS with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    /// PEM file with the certificate chain, leaf certificate first
+    pub cert_path: PathBuf,
+    /// PEM file with the private key of the certificate
+    pub key_path: PathBuf,
+}
+
+/// Credentials the GitHub client authenticates with.
+#[derive(Clone, PartialEq)]
+pub enum GitHubAuth {
+    /// A personal access token, from `GITHUB_TOKEN`
+    Token(String),
+    /// A GitHub App, which acts through installation access tokens for the organization
+    App {
+        app_id: u64,
+        /// PEM encoded RSA private key of the app
+        private_key: String,
+        /// Installation to mint tokens for; looked up from the organization when unset
+        installation_id: Option<u64>,
+    },
+}
+
+impl GitHubAuth {
+    /// Human readable name of the authentication mode, reported by `health-check`.
+    pub fn mode(&self) -> &'static str {
+        match self {
+            GitHubAuth::Token(_) => "personal access token",
+            GitHubAuth::App { .. } => "GitHub App",
+        }
+    }
+}
+
+/// Keeps the token and private key out of logs.
+impl fmt::Debug for GitHubAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitHubAuth::Token(_) => f.debug_tuple("Token").field(&"<redacted>").finish(),
+            GitHubAuth::App {
+                app_id,
+                installation_id,
+                ..
+            } => f
+                .debug_struct("App")
+                .field("app_id", app_id)
+                .field("private_key", &"<redacted>")
+                .field("installation_id", installation_id)
+                .finish(),
+        }
+    }
+}
+
+/// An organization whose repositories are documented, and the credentials used for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrganizationConfig {
+    pub name: String,
+    pub auth: GitHubAuth,
+}
 
 #[derive(Debug, Clone)]
 pub struct ApplicationConfig {
-    pub github_token: String,
-    pub github_organization: String,
+    /// Organizations whose documents are collected, in order of preference when a bare
+    /// repository name could belong to more than one
+    pub github_organizations: Vec<OrganizationConfig>,
     pub database_url: String,
+    /// Address the server binds to; all interfaces unless `SERVER_HOST` says otherwise
     pub server_host: String,
     pub server_port: u16,
+    /// Origins browsers may call the API from. `*` allows any origin; empty allows none.
+    pub cors_allowed_origins: Vec<String>,
+    /// How long a request may take before the server answers 503
+    pub request_timeout: Duration,
+    /// Serve HTTPS instead of HTTP when set
+    pub tls: Option<TlsConfig>,
+    /// Secrets used to verify GitHub webhook signatures. More than one may be active
+    /// while a secret is being rotated.
+    pub github_webhook_secrets: Vec<String>,
+    /// Bearer token accepted by every API endpoint, for bootstrapping before API keys
+    /// have been created with `api-keys create`.
+    pub admin_api_token: Option<String>,
+    /// Serve the documentation site without signing in
+    pub site_public: bool,
+    /// Attempts per job type, overriding [`DEFAULT_JOB_MAX_ATTEMPTS`]
+    pub job_max_attempts: HashMap<String, i32>,
+    /// Cron expression for scanning the organization for documented repositories
+    pub scan_schedule: Option<String>,
+    /// Cron expression for reprocessing every documented repository
+    pub process_schedule: Option<String>,
+    /// Directory processed output is written to, checked by `/health/ready`
+    pub output_dir: PathBuf,
+    /// Repositories of the organization that are listed and scanned
+    pub repository_filter: RepositoryFilter,
+    /// Directory GitHub REST responses are cached in and revalidated from. Nothing is
+    /// cached when unset.
+    pub github_http_cache: Option<PathBuf>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -16,6 +118,8 @@ pub enum ApplicationConfigError {
     MissingEnvVar(String),
     #[error("Invalid value for {variable}: {value}")]
     InvalidValue { variable: String, value: String },
+    #[error("Organization {0} is not configured in GITHUB_ORGANIZATION")]
+    UnknownOrganization(String),
 }
 
 impl ApplicationConfig {
@@ -25,16 +129,12 @@ impl ApplicationConfig {
     }
 
     pub fn from_current_env() -> Result<Self, ApplicationConfigError> {
-        let github_token = env::var("GITHUB_TOKEN")
-            .map_err(|_| ApplicationConfigError::MissingEnvVar("GITHUB_TOKEN".to_string()))?;
-
-        let github_organization = env::var("GITHUB_ORGANIZATION")
-            .map_err(|_| ApplicationConfigError::MissingEnvVar("GITHUB_ORGANIZATION".to_string()))?;
+        let github_organizations = github_organizations(&|name| env::var(name).ok())?;
 
         let database_url = env::var("DATABASE_URL")
             .map_err(|_| ApplicationConfigError::MissingEnvVar("DATABASE_URL".to_string()))?;
 
-        let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+        let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
 
         let server_port = env::var("SERVER_PORT")
             .unwrap_or_else(|_| "3000".to_string())
@@ -44,26 +144,161 @@ impl ApplicationConfig {
                 value: env::var("SERVER_PORT").unwrap_or_default(),
             })?;
 
+        let cors_allowed_origins = match env::var("CORS_ALLOWED_ORIGINS") {
+            Ok(value) => parse_origins(&value).map_err(|origin| {
+                ApplicationConfigError::InvalidValue {
+                    variable: "CORS_ALLOWED_ORIGINS".to_string(),
+                    value: origin,
+                }
+            })?,
+            Err(_) => Vec::new(),
+        };
+
+        let request_timeout = match env::var("REQUEST_TIMEOUT_SECS") {
+            Ok(value) => parse_timeout(&value).ok_or(ApplicationConfigError::InvalidValue {
+                variable: "REQUEST_TIMEOUT_SECS".to_string(),
+                value,
+            })?,
+            Err(_) => DEFAULT_REQUEST_TIMEOUT,
+        };
+
+        let tls = tls_config(path_var("TLS_CERT_PATH"), path_var("TLS_KEY_PATH"))?;
+
+        let github_webhook_secrets = env::var("GITHUB_WEBHOOK_SECRET")
+            .map(|value| parse_list(&value))
+            .unwrap_or_default();
+
+        let admin_api_token = env::var("ADMIN_API_TOKEN")
+            .ok()
+            .filter(|token| !token.trim().is_empty());
+
+        let site_public = bool_var("SITE_PUBLIC")?;
+
+        let job_max_attempts = match env::var("JOB_MAX_ATTEMPTS") {
+            Ok(value) => parse_max_attempts(&value).ok_or(ApplicationConfigError::InvalidValue {
+                variable: "JOB_MAX_ATTEMPTS".to_string(),
+                value,
+            })?,
+            Err(_) => HashMap::new(),
+        };
+
+        let scan_schedule = schedule_var("SCAN_SCHEDULE")?;
+        let process_schedule = schedule_var("PROCESS_SCHEDULE")?;
+
+        let output_dir = path_var("OUTPUT_DIR").unwrap_or_else(|| PathBuf::from("output"));
+
+        let repository_filter = RepositoryFilter {
+            skip_archived: bool_var("SKIP_ARCHIVED_REPOSITORIES")?,
+            skip_forks: bool_var("SKIP_FORKED_REPOSITORIES")?,
+            visibility: match env::var("REPOSITORY_VISIBILITY") {
+                Ok(value) => parse_visibility(&value).ok_or(
+                    ApplicationConfigError::InvalidValue {
+                        variable: "REPOSITORY_VISIBILITY".to_string(),
+                        value,
+                    },
+                )?,
+                Err(_) => Vec::new(),
+            },
+            topics: env::var("REPOSITORY_TOPICS")
+                .map(|value| parse_list(&value))
+                .unwrap_or_default(),
+            excluded_topics: env::var("EXCLUDED_REPOSITORY_TOPICS")
+                .map(|value| parse_list(&value))
+                .unwrap_or_default(),
+        };
+
+        let github_http_cache = path_var("GITHUB_HTTP_CACHE");
+
         Ok(ApplicationConfig {
-            github_token,
-            github_organization,
+            github_organizations,
             database_url,
             server_host,
             server_port,
+            cors_allowed_origins,
+            request_timeout,
+            tls,
+            github_webhook_secrets,
+            admin_api_token,
+            site_public,
+            job_max_attempts,
+            scan_schedule,
+            process_schedule,
+            output_dir,
+            repository_filter,
+            github_http_cache,
         })
     }
 
-    pub fn validate(&self) -> Result<(), ApplicationConfigError> {
-        if self.github_token.is_empty() {
-            return Err(ApplicationConfigError::MissingEnvVar("GITHUB_TOKEN".to_string()));
+    /// How many times a job of this type is attempted before it is marked dead.
+    pub fn max_attempts(&self, job_type: &str) -> i32 {
+        self.job_max_attempts
+            .get(job_type)
+            .copied()
+            .unwrap_or(DEFAULT_JOB_MAX_ATTEMPTS)
+    }
+
+    /// Names of the configured organizations, in order of preference.
+    pub fn organization_names(&self) -> Vec<String> {
+        self.github_organizations
+            .iter()
+            .map(|organization| organization.name.clone())
+            .collect()
+    }
+
+    /// Whether `owner` is one of the configured organizations. GitHub logins are not case
+    /// sensitive.
+    pub fn has_organization(&self, owner: &str) -> bool {
+        self.github_organizations
+            .iter()
+            .any(|organization| organization.name.eq_ignore_ascii_case(owner))
+    }
+
+    /// This configuration limited to one of its organizations, or unchanged for `None`.
+    pub fn with_organization(mut self, name: Option<&str>) -> Result<Self, ApplicationConfigError> {
+        if let Some(name) = name {
+            self.github_organizations
+                .retain(|organization| organization.name.eq_ignore_ascii_case(name));
+            if self.github_organizations.is_empty() {
+                return Err(ApplicationConfigError::UnknownOrganization(name.to_string()));
+            }
         }
+        Ok(self)
+    }
 
-        if self.github_organization.is_empty() {
+    /// This configuration caching GitHub responses in `dir`, or unchanged for `None`.
+    pub fn with_http_cache(mut self, dir: Option<PathBuf>) -> Self {
+        if dir.is_some() {
+            self.github_http_cache = dir;
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ApplicationConfigError> {
+        if self.github_organizations.is_empty() {
             return Err(ApplicationConfigError::MissingEnvVar(
                 "GITHUB_ORGANIZATION".to_string(),
             ));
         }
 
+        for organization in &self.github_organizations {
+            let suffix = organization_suffix(&organization.name);
+            match &organization.auth {
+                GitHubAuth::Token(token) if token.is_empty() => {
+                    return Err(ApplicationConfigError::MissingEnvVar(format!(
+                        "GITHUB_TOKEN or GITHUB_TOKEN{}",
+                        suffix
+                    )));
+                }
+                GitHubAuth::App { private_key, .. } if private_key.trim().is_empty() => {
+                    return Err(ApplicationConfigError::MissingEnvVar(format!(
+                        "GITHUB_APP_PRIVATE_KEY or GITHUB_APP_PRIVATE_KEY{}",
+                        suffix
+                    )));
+                }
+                _ => {}
+            }
+        }
+
         if self.database_url.is_empty() {
             return Err(ApplicationConfigError::MissingEnvVar("DATABASE_URL".to_string()));
         }
@@ -72,6 +307,287 @@ impl ApplicationConfig {
     }
 }
 
+/// Split a comma-separated value, such as an environment variable or a tag filter, into its
+/// non-empty, trimmed entries.
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read an optional path, treating an empty value as unset.
+fn path_var(variable: &str) -> Option<PathBuf> {
+    env::var(variable)
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Variables are read through a lookup function so that tests need not touch the
+/// environment. Empty values count as unset.
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Read the organizations from the comma-separated `GITHUB_ORGANIZATION`, each with its
+/// credentials.
+fn github_organizations(lookup: Lookup) -> Result<Vec<OrganizationConfig>, ApplicationConfigError> {
+    let var = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+
+    let value = var("GITHUB_ORGANIZATION")
+        .ok_or_else(|| ApplicationConfigError::MissingEnvVar("GITHUB_ORGANIZATION".to_string()))?;
+    let names = parse_list(&value);
+
+    let mut seen = Vec::new();
+    for name in &names {
+        let lowercase = name.to_ascii_lowercase();
+        if seen.contains(&lowercase) {
+            return Err(ApplicationConfigError::InvalidValue {
+                variable: "GITHUB_ORGANIZATION".to_string(),
+                value: value.clone(),
+            });
+        }
+        seen.push(lowercase);
+    }
+
+    let single = names.len() == 1;
+    names
+        .into_iter()
+        .map(|name| {
+            Ok(OrganizationConfig {
+                auth: organization_auth(&name, single, &var)?,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Suffix of the variables holding the credentials of one organization: `_ACME_LABS` for
+/// `acme-labs`.
+fn organization_suffix(organization: &str) -> String {
+    let name: String = organization
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("_{}", name)
+}
+
+/// Credentials of one organization. Its own, from variables with its suffix such as
+/// `GITHUB_TOKEN_ACME_LABS`, take precedence over the shared unsuffixed ones.
+///
+/// A shared GitHub App is usually installed on every organization, so its installation is
+/// looked up per organization. `GITHUB_APP_INSTALLATION_ID` only applies when a single
+/// organization is configured.
+fn organization_auth(
+    organization: &str,
+    single: bool,
+    var: Lookup,
+) -> Result<GitHubAuth, ApplicationConfigError> {
+    let suffix = organization_suffix(organization);
+    let has_own = [
+        "GITHUB_TOKEN",
+        "GITHUB_APP_ID",
+        "GITHUB_APP_PRIVATE_KEY",
+        "GITHUB_APP_PRIVATE_KEY_PATH",
+    ]
+    .iter()
+    .any(|name| var(&format!("{}{}", name, suffix)).is_some());
+
+    let installation_id = var(&format!("GITHUB_APP_INSTALLATION_ID{}", suffix))
+        .map(|id| (format!("GITHUB_APP_INSTALLATION_ID{}", suffix), id))
+        .or_else(|| {
+            var("GITHUB_APP_INSTALLATION_ID")
+                .filter(|_| single)
+                .map(|id| ("GITHUB_APP_INSTALLATION_ID".to_string(), id))
+        });
+
+    github_auth(if has_own { &suffix } else { "" }, installation_id, var)
+}
+
+/// Read the GitHub App private key, given inline in `GITHUB_APP_PRIVATE_KEY` (with `\n`
+/// escapes allowed, as many secret stores cannot hold newlines) or as a file in
+/// `GITHUB_APP_PRIVATE_KEY_PATH`.
+fn app_private_key(suffix: &str, var: Lookup) -> Result<Option<String>, ApplicationConfigError> {
+    if let Some(key) = var(&format!("GITHUB_APP_PRIVATE_KEY{}", suffix)) {
+        return Ok(Some(key.replace("\\n", "\n")));
+    }
+
+    let variable = format!("GITHUB_APP_PRIVATE_KEY_PATH{}", suffix);
+    match var(&variable) {
+        Some(path) => std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|_| ApplicationConfigError::InvalidValue {
+                variable,
+                value: path,
+            }),
+        None => Ok(None),
+    }
+}
+
+/// Read the credentials from the variables ending in `suffix`. App credentials take
+/// precedence over a personal access token, so a token can stay configured while
+/// switching to the app.
+fn github_auth(
+    suffix: &str,
+    installation_id: Option<(String, String)>,
+    var: Lookup,
+) -> Result<GitHubAuth, ApplicationConfigError> {
+    let name = |variable: &str| format!("{}{}", variable, suffix);
+    let token = var(&name("GITHUB_TOKEN"));
+    let private_key = app_private_key(suffix, var)?;
+
+    let Some(app_id) = var(&name("GITHUB_APP_ID")) else {
+        if private_key.is_some() {
+            return Err(ApplicationConfigError::MissingEnvVar(name("GITHUB_APP_ID")));
+        }
+        return token.map(GitHubAuth::Token).ok_or_else(|| {
+            ApplicationConfigError::MissingEnvVar(format!(
+                "{} or {}",
+                name("GITHUB_TOKEN"),
+                name("GITHUB_APP_ID")
+            ))
+        });
+    };
+
+    let parse_id = |variable: String, value: String| {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| ApplicationConfigError::InvalidValue { variable, value })
+    };
+
+    Ok(GitHubAuth::App {
+        app_id: parse_id(name("GITHUB_APP_ID"), app_id)?,
+        private_key: private_key.ok_or_else(|| {
+            ApplicationConfigError::MissingEnvVar(name("GITHUB_APP_PRIVATE_KEY"))
+        })?,
+        installation_id: installation_id
+            .map(|(variable, id)| parse_id(variable, id))
+            .transpose()?,
+    })
+}
+
+/// Read an optional flag such as `true`, `false`, `1` or `0`; unset means false.
+fn bool_var(variable: &str) -> Result<bool, ApplicationConfigError> {
+    match env::var(variable) {
+        Ok(value) => parse_bool(&value).ok_or(ApplicationConfigError::InvalidValue {
+            variable: variable.to_string(),
+            value,
+        }),
+        Err(_) => Ok(false),
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse comma-separated repository visibilities such as `public,internal`.
+fn parse_visibility(value: &str) -> Option<Vec<Visibility>> {
+    parse_list(value)
+        .iter()
+        .map(|visibility| visibility.parse().ok())
+        .collect()
+}
+
+/// Parse comma-separated CORS origins, returning the first invalid one as the error.
+fn parse_origins(value: &str) -> Result<Vec<String>, String> {
+    let origins = parse_list(value);
+    match origins.iter().find(|origin| !is_valid_origin(origin)) {
+        Some(origin) => Err(origin.clone()),
+        None => Ok(origins),
+    }
+}
+
+/// Parse a positive number of seconds.
+fn parse_timeout(value: &str) -> Option<Duration> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// TLS is enabled by setting both the certificate and the key; setting only one is an error.
+fn tls_config(
+    cert_path: Option<PathBuf>,
+    key_path: Option<PathBuf>,
+) -> Result<Option<TlsConfig>, ApplicationConfigError> {
+    match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => Ok(Some(TlsConfig {
+            cert_path,
+            key_path,
+        })),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(ApplicationConfigError::MissingEnvVar(
+            "TLS_KEY_PATH".to_string(),
+        )),
+        (None, Some(_)) => Err(ApplicationConfigError::MissingEnvVar(
+            "TLS_CERT_PATH".to_string(),
+        )),
+    }
+}
+
+/// Whether a CORS origin is `*` or a scheme and host such as `https://docs.example.com`.
+fn is_valid_origin(origin: &str) -> bool {
+    if origin == "*" {
+        return true;
+    }
+
+    match origin.split_once("://") {
+        Some((scheme, host)) => {
+            matches!(scheme, "http" | "https")
+                && !host.is_empty()
+                && !host.contains('/')
+                && origin.is_ascii()
+        }
+        None => false,
+    }
+}
+
+/// Read an optional cron expression, rejecting ones the scheduler cannot parse.
+fn schedule_var(variable: &str) -> Result<Option<String>, ApplicationConfigError> {
+    let Some(expression) = env::var(variable)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    match croner::Cron::new(&expression).parse() {
+        Ok(_) => Ok(Some(expression)),
+        Err(_) => Err(ApplicationConfigError::InvalidValue {
+            variable: variable.to_string(),
+            value: expression,
+        }),
+    }
+}
+
+/// Parse `job_type=attempts` pairs such as `process_repository=5,scan_organization=2`.
+fn parse_max_attempts(value: &str) -> Option<HashMap<String, i32>> {
+    parse_list(value)
+        .iter()
+        .map(|entry| {
+            let (job_type, attempts) = entry.split_once('=')?;
+            let attempts = attempts.trim().parse().ok().filter(|n| *n > 0)?;
+            Some((job_type.trim().to_string(), attempts))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,6 +601,7 @@ mod tests {
 
         unsafe {
             env::remove_var("GITHUB_TOKEN");
+            env::remove_var("GITHUB_APP_ID");
             env::remove_var("GITHUB_ORGANIZATION");
             env::remove_var("DATABASE_URL");
             env::remove_var("SERVER_HOST");
@@ -96,17 +613,242 @@ mod tests {
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_config_validation() {
-        let config = ApplicationConfig {
-            github_token: "".to_string(),
-            github_organization: "test-org".to_string(),
+    fn test_config() -> ApplicationConfig {
+        ApplicationConfig {
+            github_organizations: vec![OrganizationConfig {
+                name: "test-org".to_string(),
+                auth: GitHubAuth::Token("".to_string()),
+            }],
             database_url: "postgres://localhost/test".to_string(),
             server_host: "localhost".to_string(),
             server_port: 3000,
-        };
+            cors_allowed_origins: vec![],
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            tls: None,
+            github_webhook_secrets: vec![],
+            admin_api_token: None,
+            site_public: false,
+            job_max_attempts: HashMap::new(),
+            scan_schedule: None,
+            process_schedule: None,
+            output_dir: PathBuf::from("output"),
+            repository_filter: RepositoryFilter::default(),
+            github_http_cache: None,
+        }
+    }
 
-        let result = config.validate();
+    #[test]
+    fn test_config_validation() {
+        let result = test_config().validate();
         assert!(result.is_err());
     }
+
+    fn organizations(
+        vars: &[(&str, &str)],
+    ) -> Result<Vec<OrganizationConfig>, ApplicationConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        github_organizations(&|name| vars.get(name).cloned())
+    }
+
+    fn organization(name: &str, auth: GitHubAuth) -> OrganizationConfig {
+        OrganizationConfig {
+            name: name.to_string(),
+            auth,
+        }
+    }
+
+    #[test]
+    fn test_github_auth() {
+        let token = |value: &str| GitHubAuth::Token(value.to_string());
+
+        assert_eq!(
+            organizations(&[("GITHUB_ORGANIZATION", "test-org"), ("GITHUB_TOKEN", "ghp_token")])
+                .unwrap(),
+            vec![organization("test-org", token("ghp_token"))]
+        );
+        assert_eq!(
+            organizations(&[
+                ("GITHUB_ORGANIZATION", "test-org"),
+                ("GITHUB_TOKEN", "ghp_token"),
+                ("GITHUB_APP_ID", "123"),
+                ("GITHUB_APP_PRIVATE_KEY", "PEM"),
+                ("GITHUB_APP_INSTALLATION_ID", " 456 "),
+            ])
+            .unwrap(),
+            vec![organization(
+                "test-org",
+                GitHubAuth::App {
+                    app_id: 123,
+                    private_key: "PEM".to_string(),
+                    installation_id: Some(456),
+                }
+            )]
+        );
+
+        let invalid = [
+            vec![("GITHUB_TOKEN", "ghp_token")],
+            vec![("GITHUB_ORGANIZATION", "test-org")],
+            vec![("GITHUB_ORGANIZATION", "test-org"), ("GITHUB_TOKEN", " ")],
+            vec![("GITHUB_ORGANIZATION", "test-org"), ("GITHUB_APP_ID", "123")],
+            vec![
+                ("GITHUB_ORGANIZATION", "test-org"),
+                ("GITHUB_APP_ID", "my-app"),
+                ("GITHUB_APP_PRIVATE_KEY", "PEM"),
+            ],
+            vec![
+                ("GITHUB_ORGANIZATION", "test-org"),
+                ("GITHUB_TOKEN", "ghp_token"),
+                ("GITHUB_APP_PRIVATE_KEY", "PEM"),
+            ],
+            vec![
+                ("GITHUB_ORGANIZATION", "test-org, Test-Org"),
+                ("GITHUB_TOKEN", "ghp_token"),
+            ],
+        ];
+        for vars in invalid {
+            assert!(organizations(&vars).is_err(), "{:?}", vars);
+        }
+
+        let debug = format!("{:?}", token("ghp_secret"));
+        assert!(!debug.contains("ghp_secret"));
+    }
+
+    #[test]
+    fn test_organization_credentials() {
+        let organizations = organizations(&[
+            ("GITHUB_ORGANIZATION", "acme, acme-labs, widgets"),
+            ("GITHUB_APP_ID", "123"),
+            ("GITHUB_APP_PRIVATE_KEY", "PEM"),
+            ("GITHUB_APP_INSTALLATION_ID", "456"),
+            ("GITHUB_APP_INSTALLATION_ID_ACME", "789"),
+            ("GITHUB_TOKEN_ACME_LABS", "ghp_labs"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            organizations,
+            vec![
+                organization(
+                    "acme",
+                    GitHubAuth::App {
+                        app_id: 123,
+                        private_key: "PEM".to_string(),
+                        installation_id: Some(789),
+                    }
+                ),
+                organization("acme-labs", GitHubAuth::Token("ghp_labs".to_string())),
+                // The shared installation id is ambiguous with several organizations
+                organization(
+                    "widgets",
+                    GitHubAuth::App {
+                        app_id: 123,
+                        private_key: "PEM".to_string(),
+                        installation_id: None,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(organization_suffix("acme-labs"), "_ACME_LABS");
+    }
+
+    #[test]
+    fn test_with_organization() {
+        let mut config = test_config();
+        config.github_organizations = vec![
+            organization("acme", GitHubAuth::Token("a".to_string())),
+            organization("widgets", GitHubAuth::Token("b".to_string())),
+        ];
+
+        assert!(config.has_organization("ACME"));
+        assert_eq!(
+            config.clone().with_organization(None).unwrap().organization_names(),
+            vec!["acme", "widgets"]
+        );
+        assert_eq!(
+            config
+                .clone()
+                .with_organization(Some("Widgets"))
+                .unwrap()
+                .organization_names(),
+            vec!["widgets"]
+        );
+        assert!(matches!(
+            config.with_organization(Some("other")),
+            Err(ApplicationConfigError::UnknownOrganization(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("new-secret, old-secret"), vec!["new-secret", "old-secret"]);
+        assert_eq!(parse_list("api, errors,"), vec!["api", "errors"]);
+        assert_eq!(parse_list("only"), vec!["only"]);
+        assert!(parse_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_server_settings() {
+        assert_eq!(
+            parse_origins("https://docs.example.com, http://localhost:5173").unwrap(),
+            vec!["https://docs.example.com", "http://localhost:5173"]
+        );
+        assert_eq!(parse_origins("*").unwrap(), vec!["*"]);
+        assert_eq!(
+            parse_origins("https://docs.example.com,docs.example.com"),
+            Err("docs.example.com".to_string())
+        );
+
+        assert_eq!(parse_timeout(" 90 "), Some(Duration::from_secs(90)));
+        assert_eq!(parse_timeout("0"), None);
+        assert_eq!(parse_timeout("soon"), None);
+
+        let tls = tls_config(Some("cert.pem".into()), Some("key.pem".into())).unwrap();
+        assert_eq!(
+            tls,
+            Some(TlsConfig {
+                cert_path: PathBuf::from("cert.pem"),
+                key_path: PathBuf::from("key.pem"),
+            })
+        );
+        assert_eq!(tls_config(None, None).unwrap(), None);
+        assert!(tls_config(Some("cert.pem".into()), None).is_err());
+        assert!(tls_config(None, Some("key.pem".into())).is_err());
+    }
+
+    #[test]
+    fn test_parse_repository_filter_settings() {
+        assert_eq!(parse_bool("TRUE"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("sometimes"), None);
+
+        assert_eq!(
+            parse_visibility("public, INTERNAL"),
+            Some(vec![Visibility::Public, Visibility::Internal])
+        );
+        assert_eq!(parse_visibility("secret"), None);
+    }
+
+    #[test]
+    fn test_is_valid_origin() {
+        assert!(is_valid_origin("*"));
+        assert!(is_valid_origin("https://docs.example.com"));
+        assert!(is_valid_origin("http://localhost:5173"));
+        assert!(!is_valid_origin("docs.example.com"));
+        assert!(!is_valid_origin("https://docs.example.com/"));
+        assert!(!is_valid_origin("ftp://docs.example.com"));
+    }
+
+    #[test]
+    fn test_parse_max_attempts() {
+        let attempts = parse_max_attempts("process_repository=5, scan_organization = 2").unwrap();
+        assert_eq!(attempts["process_repository"], 5);
+        assert_eq!(attempts["scan_organization"], 2);
+
+        assert!(parse_max_attempts("process_repository").is_none());
+        assert!(parse_max_attempts("process_repository=0").is_none());
+        assert!(parse_max_attempts("").unwrap().is_empty());
+    }
 }
@@ -183,6 +183,25 @@ pub enum RepoStatus {
     Skipped(String),
 }
 
+impl RepoStatus {
+    /// Short name of the status, as reported in job progress events
+    pub fn name(&self) -> &'static str {
+        match self {
+            RepoStatus::Processing => "processing",
+            RepoStatus::Success => "success",
+            RepoStatus::Error(_) => "error",
+            RepoStatus::Skipped(_) => "skipped",
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            RepoStatus::Error(message) | RepoStatus::Skipped(message) => Some(message),
+            RepoStatus::Processing | RepoStatus::Success => None,
+        }
+    }
+}
+
 impl Default for Console {
     fn default() -> Self {
         Self::new(false)
@@ -13,6 +13,8 @@ pub struct Repository {
     pub id: Uuid,
     pub name: String,
     pub full_name: String,
+    /// Login of the organization that owns the repository
+    pub owner: String,
     pub description: Option<String>,
     pub default_branch: String,
     pub is_private: bool,
@@ -24,6 +26,35 @@ pub struct Repository {
     pub last_processed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub github_id: Option<i64>,
+    pub deleted_at: Option<DateTime<Utc>>, // Set when the repository was deleted on GitHub
+}
+
+impl Repository {
+    /// Row for a repository that is not tracked yet. GitHub metadata defaults until a
+    /// webhook event or a scan fills it in.
+    pub fn new(owner: &str, name: &str) -> Self {
+        let now = Utc::now();
+        Repository {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            full_name: format!("{}/{}", owner, name),
+            owner: owner.to_string(),
+            description: None,
+            default_branch: "main".to_string(),
+            is_private: false,
+            is_archived: false,
+            is_fork: false,
+            has_documents_config: false,
+            documents_config: None,
+            last_scanned_at: None,
+            last_processed_at: None,
+            created_at: now,
+            updated_at: now,
+            github_id: None,
+            deleted_at: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -39,6 +70,25 @@ pub struct Document {
     pub last_modified_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>, // Tombstone; set when the repository was deleted
+    pub headings: String,                  // Markdown headings, one per line, for search weighting
+    pub tags: Vec<String>,                 // Frontmatter tags
+    pub last_commit_sha: Option<String>,   // Latest commit touching the file
+    pub last_author: Option<String>,       // GitHub login of the author of that commit
+    pub contributors: i32,                 // Distinct authors of recent commits to the file
+}
+
+/// A document matching a full-text search, best matches first.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct SearchResult {
+    /// `owner/name` of the repository
+    pub repository: String,
+    pub file_path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub rank: f32,
+    pub snippet: String, // HTML-escaped excerpt with matches wrapped in <mark>
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -46,13 +96,81 @@ pub struct ProcessingJob {
     pub id: Uuid,
     pub repository_id: Option<Uuid>,
     pub job_type: String, // "scan_organization", "process_repository", "process_document"
-    pub status: String,   // "pending", "running", "completed", "failed"
+    pub status: String,   // "pending", "running", "completed", "failed", "dead", "cancelled"
     pub parameters: Option<String>, // JSON string of job parameters
     pub error_message: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Number of times a worker has started the job
+    pub attempts: i32,
+    /// The job is not claimed before this time
+    pub run_after: DateTime<Utc>,
+    /// Last time the worker running the job reported that it is still alive
+    pub heartbeat_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobEvent {
+    pub id: i64,
+    pub job_id: Uuid,
+    pub repository: String,
+    pub status: String, // "processing", "success", "error", "skipped"
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>, // "docs:read", "jobs:write", "admin"
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A browser signed in to the documentation site.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SiteSession {
+    pub id: Uuid,
+    pub session_hash: String,
+    /// Hash of the API key, or admin token, the browser signed in with
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScheduleRun {
+    pub id: Uuid,
+    pub schedule_name: String, // e.g., 'scan_organization'
+    pub scheduled_for: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub jobs_enqueued: i32,
+    pub error_message: Option<String>,
+    /// Why the occurrence queued nothing, when it was skipped
+    pub skipped_reason: Option<String>,
+    /// The job the occurrence queued
+    pub job_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub delivery_id: String, // X-GitHub-Delivery header
+    pub event_type: String,  // X-GitHub-Event header
+    pub payload: String,     // Raw JSON body
+    pub outcome: String,     // "received", "processed", "ignored", "failed"
+    pub outcome_message: Option<String>,
+    pub replay_count: i32,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -65,6 +183,10 @@ pub enum DatabaseError {
     RepositoryNotFound(String),
     #[error("Document not found: {0}")]
     DocumentNotFound(String),
+    #[error("Webhook delivery not found: {0}")]
+    WebhookDeliveryNotFound(String),
+    #[error("Processing job not found: {0}")]
+    JobNotFound(Uuid),
 }
 
 impl Database {
@@ -74,6 +196,17 @@ impl Database {
         Ok(Self { pool })
     }
 
+    /// Create a database instance whose connections are only opened when first used.
+    pub fn connect_lazy(database_url: &str) -> Result<Self, DatabaseError> {
+        let pool = PgPool::connect_lazy(database_url)?;
+        Ok(Self { pool })
+    }
+
+    /// Wrap an existing connection pool.
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
     /// Run database migrations.
     pub async fn migrate(&self) -> Result<(), DatabaseError> {
         sqlx::migrate!("./migrations")
@@ -89,24 +222,94 @@ impl Database {
         Ok(())
     }
 
+    /// Versions of the migrations shipped with this build that have not been applied.
+    pub async fn pending_migrations(&self) -> Result<Vec<i64>, DatabaseError> {
+        let applied: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(sqlx::migrate!("./migrations")
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect())
+    }
+
     /// Get a connection pool for advanced usage.
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
     // Repository operations
+
+    /// Insert or update a repository.
+    ///
+    /// Rows are matched by `github_id` first, so a repository that was renamed or transferred
+    /// keeps its id (and therefore its documents). Rows without a known GitHub id fall back to
+    /// matching on `full_name`.
     pub async fn upsert_repository(&self, repo: &Repository) -> Result<Repository, DatabaseError> {
+        if let Some(github_id) = repo.github_id {
+            let updated = sqlx::query_as!(
+                Repository,
+                r#"
+UPDATE repositories
+SET
+    name = $2,
+    full_name = $3,
+    description = $4,
+    default_branch = $5,
+    is_private = $6,
+    is_archived = $7,
+    is_fork = $8,
+    has_documents_config = $9,
+    documents_config = $10,
+    last_scanned_at = $11,
+    last_processed_at = $12,
+    updated_at = $13,
+    deleted_at = $14,
+    owner = $15
+WHERE github_id = $1
+RETURNING *
+                "#,
+                github_id,
+                repo.name,
+                repo.full_name,
+                repo.description,
+                repo.default_branch,
+                repo.is_private,
+                repo.is_archived,
+                repo.is_fork,
+                repo.has_documents_config,
+                repo.documents_config.as_deref(),
+                repo.last_scanned_at,
+                repo.last_processed_at,
+                repo.updated_at,
+                repo.deleted_at,
+                repo.owner
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+
+            if let Some(updated) = updated {
+                return Ok(updated);
+            }
+        }
+
         let result = sqlx::query_as!(
             Repository,
             r#"
 INSERT INTO repositories (
     id, name, full_name, description, default_branch, is_private, is_archived, is_fork,
-    has_documents_config, documents_config, last_scanned_at, last_processed_at, created_at, updated_at
+    has_documents_config, documents_config, last_scanned_at, last_processed_at, created_at, updated_at,
+    github_id, deleted_at, owner
 )
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
 ON CONFLICT (full_name)
 DO UPDATE SET
     name = EXCLUDED.name,
+    owner = EXCLUDED.owner,
     description = EXCLUDED.description,
     default_branch = EXCLUDED.default_branch,
     is_private = EXCLUDED.is_private,
@@ -116,7 +319,9 @@ DO UPDATE SET
     documents_config = EXCLUDED.documents_config,
     last_scanned_at = EXCLUDED.last_scanned_at,
     last_processed_at = EXCLUDED.last_processed_at,
-    updated_at = EXCLUDED.updated_at
+    updated_at = EXCLUDED.updated_at,
+    github_id = COALESCE(EXCLUDED.github_id, repositories.github_id),
+    deleted_at = EXCLUDED.deleted_at
 RETURNING *
             "#,
             repo.id,
@@ -132,7 +337,10 @@ RETURNING *
             repo.last_scanned_at,
             repo.last_processed_at,
             repo.created_at,
-            repo.updated_at
+            repo.updated_at,
+            repo.github_id,
+            repo.deleted_at,
+            repo.owner
         )
             .fetch_one(&self.pool)
             .await?;
@@ -156,10 +364,115 @@ RETURNING *
         Ok(result)
     }
 
+    pub async fn find_repository_by_full_name(
+        &self,
+        full_name: &str,
+    ) -> Result<Option<Repository>, DatabaseError> {
+        let result = sqlx::query_as!(
+            Repository,
+            "SELECT * FROM repositories WHERE full_name = $1",
+            full_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Find a documented repository of one of `organizations` by its `owner/name` or, for a
+    /// bare name, in the first organization that has one by that name.
+    pub async fn find_documented_repository(
+        &self,
+        organizations: &[String],
+        repository: &str,
+    ) -> Result<Option<Repository>, DatabaseError> {
+        let organizations: Vec<String> = organizations
+            .iter()
+            .map(|organization| organization.to_lowercase())
+            .collect();
+
+        let result = sqlx::query_as!(
+            Repository,
+            r#"
+            SELECT * FROM repositories
+            WHERE has_documents_config = true AND deleted_at IS NULL
+            AND LOWER(owner) = ANY($2::TEXT[])
+            AND (LOWER(full_name) = LOWER($1) OR name = $1)
+            ORDER BY array_position($2::TEXT[], LOWER(owner))
+            LIMIT 1
+            "#,
+            repository,
+            &organizations
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Find the row for a GitHub repository, preferring a match on its GitHub id over one on
+    /// its (possibly outdated) full name.
+    pub async fn find_repository_by_github_id_or_full_name(
+        &self,
+        github_id: Option<i64>,
+        full_name: &str,
+    ) -> Result<Option<Repository>, DatabaseError> {
+        let result = sqlx::query_as!(
+            Repository,
+            r#"
+            SELECT * FROM repositories
+            WHERE github_id = $1 OR full_name = $2
+            ORDER BY (github_id IS NOT DISTINCT FROM $1) DESC
+            LIMIT 1
+            "#,
+            github_id,
+            full_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Mark a deleted repository and all of its documents as deleted.
+    ///
+    /// Returns the number of documents that were tombstoned.
+    pub async fn tombstone_repository(&self, repository_id: Uuid) -> Result<u64, DatabaseError> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "UPDATE repositories SET deleted_at = $2, updated_at = $2 WHERE id = $1",
+            repository_id,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE documents SET deleted_at = $2, updated_at = $2
+            WHERE repository_id = $1 AND deleted_at IS NULL
+            "#,
+            repository_id,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn list_repositories_with_documents(&self) -> Result<Vec<Repository>, DatabaseError> {
         let repos = sqlx::query_as!(
             Repository,
-            "SELECT * FROM repositories WHERE has_documents_config = true ORDER BY name"
+            r#"
+            SELECT * FROM repositories
+            WHERE has_documents_config = true AND deleted_at IS NULL
+            ORDER BY name
+            "#
         )
         .fetch_all(&self.pool)
         .await?;
@@ -167,6 +480,44 @@ RETURNING *
         Ok(repos)
     }
 
+    /// A page of repositories with a `documents.toml`, optionally only those updated at or
+    /// after `updated_since`, along with the total number of matching repositories.
+    pub async fn list_repositories_page(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Repository>, i64), DatabaseError> {
+        let repos = sqlx::query_as!(
+            Repository,
+            r#"
+            SELECT * FROM repositories
+            WHERE has_documents_config = true AND deleted_at IS NULL
+            AND ($1::TIMESTAMPTZ IS NULL OR updated_at >= $1)
+            ORDER BY name, full_name
+            LIMIT $2 OFFSET $3
+            "#,
+            updated_since,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM repositories
+            WHERE has_documents_config = true AND deleted_at IS NULL
+            AND ($1::TIMESTAMPTZ IS NULL OR updated_at >= $1)
+            "#,
+            updated_since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((repos, total))
+    }
+
     // Document operations
     pub async fn upsert_document(&self, doc: &Document) -> Result<Document, DatabaseError> {
         let result = sqlx::query_as!(
@@ -174,9 +525,10 @@ RETURNING *
             r#"
 INSERT INTO documents (
     id, repository_id, file_path, title, content, content_hash, metadata,
-    file_size, last_modified_at, created_at, updated_at
+    file_size, last_modified_at, created_at, updated_at, deleted_at, headings, tags,
+    last_commit_sha, last_author, contributors
 )
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
 ON CONFLICT (repository_id, file_path)
 DO UPDATE SET
     title = EXCLUDED.title,
@@ -185,8 +537,17 @@ DO UPDATE SET
     metadata = EXCLUDED.metadata,
     file_size = EXCLUDED.file_size,
     last_modified_at = EXCLUDED.last_modified_at,
-    updated_at = EXCLUDED.updated_at
-RETURNING *
+    updated_at = EXCLUDED.updated_at,
+    deleted_at = EXCLUDED.deleted_at,
+    headings = EXCLUDED.headings,
+    tags = EXCLUDED.tags,
+    last_commit_sha = EXCLUDED.last_commit_sha,
+    last_author = EXCLUDED.last_author,
+    contributors = EXCLUDED.contributors
+RETURNING
+    id, repository_id, file_path, title, content, content_hash, metadata, file_size,
+    last_modified_at, created_at, updated_at, deleted_at, headings, tags,
+    last_commit_sha, last_author, contributors
             "#,
             doc.id,
             doc.repository_id,
@@ -198,130 +559,965 @@ RETURNING *
             doc.file_size,
             doc.last_modified_at,
             doc.created_at,
-            doc.updated_at
+            doc.updated_at,
+            doc.deleted_at,
+            doc.headings,
+            &doc.tags,
+            doc.last_commit_sha.as_deref(),
+            doc.last_author.as_deref(),
+            doc.contributors
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn get_documents_by_repository(
+        &self,
+        repository_id: Uuid,
+    ) -> Result<Vec<Document>, DatabaseError> {
+        let docs = sqlx::query_as!(
+            Document,
+            r#"
+            SELECT
+                id, repository_id, file_path, title, content, content_hash, metadata, file_size,
+                last_modified_at, created_at, updated_at, deleted_at, headings, tags,
+                last_commit_sha, last_author, contributors
+            FROM documents
+            WHERE repository_id = $1 AND deleted_at IS NULL
+            ORDER BY file_path
+            "#,
+            repository_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(docs)
+    }
+
+    /// A page of a repository's documents, optionally only those updated at or after
+    /// `updated_since`, along with the total number of matching documents.
+    pub async fn list_documents_page(
+        &self,
+        repository_id: Uuid,
+        updated_since: Option<DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Document>, i64), DatabaseError> {
+        let docs = sqlx::query_as!(
+            Document,
+            r#"
+            SELECT
+                id, repository_id, file_path, title, content, content_hash, metadata, file_size,
+                last_modified_at, created_at, updated_at, deleted_at, headings, tags,
+                last_commit_sha, last_author, contributors
+            FROM documents
+            WHERE repository_id = $1 AND deleted_at IS NULL
+            AND ($2::TIMESTAMPTZ IS NULL OR updated_at >= $2)
+            ORDER BY file_path
+            LIMIT $3 OFFSET $4
+            "#,
+            repository_id,
+            updated_since,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM documents
+            WHERE repository_id = $1 AND deleted_at IS NULL
+            AND ($2::TIMESTAMPTZ IS NULL OR updated_at >= $2)
+            "#,
+            repository_id,
+            updated_since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((docs, total))
+    }
+
+    /// Full-text search over title, headings and content, ranked by relevance.
+    ///
+    /// `query` uses web search syntax (`"exact phrase"`, `or`, `-excluded`). Results name
+    /// their repository as `owner/name` and can be limited to one repository, by `owner/name`
+    /// or by short name, and to documents that have all of the given tags.
+    pub async fn search_documents(
+        &self,
+        query: &str,
+        repository: Option<&str>,
+        tags: &[String],
+        limit: i64,
+    ) -> Result<Vec<SearchResult>, DatabaseError> {
+        let mut results = sqlx::query_as!(
+            SearchResult,
+            r#"
+            SELECT
+                r.full_name AS repository,
+                d.file_path,
+                d.title,
+                d.tags,
+                ts_rank(d.search_vector, q) AS "rank!",
+                ts_headline(
+                    'english', translate(d.content, E'\x02\x03', ''), q,
+                    E'StartSel="\x02", StopSel="\x03", MaxFragments=2, MaxWords=30, MinWords=10'
+                ) AS "snippet!",
+                d.updated_at
+            FROM documents d
+            JOIN repositories r ON r.id = d.repository_id
+            CROSS JOIN websearch_to_tsquery('english', $1) q
+            WHERE d.search_vector @@ q
+            AND d.deleted_at IS NULL AND r.deleted_at IS NULL
+            AND ($2::VARCHAR IS NULL OR LOWER(r.full_name) = LOWER($2) OR r.name = $2)
+            AND (cardinality($3::TEXT[]) = 0 OR d.tags @> $3)
+            ORDER BY 5 DESC, d.updated_at DESC
+            LIMIT $4
+            "#,
+            query,
+            repository,
+            tags,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for result in &mut results {
+            result.snippet = highlight_snippet(&result.snippet);
+        }
+
+        Ok(results)
+    }
+
+    pub async fn get_document_by_path(
+        &self,
+        repository_id: Uuid,
+        file_path: &str,
+    ) -> Result<Document, DatabaseError> {
+        let result = sqlx::query_as!(
+            Document,
+            r#"
+            SELECT
+                id, repository_id, file_path, title, content, content_hash, metadata, file_size,
+                last_modified_at, created_at, updated_at, deleted_at, headings, tags,
+                last_commit_sha, last_author, contributors
+            FROM documents
+            WHERE repository_id = $1 AND file_path = $2 AND deleted_at IS NULL
+            "#,
+            repository_id,
+            file_path
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|_| DatabaseError::DocumentNotFound(format!("{}:{}", repository_id, file_path)))?;
+
+        Ok(result)
+    }
+
+    pub async fn delete_document_by_path(
+        &self,
+        repository_id: Uuid,
+        file_path: &str,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "DELETE FROM documents WHERE repository_id = $1 AND file_path = $2",
+            repository_id,
+            file_path
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete every document of a repository whose path is not in `keep_paths`.
+    pub async fn delete_documents_except(
+        &self,
+        repository_id: Uuid,
+        keep_paths: &[String],
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query!(
+            "DELETE FROM documents WHERE repository_id = $1 AND NOT (file_path = ANY($2))",
+            repository_id,
+            keep_paths
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Processing job operations
+    pub async fn create_processing_job(
+        &self,
+        job: &ProcessingJob,
+    ) -> Result<ProcessingJob, DatabaseError> {
+        insert_processing_job(&self.pool, job).await
+    }
+
+    /// Record how a running job ended.
+    ///
+    /// Returns `false`, without changing the job, when it is no longer running because it
+    /// was cancelled or taken over by another worker while it ran.
+    pub async fn update_job_status(
+        &self,
+        job_id: Uuid,
+        status: &str,
+        error_message: Option<&str>,
+    ) -> Result<bool, DatabaseError> {
+        let now = Utc::now();
+        let completed_at = if matches!(status, "completed" | "failed" | "dead") {
+            Some(now)
+        } else {
+            None
+        };
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE processing_jobs
+            SET status = $2, error_message = $3, completed_at = $4, updated_at = $5
+            WHERE id = $1 AND status = 'running'
+            "#,
+            job_id,
+            status,
+            error_message,
+            completed_at,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The job a schedule queued most recently, if it is still pending or running.
+    pub async fn find_active_scheduled_job(
+        &self,
+        schedule_name: &str,
+    ) -> Result<Option<ProcessingJob>, DatabaseError> {
+        let job = sqlx::query_as!(
+            ProcessingJob,
+            r#"
+            SELECT processing_jobs.* FROM processing_jobs
+            JOIN schedule_runs ON schedule_runs.job_id = processing_jobs.id
+            WHERE schedule_runs.schedule_name = $1
+                AND processing_jobs.status IN ('pending', 'running')
+            ORDER BY schedule_runs.scheduled_for DESC
+            LIMIT 1
+            "#,
+            schedule_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    pub async fn get_active_jobs(&self) -> Result<Vec<ProcessingJob>, DatabaseError> {
+        let jobs = sqlx::query_as!(
+            ProcessingJob,
+            "SELECT * FROM processing_jobs WHERE status IN ('pending', 'running') ORDER BY created_at"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Claim the pending job that became due first, mark it as running and count the
+    /// attempt.
+    ///
+    /// A running job whose worker has not sent a heartbeat for `lease` is claimed again:
+    /// its worker crashed or was killed, and the abandoned attempt stays counted.
+    ///
+    /// `FOR UPDATE SKIP LOCKED` makes concurrent workers skip rows another worker is
+    /// claiming, so every job is handed to exactly one worker.
+    pub async fn claim_next_job(
+        &self,
+        lease: std::time::Duration,
+    ) -> Result<Option<ProcessingJob>, DatabaseError> {
+        let now = Utc::now();
+        let expired = chrono::Duration::from_std(lease)
+            .ok()
+            .and_then(|lease| now.checked_sub_signed(lease))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let job = sqlx::query_as!(
+            ProcessingJob,
+            r#"
+            UPDATE processing_jobs
+            SET status = 'running', started_at = $1, heartbeat_at = $1, updated_at = $1,
+                attempts = attempts + 1
+            WHERE id = (
+                SELECT id FROM processing_jobs
+                WHERE (status = 'pending' AND run_after <= $1)
+                    OR (status = 'running' AND COALESCE(heartbeat_at, started_at, updated_at) < $2)
+                ORDER BY run_after, created_at
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING *
+            "#,
+            now,
+            expired
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Record that the worker running a job is still alive, extending its lease.
+    pub async fn heartbeat_job(&self, job_id: Uuid) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "UPDATE processing_jobs SET heartbeat_at = NOW() WHERE id = $1 AND status = 'running'",
+            job_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a failed job back in the queue to be retried at `run_after`.
+    ///
+    /// Returns `false` when the job is no longer running, like [`Self::update_job_status`].
+    pub async fn retry_job(
+        &self,
+        job_id: Uuid,
+        error_message: &str,
+        run_after: DateTime<Utc>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE processing_jobs
+            SET status = 'pending', error_message = $2, run_after = $3, started_at = NULL,
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+            job_id,
+            error_message,
+            run_after
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put a job that could not run back in the queue for `run_after` without counting
+    /// the attempt, e.g. when the GitHub rate limit ran out.
+    ///
+    /// Returns `false` when the job is no longer running, like [`Self::update_job_status`].
+    pub async fn reschedule_job(
+        &self,
+        job_id: Uuid,
+        error_message: &str,
+        run_after: DateTime<Utc>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE processing_jobs
+            SET status = 'pending', error_message = $2, run_after = $3, started_at = NULL,
+                attempts = GREATEST(attempts - 1, 0), updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+            job_id,
+            error_message,
+            run_after
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_processing_job(&self, job_id: Uuid) -> Result<ProcessingJob, DatabaseError> {
+        let job = sqlx::query_as!(
+            ProcessingJob,
+            "SELECT * FROM processing_jobs WHERE id = $1",
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        job.ok_or(DatabaseError::JobNotFound(job_id))
+    }
+
+    /// List the most recently updated jobs, optionally only those with one status.
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ProcessingJob>, DatabaseError> {
+        let jobs = sqlx::query_as!(
+            ProcessingJob,
+            r#"
+            SELECT * FROM processing_jobs
+            WHERE ($1::TEXT IS NULL OR status = $1)
+            ORDER BY updated_at DESC
+            LIMIT $2
+            "#,
+            status,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Number of jobs with each status.
+    pub async fn count_jobs_by_status(&self) -> Result<Vec<(String, i64)>, DatabaseError> {
+        let counts = sqlx::query!(
+            r#"SELECT status, COUNT(*) AS "count!" FROM processing_jobs GROUP BY status"#
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.status, row.count))
+        .collect();
+
+        Ok(counts)
+    }
+
+    /// Give a dead job a fresh set of attempts. Returns `None` if the job is not dead.
+    pub async fn requeue_dead_job(
+        &self,
+        job_id: Uuid,
+    ) -> Result<Option<ProcessingJob>, DatabaseError> {
+        let job = sqlx::query_as!(
+            ProcessingJob,
+            r#"
+            UPDATE processing_jobs
+            SET status = 'pending', attempts = 0, run_after = NOW(), started_at = NULL,
+                completed_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'dead'
+            RETURNING *
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Give every dead job, optionally only those of one type, a fresh set of attempts.
+    pub async fn requeue_dead_jobs(&self, job_type: Option<&str>) -> Result<u64, DatabaseError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE processing_jobs
+            SET status = 'pending', attempts = 0, run_after = NOW(), started_at = NULL,
+                completed_at = NULL, updated_at = NOW()
+            WHERE status = 'dead' AND ($1::TEXT IS NULL OR job_type = $1)
+            "#,
+            job_type
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Cancel a job that has not finished. A running job stops at its next checkpoint.
+    /// Returns `None` if the job already finished.
+    pub async fn cancel_job(&self, job_id: Uuid) -> Result<Option<ProcessingJob>, DatabaseError> {
+        let job = sqlx::query_as!(
+            ProcessingJob,
+            r#"
+            UPDATE processing_jobs
+            SET status = 'cancelled', completed_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status IN ('pending', 'running')
+            RETURNING *
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    pub async fn record_job_event(
+        &self,
+        job_id: Uuid,
+        repository: &str,
+        status: &str,
+        message: Option<&str>,
+    ) -> Result<JobEvent, DatabaseError> {
+        let event = sqlx::query_as!(
+            JobEvent,
+            r#"
+            INSERT INTO job_events (job_id, repository, status, message)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            job_id,
+            repository,
+            status,
+            message
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    /// List the events of a job recorded after the event with id `after_id`, oldest first.
+    pub async fn list_job_events(
+        &self,
+        job_id: Uuid,
+        after_id: i64,
+    ) -> Result<Vec<JobEvent>, DatabaseError> {
+        let events = sqlx::query_as!(
+            JobEvent,
+            "SELECT * FROM job_events WHERE job_id = $1 AND id > $2 ORDER BY id",
+            job_id,
+            after_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    // Webhook delivery operations
+
+    /// Record a webhook delivery. Returns `None` if a delivery with the same
+    /// `delivery_id` was already recorded.
+    pub async fn record_webhook_delivery(
+        &self,
+        delivery_id: &str,
+        event_type: &str,
+        payload: &str,
+    ) -> Result<Option<WebhookDelivery>, DatabaseError> {
+        let result = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            INSERT INTO webhook_deliveries (id, delivery_id, event_type, payload, received_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (delivery_id) DO NOTHING
+            RETURNING *
+            "#,
+            Uuid::new_v4(),
+            delivery_id,
+            event_type,
+            payload,
+            Utc::now()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Record a delivery and queue `job` to process it, both or neither.
+    ///
+    /// Returns `None` when the delivery was already recorded. A delivery whose job could
+    /// not be queued is not recorded either, so GitHub's redelivery is not mistaken for a
+    /// duplicate.
+    pub async fn queue_webhook_delivery(
+        &self,
+        delivery_id: &str,
+        event_type: &str,
+        payload: &str,
+        job: &ProcessingJob,
+    ) -> Result<Option<(WebhookDelivery, ProcessingJob)>, DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            INSERT INTO webhook_deliveries (id, delivery_id, event_type, payload, received_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (delivery_id) DO NOTHING
+            RETURNING *
+            "#,
+            Uuid::new_v4(),
+            delivery_id,
+            event_type,
+            payload,
+            Utc::now()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(delivery) = delivery else {
+            return Ok(None);
+        };
+
+        let job = insert_processing_job(&mut *tx, job).await?;
+        tx.commit().await?;
+
+        Ok(Some((delivery, job)))
+    }
+
+    pub async fn update_webhook_delivery_outcome(
+        &self,
+        delivery_id: &str,
+        outcome: &str,
+        outcome_message: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET outcome = $2, outcome_message = $3, processed_at = $4
+            WHERE delivery_id = $1
+            "#,
+            delivery_id,
+            outcome,
+            outcome_message,
+            Utc::now()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_webhook_delivery(
+        &self,
+        delivery_id: &str,
+    ) -> Result<WebhookDelivery, DatabaseError> {
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            "SELECT * FROM webhook_deliveries WHERE delivery_id = $1",
+            delivery_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        delivery.ok_or_else(|| DatabaseError::WebhookDeliveryNotFound(delivery_id.to_string()))
+    }
+
+    /// List the most recent deliveries, optionally only those of one event type.
+    pub async fn list_webhook_deliveries(
+        &self,
+        event_type: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, DatabaseError> {
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT * FROM webhook_deliveries
+            WHERE $1::VARCHAR IS NULL OR event_type = $1
+            ORDER BY received_at DESC
+            LIMIT $2
+            "#,
+            event_type,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    pub async fn increment_webhook_delivery_replays(
+        &self,
+        delivery_id: &str,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "UPDATE webhook_deliveries SET replay_count = replay_count + 1 WHERE delivery_id = $1",
+            delivery_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Schedule run operations
+
+    /// Record that a schedule fires for the occurrence at `scheduled_for`.
+    ///
+    /// Returns `None` when another instance holds the schedule's advisory lock or has
+    /// already recorded this occurrence, in which case the caller must not fire it.
+    pub async fn start_schedule_run(
+        &self,
+        schedule_name: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Option<ScheduleRun>, DatabaseError> {
+        let mut tx = self.pool.begin().await?;
+
+        let locked = sqlx::query_scalar!(
+            "SELECT pg_try_advisory_xact_lock(hashtext($1))",
+            format!("schedule:{}", schedule_name)
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        if !locked {
+            return Ok(None);
+        }
+
+        let run = sqlx::query_as!(
+            ScheduleRun,
+            r#"
+            INSERT INTO schedule_runs (schedule_name, scheduled_for)
+            VALUES ($1, $2)
+            ON CONFLICT (schedule_name, scheduled_for) DO NOTHING
+            RETURNING *
+            "#,
+            schedule_name,
+            scheduled_for
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// Record what an occurrence of a schedule queued: the job, or the error that kept it
+    /// from being queued.
+    pub async fn finish_schedule_run(
+        &self,
+        run_id: Uuid,
+        job_id: Option<Uuid>,
+        error_message: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        let jobs_enqueued = i32::from(job_id.is_some());
+        sqlx::query!(
+            r#"
+            UPDATE schedule_runs
+            SET completed_at = NOW(), jobs_enqueued = $2, error_message = $3, job_id = $4
+            WHERE id = $1
+            "#,
+            run_id,
+            jobs_enqueued,
+            error_message,
+            job_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record that an occurrence of a schedule queued nothing, and why.
+    pub async fn skip_schedule_run(&self, run_id: Uuid, reason: &str) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            r#"
+            UPDATE schedule_runs
+            SET completed_at = NOW(), jobs_enqueued = 0, skipped_reason = $2
+            WHERE id = $1
+            "#,
+            run_id,
+            reason
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// List the most recent schedule runs, optionally only those of one schedule.
+    pub async fn list_schedule_runs(
+        &self,
+        schedule_name: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ScheduleRun>, DatabaseError> {
+        let runs = sqlx::query_as!(
+            ScheduleRun,
+            r#"
+            SELECT * FROM schedule_runs
+            WHERE ($1::TEXT IS NULL OR schedule_name = $1)
+            ORDER BY scheduled_for DESC
+            LIMIT $2
+            "#,
+            schedule_name,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    // API key operations
+
+    pub async fn create_api_key(
+        &self,
+        name: &str,
+        key_prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+    ) -> Result<ApiKey, DatabaseError> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (name, key_prefix, key_hash, scopes)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            name,
+            key_prefix,
+            key_hash,
+            scopes
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(result)
+        Ok(key)
     }
 
-    pub async fn get_documents_by_repository(
-        &self,
-        repository_id: Uuid,
-    ) -> Result<Vec<Document>, DatabaseError> {
-        let docs = sqlx::query_as!(
-            Document,
-            "SELECT * FROM documents WHERE repository_id = $1 ORDER BY file_path",
-            repository_id
+    /// Find the unrevoked API key with the given hash and record that it was used.
+    pub async fn use_api_key(&self, key_hash: &str) -> Result<Option<ApiKey>, DatabaseError> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_keys
+            SET last_used_at = NOW()
+            WHERE key_hash = $1 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+            key_hash
         )
-        .fetch_all(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(docs)
+        Ok(key)
     }
 
-    pub async fn get_document_by_path(
-        &self,
-        repository_id: Uuid,
-        file_path: &str,
-    ) -> Result<Document, DatabaseError> {
-        let result = sqlx::query_as!(
-            Document,
-            "SELECT * FROM documents WHERE repository_id = $1 AND file_path = $2",
-            repository_id,
-            file_path
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>, DatabaseError> {
+        let keys = sqlx::query_as!(ApiKey, "SELECT * FROM api_keys ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(keys)
+    }
+
+    /// Revoke an API key. Returns `None` if no unrevoked key has the given id.
+    pub async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DatabaseError> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_keys
+            SET revoked_at = NOW()
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+            id
         )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|_| DatabaseError::DocumentNotFound(format!("{}:{}", repository_id, file_path)))?;
+        .fetch_optional(&self.pool)
+        .await?;
 
-        Ok(result)
+        Ok(key)
     }
 
-    // Processing job operations
-    pub async fn create_processing_job(
+    // Site session operations
+
+    /// Store a session of the documentation site, removing sessions that have expired.
+    pub async fn create_site_session(
         &self,
-        job: &ProcessingJob,
-    ) -> Result<ProcessingJob, DatabaseError> {
-        let result = sqlx::query_as!(
-            ProcessingJob,
+        session_hash: &str,
+        key_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<SiteSession, DatabaseError> {
+        let session = sqlx::query_as!(
+            SiteSession,
             r#"
-            INSERT INTO processing_jobs (
-            id, repository_id, job_type, status, parameters, error_message,
-            started_at, completed_at, created_at, updated_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            WITH expired AS (DELETE FROM site_sessions WHERE expires_at <= NOW())
+            INSERT INTO site_sessions (session_hash, key_hash, expires_at)
+            VALUES ($1, $2, $3)
             RETURNING *
             "#,
-            job.id,
-            job.repository_id,
-            job.job_type,
-            job.status,
-            job.parameters.as_deref(),
-            job.error_message.as_deref(),
-            job.started_at,
-            job.completed_at,
-            job.created_at,
-            job.updated_at
+            session_hash,
+            key_hash,
+            expires_at
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(result)
+        Ok(session)
     }
 
-    pub async fn update_job_status(
+    /// The unexpired session with the given hash.
+    pub async fn find_site_session(
         &self,
-        job_id: Uuid,
-        status: &str,
-        error_message: Option<&str>,
-    ) -> Result<(), DatabaseError> {
-        let now = Utc::now();
-        let completed_at = if status == "completed" || status == "failed" {
-            Some(now)
-        } else {
-            None
-        };
-
-        sqlx::query!(
-            r#"
-            UPDATE processing_jobs
-            SET status = $2, error_message = $3, completed_at = $4, updated_at = $5
-            WHERE id = $1
-            "#,
-            job_id,
-            status,
-            error_message,
-            completed_at,
-            now
+        session_hash: &str,
+    ) -> Result<Option<SiteSession>, DatabaseError> {
+        let session = sqlx::query_as!(
+            SiteSession,
+            "SELECT * FROM site_sessions WHERE session_hash = $1 AND expires_at > NOW()",
+            session_hash
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(session)
     }
 
-    pub async fn get_active_jobs(&self) -> Result<Vec<ProcessingJob>, DatabaseError> {
-        let jobs = sqlx::query_as!(
-            ProcessingJob,
-            "SELECT * FROM processing_jobs WHERE status IN ('pending', 'running') ORDER BY created_at"
+    /// End a session. Returns whether there was one with the given hash.
+    pub async fn delete_site_session(&self, session_hash: &str) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "DELETE FROM site_sessions WHERE session_hash = $1",
+            session_hash
         )
-        .fetch_all(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        Ok(jobs)
+        Ok(result.rows_affected() > 0)
     }
 }
 
+/// Escape a `ts_headline` excerpt for HTML and turn its match markers into `<mark>` tags.
+/// The markers are the STX and ETX control characters, which `search_documents` strips
+/// from the content, so nothing a document contains is taken for one.
+fn highlight_snippet(snippet: &str) -> String {
+    html_escape::encode_text(snippet)
+        .replace('\u{2}', "<mark>")
+        .replace('\u{3}', "</mark>")
+}
+
+/// Insert a job through `executor`, so it can be part of a larger transaction.
+async fn insert_processing_job<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    job: &ProcessingJob,
+) -> Result<ProcessingJob, DatabaseError> {
+    let result = sqlx::query_as!(
+        ProcessingJob,
+        r#"
+        INSERT INTO processing_jobs (
+        id, repository_id, job_type, status, parameters, error_message,
+        started_at, completed_at, created_at, updated_at, attempts, run_after
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING *
+        "#,
+        job.id,
+        job.repository_id,
+        job.job_type,
+        job.status,
+        job.parameters.as_deref(),
+        job.error_message.as_deref(),
+        job.started_at,
+        job.completed_at,
+        job.created_at,
+        job.updated_at,
+        job.attempts,
+        job.run_after
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::jobs::JOB_LEASE;
 
     #[sqlx::test]
     async fn test_database_health_check(pool: PgPool) {
         let db = Database { pool };
         assert!(db.health_check().await.is_ok());
+        assert!(db.pending_migrations().await.unwrap().is_empty());
     }
 
     #[sqlx::test]
@@ -332,6 +1528,7 @@ mod tests {
             id: Uuid::new_v4(),
             name: "test-repo".to_string(),
             full_name: "test-org/test-repo".to_string(),
+            owner: "test-org".to_string(),
             description: Some("A test repository".to_string()),
             default_branch: "main".to_string(),
             is_private: false,
@@ -343,6 +1540,8 @@ mod tests {
             last_processed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
         };
 
         let saved_repo = db
@@ -374,6 +1573,7 @@ mod tests {
             id: Uuid::new_v4(),
             name: with_docs_name.clone(),
             full_name: format!("org/{}", with_docs_name),
+            owner: "org".to_string(),
             description: Some("Repository with documents".to_string()),
             default_branch: "main".to_string(),
             is_private: false,
@@ -385,12 +1585,15 @@ mod tests {
             last_processed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
         };
         
         let repo2 = Repository {
             id: Uuid::new_v4(),
             name: without_docs_name.clone(),
             full_name: format!("org/{}", without_docs_name),
+            owner: "org".to_string(),
             description: Some("Repository without documents".to_string()),
             default_branch: "main".to_string(),
             is_private: false,
@@ -402,6 +1605,8 @@ mod tests {
             last_processed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
         };
         
         // Insert both repositories
@@ -430,6 +1635,7 @@ mod tests {
             id: Uuid::new_v4(),
             name: "test-repo".to_string(),
             full_name: "test-org/test-repo".to_string(),
+            owner: "test-org".to_string(),
             description: Some("A test repository".to_string()),
             default_branch: "main".to_string(),
             is_private: false,
@@ -441,6 +1647,8 @@ mod tests {
             last_processed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
         };
         
         let saved_repo = db.upsert_repository(&repo).await.expect("Failed to upsert repository");
@@ -458,6 +1666,12 @@ mod tests {
             last_modified_at: Utc::now(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
+            headings: String::new(),
+            tags: vec![],
+            last_commit_sha: None,
+            last_author: None,
+            contributors: 0,
         };
         
         // Test upsert_document
@@ -485,6 +1699,12 @@ mod tests {
             last_modified_at: Utc::now(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            deleted_at: None,
+            headings: String::new(),
+            tags: vec![],
+            last_commit_sha: None,
+            last_author: None,
+            contributors: 0,
         };
         
         db.upsert_document(&doc2).await.expect("Failed to upsert second document");
@@ -498,6 +1718,28 @@ mod tests {
         // Documents should be ordered by file_path
         assert_eq!(docs[0].file_path, "docs/api.md");
         assert_eq!(docs[1].file_path, "docs/readme.md");
+
+        // Test delete_documents_except
+        let deleted = db
+            .delete_documents_except(saved_repo.id, &["docs/readme.md".to_string()])
+            .await
+            .expect("Failed to delete unreferenced documents");
+        assert_eq!(deleted, 1);
+
+        // Test delete_document_by_path
+        assert!(
+            db.delete_document_by_path(saved_repo.id, "docs/readme.md")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.delete_document_by_path(saved_repo.id, "docs/readme.md")
+                .await
+                .unwrap()
+        );
+
+        let docs = db.get_documents_by_repository(saved_repo.id).await.unwrap();
+        assert!(docs.is_empty());
     }
     
     #[sqlx::test]
@@ -509,6 +1751,7 @@ mod tests {
             id: Uuid::new_v4(),
             name: "test-repo".to_string(),
             full_name: "test-org/test-repo".to_string(),
+            owner: "test-org".to_string(),
             description: Some("A test repository".to_string()),
             default_branch: "main".to_string(),
             is_private: false,
@@ -520,6 +1763,8 @@ mod tests {
             last_processed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
         };
         
         let saved_repo = db.upsert_repository(&repo).await.expect("Failed to upsert repository");
@@ -536,18 +1781,23 @@ mod tests {
             completed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            attempts: 0,
+            run_after: Utc::now(),
+            heartbeat_at: None,
         };
         
         // Test create_processing_job
         let saved_job = db.create_processing_job(&job).await.expect("Failed to create processing job");
         assert_eq!(saved_job.job_type, job.job_type);
         assert_eq!(saved_job.status, "pending");
-        
-        // Test update_job_status - mark as running
-        db.update_job_status(saved_job.id, "running", None)
+
+        // Mark as running
+        let claimed = db
+            .claim_next_job(JOB_LEASE)
             .await
-            .expect("Failed to update job status to running");
-        
+            .expect("Failed to claim job");
+        assert_eq!(claimed.map(|job| job.id), Some(saved_job.id));
+
         // Create another pending job
         let job2 = ProcessingJob {
             id: Uuid::new_v4(),
@@ -560,6 +1810,9 @@ mod tests {
             completed_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            attempts: 0,
+            run_after: Utc::now(),
+            heartbeat_at: None,
         };
         
         db.create_processing_job(&job2).await.expect("Failed to create second processing job");
@@ -574,12 +1827,704 @@ mod tests {
             .expect("Failed to update job status to completed");
         
         // Test update_job_status - mark as failed with error message
-        db.update_job_status(job2.id, "failed", Some("Test error message"))
+        assert!(
+            !db.update_job_status(job2.id, "failed", Some("Test error message"))
+                .await
+                .expect("Failed to update job status to failed"),
+            "Only running jobs are finished"
+        );
+        db.claim_next_job(JOB_LEASE)
             .await
-            .expect("Failed to update job status to failed");
-        
+            .expect("Failed to claim job");
+        assert!(
+            db.update_job_status(job2.id, "failed", Some("Test error message"))
+                .await
+                .expect("Failed to update job status to failed")
+        );
+
         // Test get_active_jobs again - should be empty now
         let active_jobs = db.get_active_jobs().await.expect("Failed to get active jobs");
         assert_eq!(active_jobs.len(), 0);
     }
+
+    #[sqlx::test]
+    async fn test_queue_webhook_delivery(pool: PgPool) {
+        let db = Database { pool };
+        let job = crate::jobs::Job::WebhookDelivery {
+            delivery_id: "delivery-1".to_string(),
+        }
+        .pending_row(None);
+
+        let (delivery, queued) = db
+            .queue_webhook_delivery("delivery-1", "push", "{}", &job)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.delivery_id, "delivery-1");
+        assert_eq!(queued.id, job.id);
+        assert!(
+            db.queue_webhook_delivery("delivery-1", "push", "{}", &job)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // A job that cannot be queued leaves the delivery unrecorded
+        assert!(
+            db.queue_webhook_delivery("delivery-2", "push", "{}", &job)
+                .await
+                .is_err()
+        );
+        assert!(db.get_webhook_delivery("delivery-2").await.is_err());
+        let retried = crate::jobs::Job::WebhookDelivery {
+            delivery_id: "delivery-2".to_string(),
+        }
+        .pending_row(None);
+        assert!(
+            db.queue_webhook_delivery("delivery-2", "push", "{}", &retried)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_webhook_delivery_operations(pool: PgPool) {
+        let db = Database { pool };
+
+        let recorded = db
+            .record_webhook_delivery("delivery-1", "push", r#"{"ref":"refs/heads/main"}"#)
+            .await
+            .expect("Failed to record delivery");
+        assert!(recorded.is_some());
+        assert_eq!(recorded.unwrap().outcome, "received");
+
+        let duplicate = db
+            .record_webhook_delivery("delivery-1", "push", "{}")
+            .await
+            .expect("Failed to record duplicate delivery");
+        assert!(duplicate.is_none());
+
+        db.record_webhook_delivery("delivery-2", "ping", "{}")
+            .await
+            .expect("Failed to record delivery");
+
+        db.update_webhook_delivery_outcome("delivery-1", "processed", Some("Updated 1 document"))
+            .await
+            .expect("Failed to update outcome");
+        db.increment_webhook_delivery_replays("delivery-1")
+            .await
+            .expect("Failed to count replay");
+
+        let delivery = db
+            .get_webhook_delivery("delivery-1")
+            .await
+            .expect("Failed to get delivery");
+        assert_eq!(delivery.outcome, "processed");
+        assert_eq!(delivery.payload, r#"{"ref":"refs/heads/main"}"#);
+        assert_eq!(delivery.replay_count, 1);
+        assert!(delivery.processed_at.is_some());
+
+        assert_eq!(db.list_webhook_deliveries(None, 10).await.unwrap().len(), 2);
+        let pushes = db.list_webhook_deliveries(Some("push"), 10).await.unwrap();
+        assert_eq!(pushes.len(), 1);
+        assert_eq!(pushes[0].delivery_id, "delivery-1");
+
+        assert!(matches!(
+            db.get_webhook_delivery("missing").await,
+            Err(DatabaseError::WebhookDeliveryNotFound(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_find_documented_repository_across_organizations(pool: PgPool) {
+        let db = Database { pool };
+
+        for owner in ["org-a", "org-b", "org-c"] {
+            let repo = Repository {
+                id: Uuid::new_v4(),
+                name: "docs".to_string(),
+                full_name: format!("{}/docs", owner),
+                owner: owner.to_string(),
+                description: None,
+                default_branch: "main".to_string(),
+                is_private: false,
+                is_archived: false,
+                is_fork: false,
+                has_documents_config: true,
+                documents_config: None,
+                last_scanned_at: None,
+                last_processed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                github_id: None,
+                deleted_at: None,
+            };
+            db.upsert_repository(&repo)
+                .await
+                .expect("Failed to upsert repository");
+        }
+
+        let organizations = vec!["Org-B".to_string(), "org-a".to_string()];
+        let find = |repository: &'static str| {
+            let db = db.clone();
+            let organizations = organizations.clone();
+            async move {
+                db.find_documented_repository(&organizations, repository)
+                    .await
+                    .unwrap()
+                    .map(|repo| repo.full_name)
+            }
+        };
+
+        // A bare name resolves in configuration order
+        assert_eq!(find("docs").await.as_deref(), Some("org-b/docs"));
+        assert_eq!(find("org-a/docs").await.as_deref(), Some("org-a/docs"));
+        // Repositories of organizations that are not configured are not served
+        assert_eq!(find("org-c/docs").await, None);
+        assert_eq!(find("missing").await, None);
+    }
+
+    #[sqlx::test]
+    async fn test_repository_rename_and_tombstone(pool: PgPool) {
+        let db = Database { pool };
+
+        let repo = Repository {
+            id: Uuid::new_v4(),
+            name: "old-name".to_string(),
+            full_name: "test-org/old-name".to_string(),
+            owner: "test-org".to_string(),
+            description: None,
+            default_branch: "main".to_string(),
+            is_private: false,
+            is_archived: false,
+            is_fork: false,
+            has_documents_config: true,
+            documents_config: None,
+            last_scanned_at: None,
+            last_processed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            github_id: Some(42),
+            deleted_at: None,
+        };
+        let saved_repo = db
+            .upsert_repository(&repo)
+            .await
+            .expect("Failed to upsert repository");
+
+        let doc = Document {
+            id: Uuid::new_v4(),
+            repository_id: saved_repo.id,
+            file_path: "docs/readme.md".to_string(),
+            title: "README".to_string(),
+            content: "# README".to_string(),
+            content_hash: "abc123".to_string(),
+            metadata: None,
+            file_size: 8,
+            last_modified_at: Utc::now(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+            headings: String::new(),
+            tags: vec![],
+            last_commit_sha: None,
+            last_author: None,
+            contributors: 0,
+        };
+        db.upsert_document(&doc)
+            .await
+            .expect("Failed to upsert document");
+
+        // A rename arrives with a new id and full name but the same GitHub id
+        let renamed = Repository {
+            id: Uuid::new_v4(),
+            name: "new-name".to_string(),
+            full_name: "test-org/new-name".to_string(),
+            owner: "test-org".to_string(),
+            is_archived: true,
+            ..repo
+        };
+        let saved_rename = db
+            .upsert_repository(&renamed)
+            .await
+            .expect("Failed to rename repository");
+        assert_eq!(saved_rename.id, saved_repo.id);
+        assert_eq!(saved_rename.full_name, "test-org/new-name");
+        assert!(saved_rename.is_archived);
+        assert!(
+            db.find_repository_by_full_name("test-org/old-name")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let found = db
+            .find_repository_by_github_id_or_full_name(Some(42), "test-org/old-name")
+            .await
+            .unwrap()
+            .expect("Repository not found by GitHub id");
+        assert_eq!(found.id, saved_repo.id);
+        assert_eq!(
+            db.get_documents_by_repository(saved_repo.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Deleting tombstones the repository and hides its documents
+        assert_eq!(db.tombstone_repository(saved_repo.id).await.unwrap(), 1);
+        let tombstoned = db
+            .get_repository_by_full_name("test-org/new-name")
+            .await
+            .unwrap();
+        assert!(tombstoned.deleted_at.is_some());
+        assert!(
+            db.get_documents_by_repository(saved_repo.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            db.get_document_by_path(saved_repo.id, "docs/readme.md")
+                .await
+                .is_err()
+        );
+        assert!(
+            db.list_repositories_with_documents()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_highlight_snippet() {
+        assert_eq!(
+            highlight_snippet("a <b> & [[mark]] \u{2}fox\u{3}"),
+            "a &lt;b&gt; &amp; [[mark]] <mark>fox</mark>"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_search_documents(pool: PgPool) {
+        let db = Database { pool };
+
+        let mut repo_ids = Vec::new();
+        for name in ["api-repo", "guide-repo"] {
+            let repo = Repository {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                full_name: format!("test-org/{}", name),
+                owner: "test-org".to_string(),
+                description: None,
+                default_branch: "main".to_string(),
+                is_private: false,
+                is_archived: false,
+                is_fork: false,
+                has_documents_config: true,
+                documents_config: None,
+                last_scanned_at: None,
+                last_processed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                github_id: None,
+                deleted_at: None,
+            };
+            repo_ids.push(db.upsert_repository(&repo).await.unwrap().id);
+        }
+
+        let documents = [
+            (
+                repo_ids[0],
+                "docs/auth.md",
+                "Authentication",
+                "Tokens",
+                "Send a bearer token.",
+                vec!["api"],
+            ),
+            (
+                repo_ids[0],
+                "docs/errors.md",
+                "Errors",
+                "Authentication errors",
+                "A 401 means the token is wrong.",
+                vec!["api", "errors"],
+            ),
+            (
+                repo_ids[1],
+                "docs/setup.md",
+                "Setup",
+                "Install",
+                "Ask for an authentication token first. Wrap text in [[mark]] to \u{2}stress\u{3} it.",
+                vec!["guide"],
+            ),
+        ];
+        for (repository_id, path, title, headings, content, tags) in documents {
+            db.upsert_document(&Document {
+                id: Uuid::new_v4(),
+                repository_id,
+                file_path: path.to_string(),
+                title: title.to_string(),
+                content: content.to_string(),
+                content_hash: "abc123".to_string(),
+                metadata: None,
+                file_size: content.len() as i64,
+                last_modified_at: Utc::now(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                headings: headings.to_string(),
+                tags: tags.into_iter().map(String::from).collect(),
+                last_commit_sha: None,
+                last_author: None,
+                contributors: 0,
+            })
+            .await
+            .unwrap();
+        }
+
+        // Title matches rank above heading matches, which rank above body matches
+        let results = db
+            .search_documents("authentication", None, &[], 10)
+            .await
+            .unwrap();
+        let paths: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["docs/auth.md", "docs/errors.md", "docs/setup.md"]
+        );
+
+        let results = db.search_documents("token", None, &[], 10).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.snippet.contains("<mark>")));
+        let setup = results
+            .iter()
+            .find(|r| r.file_path == "docs/setup.md")
+            .unwrap();
+        assert_eq!(setup.snippet.matches("<mark>").count(), 1);
+        assert!(setup.snippet.contains("[[mark]] to stress"));
+
+        let results = db
+            .search_documents("token", Some("guide-repo"), &[], 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repository, "test-org/guide-repo");
+        let results = db
+            .search_documents("token", Some("Test-Org/guide-repo"), &[], 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let tags = vec!["api".to_string(), "errors".to_string()];
+        let results = db.search_documents("token", None, &tags, 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "docs/errors.md");
+
+        assert!(
+            db.search_documents("nonexistent", None, &[], 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_claim_next_job(pool: PgPool) {
+        let db = Database { pool };
+
+        for job_type in ["scan_organization", "process_repository"] {
+            let job = ProcessingJob {
+                id: Uuid::new_v4(),
+                repository_id: None,
+                job_type: job_type.to_string(),
+                status: "pending".to_string(),
+                parameters: None,
+                error_message: None,
+                started_at: None,
+                completed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                attempts: 0,
+                run_after: Utc::now(),
+                heartbeat_at: None,
+            };
+            db.create_processing_job(&job)
+                .await
+                .expect("Failed to create job");
+        }
+
+        // Concurrent claims never hand out the same job twice
+        let (first, second, third) = tokio::join!(
+            db.claim_next_job(JOB_LEASE),
+            db.claim_next_job(JOB_LEASE),
+            db.claim_next_job(JOB_LEASE)
+        );
+        let mut claimed: Vec<ProcessingJob> = [first, second, third]
+            .into_iter()
+            .filter_map(|job| job.unwrap())
+            .collect();
+        claimed.sort_by_key(|job| job.created_at);
+
+        assert_eq!(claimed.len(), 2);
+        assert_ne!(claimed[0].id, claimed[1].id);
+        assert!(
+            claimed
+                .iter()
+                .all(|job| job.status == "running" && job.started_at.is_some())
+        );
+        assert!(claimed.iter().all(|job| job.attempts == 1));
+        assert!(db.claim_next_job(JOB_LEASE).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_claim_jobs_with_expired_lease(pool: PgPool) {
+        let db = Database { pool };
+        let job = db
+            .create_processing_job(&ProcessingJob {
+                id: Uuid::new_v4(),
+                repository_id: None,
+                job_type: "process_repository".to_string(),
+                status: "pending".to_string(),
+                parameters: None,
+                error_message: None,
+                started_at: None,
+                completed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                attempts: 0,
+                run_after: Utc::now(),
+                heartbeat_at: None,
+            })
+            .await
+            .unwrap();
+
+        let lease = std::time::Duration::from_secs(60);
+        let claimed = db.claim_next_job(lease).await.unwrap().unwrap();
+        assert!(claimed.heartbeat_at.is_some());
+        db.heartbeat_job(job.id).await.unwrap();
+        assert!(db.claim_next_job(lease).await.unwrap().is_none());
+
+        // The worker stopped sending heartbeats
+        sqlx::query!(
+            "UPDATE processing_jobs SET heartbeat_at = NOW() - INTERVAL '2 minutes' WHERE id = $1",
+            job.id
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let reclaimed = db.claim_next_job(lease).await.unwrap().unwrap();
+        assert_eq!(reclaimed.id, job.id);
+        assert_eq!(reclaimed.attempts, 2);
+        assert!(reclaimed.heartbeat_at > claimed.heartbeat_at);
+    }
+
+    #[sqlx::test]
+    async fn test_retry_and_requeue_jobs(pool: PgPool) {
+        let db = Database { pool };
+        let job = db
+            .create_processing_job(&ProcessingJob {
+                id: Uuid::new_v4(),
+                repository_id: None,
+                job_type: "process_repository".to_string(),
+                status: "pending".to_string(),
+                parameters: None,
+                error_message: None,
+                started_at: None,
+                completed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                attempts: 0,
+                run_after: Utc::now(),
+                heartbeat_at: None,
+            })
+            .await
+            .unwrap();
+
+        // A retried job is not claimed before it is due
+        let claimed = db.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        db.retry_job(claimed.id, "boom", Utc::now() + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert!(db.claim_next_job(JOB_LEASE).await.unwrap().is_none());
+        // Only running jobs are put back in the queue
+        assert!(!db.retry_job(job.id, "boom", Utc::now()).await.unwrap());
+
+        // Rescheduling does not count the attempt
+        sqlx::query!(
+            "UPDATE processing_jobs SET run_after = NOW() WHERE id = $1",
+            job.id
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let claimed = db.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        assert_eq!(claimed.attempts, 2);
+        db.reschedule_job(job.id, "rate limited", Utc::now())
+            .await
+            .unwrap();
+        let rescheduled = db.get_processing_job(job.id).await.unwrap();
+        assert_eq!(rescheduled.status, "pending");
+        assert_eq!(rescheduled.attempts, 1);
+
+        // Only dead jobs are requeued
+        assert!(db.requeue_dead_job(job.id).await.unwrap().is_none());
+        db.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        db.update_job_status(job.id, "dead", Some("boom"))
+            .await
+            .unwrap();
+        assert_eq!(db.list_jobs(Some("dead"), 10).await.unwrap().len(), 1);
+
+        let requeued = db.requeue_dead_job(job.id).await.unwrap().unwrap();
+        assert_eq!(requeued.status, "pending");
+        assert_eq!(requeued.attempts, 0);
+        assert!(requeued.completed_at.is_none());
+
+        db.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        db.update_job_status(job.id, "dead", Some("boom"))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.requeue_dead_jobs(Some("scan_organization"))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(db.requeue_dead_jobs(None).await.unwrap(), 1);
+
+        assert!(matches!(
+            db.get_processing_job(Uuid::new_v4()).await,
+            Err(DatabaseError::JobNotFound(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_schedule_runs_fire_once(pool: PgPool) {
+        let db = Database { pool };
+        let scheduled_for = Utc::now();
+
+        let (first, second) = tokio::join!(
+            db.start_schedule_run("scan_organization", scheduled_for),
+            db.start_schedule_run("scan_organization", scheduled_for)
+        );
+        let started: Vec<ScheduleRun> = [first, second]
+            .into_iter()
+            .filter_map(|run| run.unwrap())
+            .collect();
+        assert_eq!(started.len(), 1);
+
+        // Later occurrences and other schedules are recorded separately
+        let later = scheduled_for + chrono::Duration::hours(1);
+        assert!(
+            db.start_schedule_run("scan_organization", later)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            db.start_schedule_run("process_organization", later)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        db.finish_schedule_run(started[0].id, None, Some("queue unavailable"))
+            .await
+            .unwrap();
+        let runs = db
+            .list_schedule_runs(Some("scan_organization"), 10)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].scheduled_for.timestamp(), later.timestamp());
+        assert_eq!(runs[1].jobs_enqueued, 0);
+        assert_eq!(runs[1].error_message.as_deref(), Some("queue unavailable"));
+        assert!(runs[1].completed_at.is_some());
+    }
+
+    #[sqlx::test]
+    async fn test_cancel_job_and_events(pool: PgPool) {
+        let db = Database { pool };
+        let job = db
+            .create_processing_job(&ProcessingJob {
+                id: Uuid::new_v4(),
+                repository_id: None,
+                job_type: "process_organization".to_string(),
+                status: "pending".to_string(),
+                parameters: None,
+                error_message: None,
+                started_at: None,
+                completed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                attempts: 0,
+                run_after: Utc::now(),
+                heartbeat_at: None,
+            })
+            .await
+            .unwrap();
+
+        let first = db
+            .record_job_event(job.id, "repo-a", "success", None)
+            .await
+            .unwrap();
+        db.record_job_event(job.id, "repo-b", "error", Some("boom"))
+            .await
+            .unwrap();
+        assert_eq!(db.list_job_events(job.id, 0).await.unwrap().len(), 2);
+        let later = db.list_job_events(job.id, first.id).await.unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].repository, "repo-b");
+
+        let cancelled = db.cancel_job(job.id).await.unwrap().unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+        assert!(db.cancel_job(job.id).await.unwrap().is_none());
+        assert!(db.claim_next_job(JOB_LEASE).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_api_key_lifecycle(pool: PgPool) {
+        let db = Database { pool };
+        let scopes = vec!["docs:read".to_string()];
+        let key = db
+            .create_api_key("docs-site", "dk_1234", "hash", &scopes)
+            .await
+            .unwrap();
+        assert!(key.last_used_at.is_none());
+
+        let used = db.use_api_key("hash").await.unwrap().unwrap();
+        assert_eq!(used.id, key.id);
+        assert_eq!(used.scopes, scopes);
+        assert!(used.last_used_at.is_some());
+        assert!(db.use_api_key("other").await.unwrap().is_none());
+
+        assert!(db.revoke_api_key(key.id).await.unwrap().is_some());
+        assert!(db.revoke_api_key(key.id).await.unwrap().is_none());
+        assert!(db.use_api_key("hash").await.unwrap().is_none());
+        assert_eq!(db.list_api_keys().await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_site_session_lifecycle(pool: PgPool) {
+        let db = Database { pool };
+        let expired = Utc::now() - chrono::Duration::minutes(1);
+        db.create_site_session("old", "key", expired).await.unwrap();
+        assert!(db.find_site_session("old").await.unwrap().is_none());
+
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        let session = db
+            .create_site_session("current", "key", expires_at)
+            .await
+            .unwrap();
+        let found = db.find_site_session("current").await.unwrap().unwrap();
+        assert_eq!(found.id, session.id);
+        assert_eq!(found.key_hash, "key");
+
+        // Opening a session clears the expired ones
+        assert!(!db.delete_site_session("old").await.unwrap());
+        assert!(db.delete_site_session("current").await.unwrap());
+        assert!(db.find_site_session("current").await.unwrap().is_none());
+    }
 }
@@ -1,18 +1,55 @@
-use crate::ApplicationConfig;
 use crate::ProjectConfig;
+use crate::{ApplicationConfig, GitHubAuth, OrganizationConfig};
+use crate::metrics::METRICS;
 use async_trait::async_trait;
-use octocrab::{Octocrab, OctocrabBuilder};
+use octocrab::models::{AppId, InstallationId};
+use octocrab::{FromResponse, Octocrab, OctocrabBuilder};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
+mod budget;
+mod cache;
+
+pub use budget::{Priority, Quota, RateLimitBudget, RateLimitResource, Spend};
+pub use cache::{CacheEntry, CachedResponse, HttpCache};
+
+/// Repositories requested per page when listing the organization.
+const REPOSITORIES_PER_PAGE: u8 = 100;
+
+/// Files whose history is requested in one GraphQL query.
+const HISTORY_BATCH_SIZE: usize = 20;
+
+/// Commits per file whose authors are counted as contributors.
+const HISTORY_COMMITS: u8 = 100;
+
+/// Requests of each quota that background work leaves to interactive requests.
+const RATE_LIMIT_RESERVE: u64 = 100;
+
+/// Characters left as they are in a ref that is part of a REST path.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Whether `git_ref` is a full commit SHA, which names the same commit forever.
+fn is_commit_sha(git_ref: &str) -> bool {
+    git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GitHubError {
     #[error("Authentication failed: {0}")]
     AuthenticationError(String),
 
-    #[error("API rate limit exceeded")]
-    RateLimitExceeded,
+    #[error("API rate limit exceeded until {reset_at}")]
+    RateLimitExceeded { reset_at: chrono::DateTime<chrono::Utc> },
 
     #[error("Organization not found: {0}")]
     OrganizationNotFound(String),
@@ -23,6 +60,9 @@ pub enum GitHubError {
     #[error("Repository not found: {0}")]
     RepositoryNotFound(String),
 
+    #[error("Ref not found: {0}")]
+    RefNotFound(String),
+
     #[error("Configuration file not found in repository: {0}")]
     ConfigFileNotFound(String),
 
@@ -56,6 +96,125 @@ pub struct RepositoryFile {
     pub file_type: String,
 }
 
+/// Every file and directory of a repository at one commit.
+#[derive(Debug, Clone)]
+pub struct RepositoryTree {
+    /// SHA of the tree
+    pub sha: String,
+    pub files: Vec<RepositoryFile>,
+    /// Whether GitHub left entries out because the tree is too large
+    pub truncated: bool,
+}
+
+/// Response of `GET /repos/{owner}/{repo}/contents/{path}`: a directory listing, or the
+/// file itself.
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum ContentResponse {
+    Directory(Vec<octocrab::models::repos::Content>),
+    File(Box<octocrab::models::repos::Content>),
+}
+
+impl From<ContentResponse> for octocrab::models::repos::ContentItems {
+    fn from(response: ContentResponse) -> Self {
+        let items = match response {
+            ContentResponse::Directory(items) => items,
+            ContentResponse::File(item) => vec![*item],
+        };
+        Self { items }
+    }
+}
+
+/// Response of `GET /repos/{owner}/{repo}/git/trees/{tree_sha}`.
+#[derive(Debug, serde::Deserialize)]
+struct GitTree {
+    sha: String,
+    tree: Vec<GitTreeEntry>,
+    truncated: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GitTreeEntry {
+    path: String,
+    #[serde(rename = "type")]
+    entry_type: String,
+    /// SHA of the blob, tree or commit the entry names
+    sha: String,
+    size: Option<u64>,
+}
+
+impl From<GitTree> for RepositoryTree {
+    fn from(tree: GitTree) -> Self {
+        let files = tree
+            .tree
+            .into_iter()
+            .filter_map(|entry| {
+                // Submodules are listed as commits and have no files of their own
+                let file_type = match entry.entry_type.as_str() {
+                    "blob" => "file",
+                    "tree" => "dir",
+                    _ => return None,
+                };
+                Some(RepositoryFile {
+                    name: entry
+                        .path
+                        .rsplit_once('/')
+                        .map_or(entry.path.as_str(), |(_, name)| name)
+                        .to_string(),
+                    path: entry.path,
+                    size: entry.size,
+                    file_type: file_type.to_string(),
+                })
+            })
+            .collect();
+
+        Self {
+            sha: tree.sha,
+            files,
+            truncated: tree.truncated,
+        }
+    }
+}
+
+/// The latest commit that touched a file, and who has worked on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHistory {
+    pub last_modified: chrono::DateTime<chrono::Utc>,
+    pub commit_sha: String,
+    /// GitHub login of the author of the latest commit, if it is linked to an account
+    pub author: Option<String>,
+    /// Distinct authors of the most recent commits to the file
+    pub contributors: usize,
+}
+
+impl FileHistory {
+    /// Read the `history` connection of a commit, newest commit first. `None` when no
+    /// commit touched the path, such as for a file that does not exist.
+    pub fn from_graphql(history: &serde_json::Value) -> Option<Self> {
+        let commits = history["nodes"].as_array()?;
+        let latest = commits.first()?;
+
+        // Authors without a GitHub account are told apart by name
+        let author_key = |commit: &serde_json::Value| {
+            commit["author"]["user"]["login"]
+                .as_str()
+                .or_else(|| commit["author"]["name"].as_str())
+                .map(str::to_lowercase)
+        };
+        let contributors: std::collections::HashSet<String> =
+            commits.iter().filter_map(author_key).collect();
+
+        Some(Self {
+            last_modified: latest["committedDate"].as_str()?.parse().ok()?,
+            commit_sha: latest["oid"].as_str()?.to_string(),
+            author: latest["author"]["user"]["login"]
+                .as_str()
+                .map(str::to_string),
+            contributors: contributors.len(),
+        })
+    }
+}
+
 /// Represents a file in a repository with its content
 #[derive(Debug, Clone)]
 pub struct RepositoryFileContent {
@@ -67,34 +226,233 @@ pub struct RepositoryFileContent {
     pub content: Option<String>,
 }
 
+/// The state of the REST API rate limit.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Who can see a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+    Internal,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+            Visibility::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = String;
+
+    /// Accepts both the REST (`public`) and GraphQL (`PUBLIC`) spellings.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "public" => Ok(Visibility::Public),
+            "private" => Ok(Visibility::Private),
+            "internal" => Ok(Visibility::Internal),
+            _ => Err(format!(
+                "unknown visibility {}, expected public, private or internal",
+                value
+            )),
+        }
+    }
+}
+
+/// The attributes of a repository that [`RepositoryFilter`] looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryAttributes {
+    pub name: String,
+    pub archived: bool,
+    pub fork: bool,
+    pub visibility: Visibility,
+    pub topics: Vec<String>,
+}
+
+impl RepositoryAttributes {
+    fn from_rest(repo: &octocrab::models::Repository) -> Self {
+        let private = repo.private.unwrap_or(false);
+        let visibility = repo
+            .visibility
+            .as_deref()
+            .and_then(|visibility| visibility.parse().ok())
+            .unwrap_or(if private {
+                Visibility::Private
+            } else {
+                Visibility::Public
+            });
+
+        Self {
+            name: repo.name.clone(),
+            archived: repo.archived.unwrap_or(false),
+            fork: repo.fork.unwrap_or(false),
+            visibility,
+            topics: repo.topics.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Read a repository node selected with [`REPOSITORY_ATTRIBUTES_FIELDS`].
+    fn from_graphql(node: &serde_json::Value) -> Result<Self, GitHubError> {
+        let name = node["name"].as_str().ok_or_else(|| {
+            GitHubError::RequestFailed("Invalid repository name in response".to_string())
+        })?;
+        let visibility = node["visibility"]
+            .as_str()
+            .and_then(|visibility| visibility.parse().ok())
+            .unwrap_or(Visibility::Public);
+        let topics = node["repositoryTopics"]["nodes"]
+            .as_array()
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .filter_map(|node| node["topic"]["name"].as_str())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            name: name.to_string(),
+            archived: node["isArchived"].as_bool().unwrap_or(false),
+            fork: node["isFork"].as_bool().unwrap_or(false),
+            visibility,
+            topics,
+        })
+    }
+}
+
+/// GraphQL fields of a repository node read by [`RepositoryAttributes::from_graphql`].
+const REPOSITORY_ATTRIBUTES_FIELDS: &str = r#"name
+                        isArchived
+                        isFork
+                        visibility
+                        repositoryTopics(first: 20) {
+                          nodes {
+                            topic {
+                              name
+                            }
+                          }
+                        }"#;
+
+/// Which repositories of the organization are listed and scanned. The default includes
+/// every repository.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepositoryFilter {
+    pub skip_archived: bool,
+    pub skip_forks: bool,
+    /// Only include repositories with one of these visibilities; any when empty
+    pub visibility: Vec<Visibility>,
+    /// Only include repositories with at least one of these topics; any when empty
+    pub topics: Vec<String>,
+    /// Leave out repositories with any of these topics
+    pub excluded_topics: Vec<String>,
+}
+
+impl RepositoryFilter {
+    pub fn matches(&self, repo: &RepositoryAttributes) -> bool {
+        let has_topic = |topics: &[String]| {
+            topics
+                .iter()
+                .any(|topic| repo.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)))
+        };
+
+        if (self.skip_archived && repo.archived) || (self.skip_forks && repo.fork) {
+            return false;
+        }
+
+        (self.visibility.is_empty() || self.visibility.contains(&repo.visibility))
+            && (self.topics.is_empty() || has_topic(&self.topics))
+            && !has_topic(&self.excluded_topics)
+    }
+}
+
+/// Read access to the repositories of an organization. Methods that take a `git_ref` read
+/// that branch, tag or commit SHA, and the default branch when it is `None`.
 #[async_trait]
 pub trait Client {
     async fn current_user(&self) -> Result<String, GitHubError>;
 
+    /// How the client authenticates, such as `personal access token`.
+    fn auth_mode(&self) -> String;
+
+    /// The organization whose repositories the client reads.
+    fn organization(&self) -> &str;
+
     async fn handle_rate_limits(&self) -> Result<(), GitHubError>;
 
+    /// The current REST API rate limit without waiting for it to reset. Clients that keep
+    /// a rate limit budget answer from it rather than asking GitHub every time.
+    async fn rate_limit(&self) -> Result<RateLimit, GitHubError>;
+
+    /// Names of every repository of the organization that passes the repository filter.
     async fn repositories(&self) -> Result<Vec<String>, GitHubError>;
 
     async fn scan_for_config_file(&self, repo_name: &str) -> Result<Option<String>, GitHubError>;
 
-    async fn read_config_file(&self, repo_name: &str) -> Result<String, GitHubError>;
+    /// The SHA of the commit a branch, tag or commit SHA points at.
+    async fn resolve_ref(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<String, GitHubError>;
+
+    async fn read_config_file(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<String, GitHubError>;
 
-    async fn get_project_config(&self, repo_name: &str) -> Result<ProjectConfig, GitHubError>;
+    async fn get_project_config(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<ProjectConfig, GitHubError>;
 
     async fn get_file_content(
         &self,
         repo_name: &str,
         file_path: &str,
+        git_ref: Option<&str>,
     ) -> Result<String, GitHubError>;
 
-    async fn file_exists(&self, repo_name: &str, file_path: &str) -> Result<bool, GitHubError>;
+    async fn file_exists(
+        &self,
+        repo_name: &str,
+        file_path: &str,
+        git_ref: Option<&str>,
+    ) -> Result<bool, GitHubError>;
 
     async fn list_repository_files(
         &self,
         repo_name: &str,
         path: Option<&str>,
+        git_ref: Option<&str>,
     ) -> Result<Vec<RepositoryFile>, GitHubError>;
 
+    /// Every file and directory of the repository in a single request. Very large trees
+    /// come back truncated, in which case callers should list directories instead.
+    async fn get_tree(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<RepositoryTree, GitHubError>;
+
     /// Batch fetch multiple files from a single repository using GraphQL
     ///
     /// This method uses GitHub's GraphQL API to efficiently fetch the content of multiple files
@@ -104,6 +462,7 @@ pub trait Client {
     ///
     /// * `repo_name` - The name of the repository
     /// * `file_paths` - A vector of file paths to fetch
+    /// * `git_ref` - The branch, tag or commit SHA to read, or the default branch
     ///
     /// # Returns
     ///
@@ -113,8 +472,18 @@ pub trait Client {
         &self,
         repo_name: &str,
         file_paths: &[String],
+        git_ref: Option<&str>,
     ) -> Result<HashMap<String, Option<String>>, GitHubError>;
     
+    /// The latest commit touching each of `file_paths` and its number of contributors,
+    /// fetched with batched GraphQL queries. Paths no commit touched are left out.
+    async fn batch_fetch_file_history(
+        &self,
+        repo_name: &str,
+        file_paths: &[String],
+        git_ref: Option<&str>,
+    ) -> Result<HashMap<String, FileHistory>, GitHubError>;
+
     /// Batch check multiple repositories for the existence of the documents.toml configuration file using GraphQL
     ///
     /// This method uses GitHub's GraphQL API to efficiently check multiple repositories
@@ -155,69 +524,456 @@ pub trait Client {
 pub struct GitHubClient {
     pub client: Octocrab,
     pub organization: String,
-    rate_limit_buffer: u32, // Number of requests to keep as a buffer
+    /// Repositories listed and scanned; the batch methods skip the rest
+    pub repository_filter: RepositoryFilter,
+    /// The GitHub App itself, authenticated with a JWT, when `client` acts as one of its
+    /// installations
+    app: Option<Octocrab>,
+    auth_mode: String,
+    /// Shared by every clone, and by the clients of organizations with the same credentials,
+    /// so concurrent tasks spend the same quotas
+    budget: Arc<RateLimitBudget>,
+    priority: Priority,
+    /// REST responses kept on disk and revalidated, when configured
+    cache: Option<HttpCache>,
 }
 
 impl GitHubClient {
-    pub async fn new(config: &ApplicationConfig) -> Result<Self, GitHubError> {
-        let client = OctocrabBuilder::new()
-            .personal_token(config.github_token.clone())
-            .build()?;
+    /// A client for one of the configured organizations, with its credentials.
+    pub async fn new(
+        config: &ApplicationConfig,
+        organization: &OrganizationConfig,
+    ) -> Result<Self, GitHubError> {
+        let (client, app, auth_mode) = match &organization.auth {
+            GitHubAuth::Token(token) => {
+                let client = OctocrabBuilder::new()
+                    .personal_token(token.clone())
+                    .build()?;
+                (client, None, organization.auth.mode().to_string())
+            }
+            GitHubAuth::App {
+                app_id,
+                private_key,
+                installation_id,
+            } => {
+                let key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key.as_bytes())
+                    .map_err(|e| {
+                        GitHubError::AuthenticationError(format!(
+                            "Invalid GitHub App private key: {}",
+                            e
+                        ))
+                    })?;
+                let app = OctocrabBuilder::new().app(AppId(*app_id), key).build()?;
+
+                let installation_id = match installation_id {
+                    Some(id) => InstallationId(*id),
+                    None => {
+                        METRICS.github_request("installation");
+                        app.apps()
+                            .get_org_installation(&organization.name)
+                            .await
+                            .map_err(|e| {
+                                GitHubError::AuthenticationError(format!(
+                                    "GitHub App {} is not installed on {}: {}",
+                                    app_id, organization.name, e
+                                ))
+                            })?
+                            .id
+                    }
+                };
+
+                // Installation access tokens expire after an hour. Octocrab mints one on
+                // the first request and mints a new one shortly before it expires.
+                let client = app.installation(installation_id)?;
+                let auth_mode = format!(
+                    "{} {} (installation {})",
+                    organization.auth.mode(),
+                    app_id,
+                    installation_id
+                );
+                (client, Some(app), auth_mode)
+            }
+        };
 
         Ok(Self {
             client,
-            organization: config.github_organization.clone(),
-            rate_limit_buffer: 100, // Keep 100 requests as a buffer
+            organization: organization.name.clone(),
+            repository_filter: config.repository_filter.clone(),
+            app,
+            auth_mode,
+            budget: Arc::new(RateLimitBudget::new(RATE_LIMIT_RESERVE)),
+            priority: Priority::default(),
+            cache: config.github_http_cache.clone().map(HttpCache::new),
         })
     }
 
-    /// Enhanced rate limit handling with exponential backoff and proactive throttling
+    /// A client for the same organization that lists and scans the repositories `filter`
+    /// selects.
+    pub fn with_repository_filter(mut self, filter: RepositoryFilter) -> Self {
+        self.repository_filter = filter;
+        self
+    }
+
+    /// A client that spends the same rate limit budget with requests of `priority`.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Count a request to `endpoint` against its quota, seeding the budget from
+    /// `/rate_limit` when nothing is known about the quota's current window.
+    async fn spend(&self, endpoint: &str, resource: RateLimitResource) -> Result<(), GitHubError> {
+        let spend = match self.budget.spend(resource, self.priority, chrono::Utc::now()) {
+            // A quota GitHub does not report stays unknown and is left to GitHub to enforce
+            Spend::Unknown => {
+                self.seed_budget(resource).await?;
+                self.budget.spend(resource, self.priority, chrono::Utc::now())
+            }
+            spend => spend,
+        };
+
+        if let Spend::Exhausted { reset_at } = spend {
+            tracing::warn!(
+                "{:?} rate limit budget of {} is used up for {:?} requests until {}",
+                resource,
+                self.organization,
+                self.priority,
+                reset_at
+            );
+            return Err(GitHubError::RateLimitExceeded { reset_at });
+        }
+        METRICS.github_request(endpoint);
+        Ok(())
+    }
+
+    /// Seed every quota of the budget from `/rate_limit`, which does not count against them.
+    async fn seed_budget(&self, resource: RateLimitResource) -> Result<(), GitHubError> {
+        let _seeding = self.budget.seeding.lock().await;
+        // Another request may have seeded the budget while this one waited
+        if !self.budget.needs_seeding(resource, chrono::Utc::now()) {
+            return Ok(());
+        }
+
+        METRICS.github_request("rate_limit");
+        let rate_limit = self.client.ratelimit().get().await?;
+        let quota = |rate: &octocrab::models::Rate| Quota {
+            limit: rate.limit as u64,
+            remaining: rate.remaining as u64,
+            reset_at: chrono::DateTime::from_timestamp(rate.reset as i64, 0)
+                .unwrap_or_else(chrono::Utc::now),
+        };
+
+        let resources = &rate_limit.resources;
+        self.budget
+            .seed(RateLimitResource::Core, quota(&resources.core));
+        self.budget
+            .seed(RateLimitResource::Search, quota(&resources.search));
+        if let Some(graphql) = &resources.graphql {
+            self.budget.seed(RateLimitResource::GraphQL, quota(graphql));
+        }
+        METRICS
+            .github_rate_limit_remaining
+            .set(resources.core.remaining as i64);
+        Ok(())
+    }
+
+    /// Correct the budget from the `x-ratelimit-*` headers of a response.
+    fn record_rate_limit(&self, headers: &http::HeaderMap) {
+        if self.budget.update_from_headers(headers) == Some(RateLimitResource::Core)
+            && let Some(quota) = self.budget.quota(RateLimitResource::Core)
+        {
+            METRICS
+                .github_rate_limit_remaining
+                .set(quota.remaining as i64);
+        }
+    }
+
+    /// Wait for the REST quota to reset when little of it is left, or fail if it is used up
+    /// and resets more than an hour from now.
     async fn check_and_handle_rate_limits(&self) -> Result<(), GitHubError> {
-        const MAX_RETRIES: u32 = 3;
-        let mut retry_count = 0;
+        let rate_limit = self.rate_limit().await?;
+        if rate_limit.remaining > RATE_LIMIT_RESERVE {
+            return Ok(());
+        }
 
-        loop {
-            let rate_limit = self.client.ratelimit().get().await.map_err(GitHubError::ApiError)?;
-
-            // If we're getting close to the limit, wait
-            if rate_limit.rate.remaining <= self.rate_limit_buffer as usize {
-                let reset_time = chrono::DateTime::from_timestamp(rate_limit.rate.reset as i64, 0)
-                    .unwrap_or_else(|| chrono::Utc::now());
-                let reset_duration = reset_time.signed_duration_since(chrono::Utc::now());
-
-                if reset_duration.num_seconds() > 0 && reset_duration.num_seconds() < 3600 {
-                    tracing::info!(
-                        "Rate limit approaching (remaining: {}), waiting {} seconds until reset",
-                        rate_limit.rate.remaining,
-                        reset_duration.num_seconds()
-                    );
+        let reset_duration = rate_limit
+            .reset_at
+            .signed_duration_since(chrono::Utc::now());
+        if reset_duration.num_seconds() > 0 && reset_duration.num_seconds() < 3600 {
+            tracing::info!(
+                "Rate limit approaching (remaining: {}), waiting {} seconds until reset",
+                rate_limit.remaining,
+                reset_duration.num_seconds()
+            );
+            sleep(Duration::from_secs(reset_duration.num_seconds() as u64 + 1)).await;
+            return Ok(());
+        }
 
-                    sleep(Duration::from_secs(reset_duration.num_seconds() as u64 + 1)).await;
-                    continue;
+        if rate_limit.remaining > 0 {
+            Ok(())
+        } else {
+            Err(GitHubError::RateLimitExceeded {
+                reset_at: rate_limit.reset_at,
+            })
+        }
+    }
+
+    /// GET a REST route, correcting the budget from the response headers. With an HTTP
+    /// cache, a stored response is revalidated and served again when GitHub answers 304.
+    async fn rest_get<R: DeserializeOwned>(&self, route: String) -> Result<R, GitHubError> {
+        let cached = match &self.cache {
+            Some(cache) => cache.get(&route).await,
+            None => None,
+        };
+        let conditional = cached.as_ref().map(CachedResponse::conditional_headers);
+        let response = self
+            .client
+            ._get_with_headers(route.as_str(), conditional)
+            .await?;
+        self.record_rate_limit(response.headers());
+
+        let body = match cached {
+            Some(cached) if response.status() == http::StatusCode::NOT_MODIFIED => {
+                self.budget.refund(RateLimitResource::Core);
+                tracing::debug!("{} has not changed since {}", route, cached.stored_at);
+                cached.body
+            }
+            _ => {
+                let response = octocrab::map_github_error(response).await?;
+                let header = |name: http::header::HeaderName| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                };
+                let etag = header(http::header::ETAG);
+                let last_modified = header(http::header::LAST_MODIFIED);
+                let body = self.client.body_to_string(response).await?;
+
+                if let Some(cache) = &self.cache
+                    && (etag.is_some() || last_modified.is_some())
+                {
+                    let response = CachedResponse {
+                        route: route.clone(),
+                        etag,
+                        last_modified,
+                        stored_at: chrono::Utc::now(),
+                        body,
+                    };
+                    if let Err(e) = cache.put(&response).await {
+                        tracing::warn!("Failed to cache {}: {}", route, e);
+                    }
+                    response.body
+                } else {
+                    body
                 }
             }
+        };
 
-            // If we have sufficient requests remaining, proceed
-            if rate_limit.rate.remaining > 0 {
-                return Ok(());
-            }
+        serde_json::from_str(&body)
+            .map_err(|e| GitHubError::InvalidFormat(format!("{}: {}", route, e)))
+    }
+
+    /// POST a GraphQL query, correcting the budget from the response headers.
+    async fn post_graphql(
+        &self,
+        query: &serde_json::Value,
+    ) -> Result<serde_json::Value, octocrab::Error> {
+        let response = self.client._post("/graphql", Some(query)).await?;
+        self.record_rate_limit(response.headers());
+        serde_json::Value::from_response(octocrab::map_github_error(response).await?).await
+    }
+
+    /// GET a REST route, or the next page of one, past the HTTP cache, correcting the budget
+    /// from the response headers.
+    async fn rest_get_uncached<R: FromResponse>(
+        &self,
+        uri: impl TryInto<http::Uri>,
+    ) -> Result<R, octocrab::Error> {
+        let response = self.client._get(uri).await?;
+        self.record_rate_limit(response.headers());
+        R::from_response(octocrab::map_github_error(response).await?).await
+    }
 
-            // If we're completely rate-limited, implement retry with backoff
-            retry_count += 1;
-            if retry_count > MAX_RETRIES {
-                return Err(GitHubError::RateLimitExceeded);
+    /// The contents API response for a path at a ref, or the default branch.
+    async fn get_content(
+        &self,
+        repo_name: &str,
+        path: &str,
+        git_ref: Option<&str>,
+    ) -> Result<octocrab::models::repos::ContentItems, GitHubError> {
+        let mut route = format!("/repos/{}/{}/contents/{}", self.organization, repo_name, path);
+        if let Some(git_ref) = git_ref {
+            let query = serde_urlencoded::to_string([("ref", git_ref)])
+                .map_err(|e| GitHubError::RequestFailed(e.to_string()))?;
+            route = format!("{}?{}", route, query);
+        }
+        let response: ContentResponse = self.rest_get(route).await?;
+        Ok(response.into())
+    }
+
+    /// The text of each of `file_paths` at `git_ref`, fetched with batched GraphQL queries.
+    async fn fetch_files(
+        &self,
+        repo_name: &str,
+        file_paths: &[String],
+        git_ref: Option<&str>,
+    ) -> Result<HashMap<String, Option<String>>, GitHubError> {
+        if file_paths.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let git_ref = git_ref.unwrap_or("HEAD");
+
+        let mut final_result = HashMap::new();
+
+        // Split into batches to avoid complexity limits
+        let batches = self.create_file_batches(file_paths, 50); // 50 files per batch
+
+        for batch in batches {
+            // Build GraphQL query to fetch multiple files from a single repository
+            let mut file_queries = Vec::new();
+            for (index, file_path) in batch.iter().enumerate() {
+                // JSON string literals are valid GraphQL strings
+                let expression = serde_json::Value::from(format!("{}:{}", git_ref, file_path));
+                file_queries.push(format!(
+                    r#"file{}: object(expression: {}) {{
+                      ... on Blob {{
+                        id
+                        text
+                      }}
+                    }}"#,
+                    index, expression
+                ));
             }
 
-            let backoff_duration = Duration::from_secs(2_u64.pow(retry_count));
-            tracing::warn!(
-                "Rate limit exceeded, retrying in {} seconds (attempt {}/{})",
-                backoff_duration.as_secs(),
-                retry_count,
-                MAX_RETRIES
+            let query = format!(
+                r#"
+                query {{
+                  repository(owner: "{org}", name: "{repo}") {{
+                    {files}
+                  }}
+                }}
+                "#,
+                org = self.organization,
+                repo = repo_name,
+                files = file_queries.join("\n                      ")
             );
 
-            sleep(backoff_duration).await;
+            let query = serde_json::json!({"query": &query});
+
+            // Execute the GraphQL query with retries
+            let response = self.execute_graphql_with_retries(&query).await?;
+
+            // Extract file data from response
+            let repository = response["data"]["repository"]
+                .as_object()
+                .ok_or_else(|| GitHubError::RequestFailed("Invalid GraphQL response format".to_string()))?;
+
+            // Process each file in this batch
+            for (index, file_path) in batch.iter().enumerate() {
+                let file_key = format!("file{}", index);
+                let file_object = &repository[&file_key];
+
+                // Check if the file exists (object will be null if file doesn't exist)
+                let content = if !file_object.is_null() {
+                    file_object["text"].as_str().map(|s| s.to_string())
+                } else {
+                    None
+                };
+
+                final_result.insert(file_path.clone(), content);
+            }
         }
+
+        Ok(final_result)
+    }
+
+    /// `batch_fetch_files` with an HTTP cache. The tree at `git_ref`, which is revalidated
+    /// for free, names the blob of each file; blobs already in the cache are served from
+    /// it, and only the others are fetched.
+    ///
+    /// `git_ref` is resolved to a commit once, so the files fetched are those of the tree
+    /// even if the ref moves meanwhile; otherwise the text of a newer file could be cached
+    /// under the blob SHA of an older one.
+    async fn fetch_files_through_cache(
+        &self,
+        cache: &HttpCache,
+        repo_name: &str,
+        file_paths: &[String],
+        git_ref: Option<&str>,
+    ) -> Result<HashMap<String, Option<String>>, GitHubError> {
+        let commit = match git_ref {
+            Some(git_ref) if is_commit_sha(git_ref) => git_ref.to_string(),
+            _ => self.resolve_ref(repo_name, git_ref).await?,
+        };
+        let git_ref = Some(commit.as_str());
+
+        let tree = self.get_git_tree(repo_name, git_ref).await?;
+        if tree.truncated {
+            return self.fetch_files(repo_name, file_paths, git_ref).await;
+        }
+        let blobs: HashMap<&str, &str> = tree
+            .tree
+            .iter()
+            .filter(|entry| entry.entry_type == "blob")
+            .map(|entry| (entry.path.as_str(), entry.sha.as_str()))
+            .collect();
+
+        let mut result = HashMap::new();
+        let mut changed = Vec::new();
+        for file_path in file_paths {
+            match blobs.get(file_path.as_str()) {
+                Some(sha) => match cache.get_blob(sha).await {
+                    Some(text) => {
+                        result.insert(file_path.clone(), Some(text));
+                    }
+                    None => changed.push(file_path.clone()),
+                },
+                // Left to GitHub, which may know the path by another spelling
+                None => changed.push(file_path.clone()),
+            }
+        }
+        tracing::debug!(
+            "{} of {} files of {} are cached",
+            result.len(),
+            file_paths.len(),
+            repo_name
+        );
+
+        for (file_path, text) in self.fetch_files(repo_name, &changed, git_ref).await? {
+            if let (Some(sha), Some(text)) = (blobs.get(file_path.as_str()), &text)
+                && let Err(e) = cache.put_blob(sha, text).await
+            {
+                tracing::warn!("Failed to cache {}: {}", file_path, e);
+            }
+            result.insert(file_path, text);
+        }
+
+        Ok(result)
+    }
+
+    /// The recursive tree at `git_ref`, or the default branch.
+    async fn get_git_tree(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<GitTree, GitHubError> {
+        let git_ref = match git_ref {
+            Some(git_ref) => git_ref.to_string(),
+            None => self.resolve_ref(repo_name, None).await?,
+        };
+        self.spend("trees", RateLimitResource::Core).await?;
+
+        let route = format!(
+            "/repos/{}/{}/git/trees/{}?recursive=1",
+            self.organization,
+            repo_name,
+            utf8_percent_encode(&git_ref, PATH_SEGMENT)
+        );
+        self.rest_get(route).await
     }
 
     /// Execute GraphQL query with automatic rate limiting and retry logic
@@ -229,10 +985,8 @@ impl GitHubClient {
         let mut retry_count = 0;
 
         loop {
-            // Check rate limits before making the request
-            self.check_and_handle_rate_limits().await?;
-
-            let query_response: Result<serde_json::Value, _> = self.client.graphql(query).await;
+            self.spend("graphql", RateLimitResource::GraphQL).await?;
+            let query_response = self.post_graphql(query).await;
 
             match query_response {
                 Ok(response) => {
@@ -244,7 +998,8 @@ impl GitHubClient {
                         if error_msg.contains("rate limit") || error_msg.contains("RATE_LIMITED") {
                             retry_count += 1;
                             if retry_count > MAX_RETRIES {
-                                return Err(GitHubError::RateLimitExceeded);
+                                let reset_at = self.graphql_rate_limit_reset();
+                                return Err(GitHubError::RateLimitExceeded { reset_at });
                             }
 
                             let backoff_duration = Duration::from_secs(2_u64.pow(retry_count));
@@ -284,6 +1039,108 @@ impl GitHubClient {
         }
     }
 
+    /// When the GraphQL rate limit resets, or a minute from now if GitHub has not said
+    fn graphql_rate_limit_reset(&self) -> chrono::DateTime<chrono::Utc> {
+        self.budget
+            .quota(RateLimitResource::GraphQL)
+            .map(|quota| quota.reset_at)
+            .filter(|reset_at| *reset_at > chrono::Utc::now())
+            .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::minutes(1))
+    }
+
+    /// Page through the organization's repositories with GraphQL cursors, checking each one
+    /// that passes the repository filter for documents.toml and fetching its text if asked.
+    async fn batch_config_files(
+        &self,
+        with_content: bool,
+    ) -> Result<Vec<RepositoryFileContent>, GitHubError> {
+        let mut result = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        // Handle pagination to get all repositories
+        loop {
+            let query = format!(
+                r#"
+                query {{
+                  organization(login: "{org}") {{
+                    repositories(first: {per_page}, after: {cursor}) {{
+                      pageInfo {{
+                        hasNextPage
+                        endCursor
+                      }}
+                      nodes {{
+                        {attributes}
+                        object(expression: "HEAD:documents.toml") {{
+                          ... on Blob {{
+                            id
+                            {text}
+                          }}
+                        }}
+                      }}
+                    }}
+                  }}
+                }}
+                "#,
+                org = self.organization,
+                per_page = REPOSITORIES_PER_PAGE,
+                cursor = match &cursor {
+                    Some(c) => format!("\"{}\"", c),
+                    None => "null".to_string()
+                },
+                attributes = REPOSITORY_ATTRIBUTES_FIELDS,
+                text = if with_content { "text" } else { "" },
+            );
+
+            let query = serde_json::json!({"query": &query});
+
+            // Execute the GraphQL query
+            let response = self.execute_graphql_with_retries(&query).await?;
+            let repositories = &response["data"]["organization"]["repositories"];
+
+            // Extract repository data from response
+            let nodes = repositories["nodes"]
+                .as_array()
+                .ok_or_else(|| GitHubError::RequestFailed("Invalid GraphQL response format".to_string()))?;
+
+            for node in nodes {
+                let attributes = RepositoryAttributes::from_graphql(node)?;
+                if !self.repository_filter.matches(&attributes) {
+                    continue;
+                }
+
+                // Check if the file exists (object will be null if file doesn't exist)
+                let file_exists = !node["object"].is_null();
+                let content = if file_exists {
+                    node["object"]["text"].as_str().map(|s| s.to_string())
+                } else {
+                    None
+                };
+
+                result.push(RepositoryFileContent {
+                    repo_name: attributes.name,
+                    exists: file_exists,
+                    content,
+                });
+            }
+
+            // Check if there are more pages
+            let has_next_page = repositories["pageInfo"]["hasNextPage"]
+                .as_bool()
+                .unwrap_or(false);
+
+            if !has_next_page {
+                break;
+            }
+
+            // Update cursor for next page
+            cursor = repositories["pageInfo"]["endCursor"]
+                .as_str()
+                .map(|s| s.to_string());
+        }
+
+        Ok(result)
+    }
+
     /// Split large file lists into smaller batches to avoid GraphQL complexity limits
     fn create_file_batches(&self, file_paths: &[String], batch_size: usize) -> Vec<Vec<String>> {
         file_paths
@@ -293,38 +1150,223 @@ impl GitHubClient {
     }
 }
 
+#[cfg(test)]
+impl GitHubClient {
+    /// A token-authenticated client for `organization` that sends its requests to
+    /// `base_uri`, such as a mock server, with a full rate limit budget.
+    pub(crate) fn with_base_uri(organization: &str, base_uri: &str) -> Self {
+        let budget = RateLimitBudget::new(RATE_LIMIT_RESERVE);
+        let reset_at = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp() + 3600, 0)
+            .unwrap();
+        for resource in [RateLimitResource::Core, RateLimitResource::GraphQL] {
+            budget.seed(
+                resource,
+                Quota {
+                    limit: 5000,
+                    remaining: 5000,
+                    reset_at,
+                },
+            );
+        }
+
+        Self {
+            client: OctocrabBuilder::new()
+                .base_uri(base_uri)
+                .unwrap()
+                .personal_token("token".to_string())
+                .build()
+                .unwrap(),
+            organization: organization.to_string(),
+            repository_filter: RepositoryFilter::default(),
+            app: None,
+            auth_mode: "personal access token".to_string(),
+            budget: Arc::new(budget),
+            priority: Priority::default(),
+            cache: None,
+        }
+    }
+
+    /// This client, caching its responses in `cache`.
+    pub(crate) fn with_http_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+}
+
+/// A client for each configured organization, in configuration order.
+#[derive(Clone, Debug)]
+pub struct GitHubClients {
+    clients: Vec<GitHubClient>,
+}
+
+impl GitHubClients {
+    #[cfg(test)]
+    pub(crate) fn from_clients(clients: Vec<GitHubClient>) -> Self {
+        Self { clients }
+    }
+
+    pub async fn new(config: &ApplicationConfig) -> Result<Self, GitHubError> {
+        let mut clients = Vec::with_capacity(config.github_organizations.len());
+        // Organizations that share a token or an App installation spend the same quotas
+        let mut budgets: HashMap<String, Arc<RateLimitBudget>> = HashMap::new();
+        for organization in &config.github_organizations {
+            let mut client = GitHubClient::new(config, organization).await?;
+            let credential = match &organization.auth {
+                GitHubAuth::Token(token) => token.clone(),
+                GitHubAuth::App { .. } => client.auth_mode.clone(),
+            };
+            client.budget = budgets
+                .entry(credential)
+                .or_insert_with(|| client.budget.clone())
+                .clone();
+            clients.push(client);
+        }
+        Ok(Self { clients })
+    }
+
+    /// The same clients, spending their rate limit budgets with requests of `priority`.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            clients: self
+                .clients
+                .iter()
+                .map(|client| client.with_priority(priority))
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, GitHubClient> {
+        self.clients.iter()
+    }
+
+    pub fn as_slice(&self) -> &[GitHubClient] {
+        &self.clients
+    }
+
+    /// The client of an organization. GitHub logins are not case sensitive.
+    pub fn get(&self, organization: &str) -> Option<&GitHubClient> {
+        self.clients
+            .iter()
+            .find(|client| client.organization.eq_ignore_ascii_case(organization))
+    }
+
+    /// The clients of one organization, or of all of them for `None`.
+    pub fn select(&self, organization: Option<&str>) -> Result<Vec<&GitHubClient>, GitHubError> {
+        match organization {
+            Some(organization) => self
+                .get(organization)
+                .map(|client| vec![client])
+                .ok_or_else(|| GitHubError::OrganizationNotFound(organization.to_string())),
+            None => Ok(self.clients.iter().collect()),
+        }
+    }
+
+    /// The client for a repository given as `owner/name`, along with its name. A bare name
+    /// belongs to the first configured organization.
+    pub fn resolve<'a>(
+        &self,
+        repository: &'a str,
+    ) -> Result<(&GitHubClient, &'a str), GitHubError> {
+        match repository.split_once('/') {
+            Some((owner, name)) => self
+                .get(owner)
+                .map(|client| (client, name))
+                .ok_or_else(|| GitHubError::OrganizationNotFound(owner.to_string())),
+            None => self
+                .clients
+                .first()
+                .map(|client| (client, repository))
+                .ok_or_else(|| GitHubError::OrganizationNotFound(repository.to_string())),
+        }
+    }
+
+    /// The `owner/name` of a repository given as either that or a bare name.
+    pub fn full_name(&self, repository: &str) -> Result<String, GitHubError> {
+        let (client, name) = self.resolve(repository)?;
+        Ok(format!("{}/{}", client.organization, name))
+    }
+}
+
 #[async_trait]
 impl Client for GitHubClient {
     async fn current_user(&self) -> Result<String, GitHubError> {
+        // Installation tokens cannot read `/user`, so an app reports its bot account
+        if let Some(app) = &self.app {
+            METRICS.github_request("app");
+            let app = app.current().app().await?;
+            return Ok(format!("{}[bot]", app.slug.unwrap_or(app.name)));
+        }
+
         // Test authentication by making a simple API call
-        let current_user = self.client.current().user().await?;
+        self.spend("user", RateLimitResource::Core).await?;
+        let current_user: octocrab::models::Author = self.rest_get_uncached("/user").await?;
         Ok(current_user.login)
     }
 
+    fn auth_mode(&self) -> String {
+        self.auth_mode.clone()
+    }
+
+    fn organization(&self) -> &str {
+        &self.organization
+    }
+
     async fn handle_rate_limits(&self) -> Result<(), GitHubError> {
         self.check_and_handle_rate_limits().await
     }
 
+    async fn rate_limit(&self) -> Result<RateLimit, GitHubError> {
+        let core = RateLimitResource::Core;
+        if self.budget.needs_seeding(core, chrono::Utc::now()) {
+            self.seed_budget(core).await?;
+        }
+        let quota = self.budget.quota(core).ok_or_else(|| {
+            GitHubError::RequestFailed("GitHub did not report a rate limit".to_string())
+        })?;
+
+        Ok(RateLimit {
+            limit: quota.limit,
+            remaining: quota.remaining,
+            reset_at: quota.reset_at,
+        })
+    }
+
     async fn repositories(&self) -> Result<Vec<String>, GitHubError> {
-        let repos = self
-            .client
-            .orgs(&self.organization)
-            .list_repos()
-            .send()
-            .await
-            .map_err(GitHubError::ApiError)?;
+        let mut names = Vec::new();
 
-        Ok(repos.items.into_iter().map(|repo| repo.name).collect())
+        self.spend("list_repos", RateLimitResource::Core).await?;
+        let route = format!(
+            "/orgs/{}/repos?per_page={}",
+            self.organization, REPOSITORIES_PER_PAGE
+        );
+        let mut page: octocrab::Page<octocrab::models::Repository> =
+            self.rest_get_uncached(route).await?;
+
+        loop {
+            names.extend(
+                page.items
+                    .iter()
+                    .map(RepositoryAttributes::from_rest)
+                    .filter(|repo| self.repository_filter.matches(repo))
+                    .map(|repo| repo.name),
+            );
+
+            let Some(next) = page.next.take() else {
+                break;
+            };
+            self.spend("list_repos", RateLimitResource::Core).await?;
+            page = self.rest_get_uncached(next).await?;
+        }
+
+        Ok(names)
     }
 
     async fn scan_for_config_file(&self, repo_name: &str) -> Result<Option<String>, GitHubError> {
         let repo_name = repo_name.trim();
+        self.spend("contents", RateLimitResource::Core).await?;
+
         let contents = self
-            .client
-            .repos(&self.organization, repo_name)
-            .get_content()
-            .path("documents.toml")
-            .send()
+            .get_content(repo_name, "documents.toml", None)
             .await
             .map_err(|_| GitHubError::ConfigFileNotFound(repo_name.to_string()))?;
 
@@ -343,15 +1385,55 @@ impl Client for GitHubClient {
         Ok(Some(url))
     }
 
-    async fn read_config_file(&self, repo_name: &str) -> Result<String, GitHubError> {
+    async fn resolve_ref(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<String, GitHubError> {
+        let git_ref = git_ref.unwrap_or("HEAD");
+        let query = serde_json::json!({
+            "query": r#"
+                query($owner: String!, $name: String!, $expression: String!) {
+                  repository(owner: $owner, name: $name) {
+                    object(expression: $expression) {
+                      ... on Commit { oid }
+                      ... on Tag { target { oid } }
+                    }
+                  }
+                }
+            "#,
+            "variables": {
+                "owner": self.organization,
+                "name": repo_name,
+                "expression": git_ref,
+            },
+        });
+
+        let response = self.execute_graphql_with_retries(&query).await?;
+        let repository = &response["data"]["repository"];
+        if repository.is_null() {
+            return Err(GitHubError::RepositoryNotFound(repo_name.to_string()));
+        }
+
+        // Annotated tags point at the commit they tag
+        let object = &repository["object"];
+        object["oid"]
+            .as_str()
+            .or_else(|| object["target"]["oid"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| GitHubError::RefNotFound(format!("{} in {}", git_ref, repo_name)))
+    }
+
+    async fn read_config_file(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<String, GitHubError> {
+        self.spend("contents", RateLimitResource::Core).await?;
+
         let contents = self
-            .client
-            .repos(&self.organization, repo_name)
-            .get_content()
-            .path("documents.toml")
-            .send()
-            .await
-            .map_err(GitHubError::ApiError)?;
+            .get_content(repo_name, "documents.toml", git_ref)
+            .await?;
 
         if contents.items.is_empty() {
             return Err(GitHubError::ConfigFileEmpty(repo_name.to_string()));
@@ -368,11 +1450,19 @@ impl Client for GitHubClient {
         Ok(content.clone())
     }
 
-    async fn get_project_config(&self, repo_name: &str) -> Result<ProjectConfig, GitHubError> {
-        if let Err(e) = self.scan_for_config_file(repo_name).await {
-            return Err(e);
-        }
-        let config = self.read_config_file(repo_name).await?;
+    async fn get_project_config(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<ProjectConfig, GitHubError> {
+        let config = match self.read_config_file(repo_name, git_ref).await {
+            Err(GitHubError::ApiError(octocrab::Error::GitHub { source, .. }))
+                if source.status_code.as_u16() == 404 =>
+            {
+                return Err(GitHubError::ConfigFileNotFound(repo_name.to_string()));
+            }
+            result => result?,
+        };
         if config.is_empty() {
             Err(GitHubError::ConfigFileEmpty(repo_name.to_string()))
         } else {
@@ -386,13 +1476,12 @@ impl Client for GitHubClient {
         &self,
         repo_name: &str,
         file_path: &str,
+        git_ref: Option<&str>,
     ) -> Result<String, GitHubError> {
+        self.spend("contents", RateLimitResource::Core).await?;
+
         let content = self
-            .client
-            .repos(&self.organization, repo_name)
-            .get_content()
-            .path(file_path)
-            .send()
+            .get_content(repo_name, file_path, git_ref)
             .await
             .map_err(|e| {
                 GitHubError::RequestFailed(format!("Failed to get file content: {}", e))
@@ -410,281 +1499,150 @@ impl Client for GitHubClient {
         Ok(decoded_content)
     }
 
-    async fn file_exists(&self, repo_name: &str, file_path: &str) -> Result<bool, GitHubError> {
-        let content = self
-            .client
-            .repos(&self.organization, repo_name)
-            .get_content()
-            .path(file_path)
-            .send()
-            .await
-            .map_err(GitHubError::ApiError)?;
-
-        Ok(!content.items.is_empty())
-    }
-
-    async fn list_repository_files(
-        &self,
-        repo_name: &str,
-        path: Option<&str>,
-    ) -> Result<Vec<RepositoryFile>, GitHubError> {
-        let path = path.unwrap_or("");
-        let contents = self
-            .client
-            .repos(&self.organization, repo_name)
-            .get_content()
-            .path(path)
-            .send()
-            .await
-            .map_err(GitHubError::ApiError)?;
-
-        let mut files = Vec::new();
-        for item in contents.items {
-            files.push(RepositoryFile {
-                path: item.path,
-                name: item.name,
-                size: Some(item.size as u64),
-                file_type: item.r#type,
-            });
-        }
-
-        Ok(files)
-    }
-
-    async fn batch_fetch_files(
-        &self,
-        repo_name: &str,
-        file_paths: &[String],
-    ) -> Result<HashMap<String, Option<String>>, GitHubError> {
-        if file_paths.is_empty() {
-            return Ok(HashMap::new());
-        }
-
-        let mut final_result = HashMap::new();
-
-        // Split into batches to avoid complexity limits
-        let batches = self.create_file_batches(file_paths, 50); // 50 files per batch
-
-        for batch in batches {
-            // Build GraphQL query to fetch multiple files from a single repository
-            let mut file_queries = Vec::new();
-            for (index, file_path) in batch.iter().enumerate() {
-                file_queries.push(format!(
-                    r#"file{}: object(expression: "HEAD:{}") {{
-                      ... on Blob {{
-                        id
-                        text
-                      }}
-                    }}"#,
-                    index,
-                    file_path
-                ));
-            }
-
-            let query = format!(
-                r#"
-                query {{
-                  repository(owner: "{org}", name: "{repo}") {{
-                    {files}
-                  }}
-                }}
-                "#,
-                org = self.organization,
-                repo = repo_name,
-                files = file_queries.join("\n                      ")
-            );
-
-            let query = serde_json::json!({"query": &query});
-
-            // Execute the GraphQL query with retries
-            let response = self.execute_graphql_with_retries(&query).await?;
-
-            // Extract file data from response
-            let repository = response["data"]["repository"]
-                .as_object()
-                .ok_or_else(|| GitHubError::RequestFailed("Invalid GraphQL response format".to_string()))?;
-
-            // Process each file in this batch
-            for (index, file_path) in batch.iter().enumerate() {
-                let file_key = format!("file{}", index);
-                let file_object = &repository[&file_key];
-
-                // Check if the file exists (object will be null if file doesn't exist)
-                let content = if !file_object.is_null() {
-                    file_object["text"].as_str().map(|s| s.to_string())
-                } else {
-                    None
-                };
-
-                final_result.insert(file_path.clone(), content);
-            }
-        }
-
-        Ok(final_result)
-    }
-
-    async fn batch_check_config_file_exists(&self) -> Result<HashMap<String, bool>, GitHubError> {
-        let mut result = HashMap::new();
-        let mut cursor: Option<String> = None;
+    async fn file_exists(
+        &self,
+        repo_name: &str,
+        file_path: &str,
+        git_ref: Option<&str>,
+    ) -> Result<bool, GitHubError> {
+        self.spend("contents", RateLimitResource::Core).await?;
 
-        // Handle pagination to get all repositories
-        loop {
-            // Create the GraphQL query to check for documents.toml configuration file
-            let query = format!(
-                r#"
-                query {{
-                  organization(login: "{org}") {{
-                    repositories(first: 100, after: {cursor}) {{
-                      pageInfo {{
-                        hasNextPage
-                        endCursor
-                      }}
-                      nodes {{
-                        name
-                        object(expression: "HEAD:documents.toml") {{
-                          ... on Blob {{
-                            id
-                          }}
-                        }}
-                      }}
-                    }}
-                  }}
-                }}
-                "#,
-                org = self.organization,
-                cursor = match &cursor {
-                    Some(c) => format!("\"{}\"", c),
-                    None => "null".to_string()
-                }
-            );
+        let content = self
+            .get_content(repo_name, file_path, git_ref)
+            .await?;
 
-            let query = serde_json::json!({"query": &query});
+        Ok(!content.items.is_empty())
+    }
 
-            // Execute the GraphQL query
-            let response = self.execute_graphql_with_retries(&query).await?;
+    async fn list_repository_files(
+        &self,
+        repo_name: &str,
+        path: Option<&str>,
+        git_ref: Option<&str>,
+    ) -> Result<Vec<RepositoryFile>, GitHubError> {
+        let path = path.unwrap_or("");
+        self.spend("contents", RateLimitResource::Core).await?;
 
-            // Extract repository data from response
-            let repos = response["data"]["organization"]["repositories"]["nodes"]
-                .as_array()
-                .ok_or_else(|| GitHubError::RequestFailed("Invalid GraphQL response format".to_string()))?;
+        let contents = self
+            .get_content(repo_name, path, git_ref)
+            .await?;
 
-            // Process each repository
-            for repo in repos {
-                let repo_name = repo["name"]
-                    .as_str()
-                    .ok_or_else(|| GitHubError::RequestFailed("Invalid repository name in response".to_string()))?;
+        let mut files = Vec::new();
+        for item in contents.items {
+            files.push(RepositoryFile {
+                path: item.path,
+                name: item.name,
+                size: Some(item.size as u64),
+                file_type: item.r#type,
+            });
+        }
 
-                // Check if the file exists (object will be null if file doesn't exist)
-                let file_exists = !repo["object"].is_null();
-                result.insert(repo_name.to_string(), file_exists);
-            }
+        Ok(files)
+    }
 
-            // Check if there are more pages
-            let has_next_page = response["data"]["organization"]["repositories"]["pageInfo"]["hasNextPage"]
-                .as_bool()
-                .unwrap_or(false);
+    async fn get_tree(
+        &self,
+        repo_name: &str,
+        git_ref: Option<&str>,
+    ) -> Result<RepositoryTree, GitHubError> {
+        Ok(self.get_git_tree(repo_name, git_ref).await?.into())
+    }
 
-            if !has_next_page {
-                break;
+    async fn batch_fetch_files(
+        &self,
+        repo_name: &str,
+        file_paths: &[String],
+        git_ref: Option<&str>,
+    ) -> Result<HashMap<String, Option<String>>, GitHubError> {
+        match &self.cache {
+            Some(cache) if !file_paths.is_empty() => {
+                self.fetch_files_through_cache(cache, repo_name, file_paths, git_ref)
+                    .await
             }
-
-            // Update cursor for next page
-            cursor = response["data"]["organization"]["repositories"]["pageInfo"]["endCursor"]
-                .as_str()
-                .map(|s| s.to_string());
+            _ => self.fetch_files(repo_name, file_paths, git_ref).await,
         }
-
-        Ok(result)
     }
 
-    async fn batch_fetch_config_file_content(&self) -> Result<Vec<RepositoryFileContent>, GitHubError> {
-        let mut result = Vec::new();
-        let mut cursor: Option<String> = None;
+    async fn batch_fetch_file_history(
+        &self,
+        repo_name: &str,
+        file_paths: &[String],
+        git_ref: Option<&str>,
+    ) -> Result<HashMap<String, FileHistory>, GitHubError> {
+        let mut result = HashMap::new();
+        let git_ref = git_ref.unwrap_or("HEAD");
+        // JSON string literals are valid GraphQL strings
+        let expression = serde_json::Value::from(git_ref);
+
+        for batch in self.create_file_batches(file_paths, HISTORY_BATCH_SIZE) {
+            let histories: Vec<String> = batch
+                .iter()
+                .enumerate()
+                .map(|(index, file_path)| {
+                    format!(
+                        r#"file{}: history(first: {}, path: {}) {{
+                          nodes {{
+                            oid
+                            committedDate
+                            author {{ name user {{ login }} }}
+                          }}
+                        }}"#,
+                        index,
+                        HISTORY_COMMITS,
+                        serde_json::Value::from(file_path.as_str())
+                    )
+                })
+                .collect();
 
-        // Handle pagination to get all repositories
-        loop {
-            // Create the GraphQL query for documents.toml file, including text content
             let query = format!(
                 r#"
                 query {{
-                  organization(login: "{org}") {{
-                    repositories(first: 100, after: {cursor}) {{
-                      pageInfo {{
-                        hasNextPage
-                        endCursor
-                      }}
-                      nodes {{
-                        name
-                        object(expression: "HEAD:documents.toml") {{
-                          ... on Blob {{
-                            id
-                            text
-                          }}
-                        }}
+                  repository(owner: "{org}", name: "{repo}") {{
+                    object(expression: {expression}) {{
+                      ... on Commit {{
+                        {histories}
                       }}
                     }}
                   }}
                 }}
                 "#,
                 org = self.organization,
-                cursor = match &cursor {
-                    Some(c) => format!("\"{}\"", c),
-                    None => "null".to_string()
-                }
+                repo = repo_name,
+                expression = expression,
+                histories = histories.join("\n                        ")
             );
 
-            let query = serde_json::json!({"query": &query});
-
-            // Execute the GraphQL query
-            let response = self.execute_graphql_with_retries(&query).await?;
-
-            // Extract repository data from response
-            let repos = response["data"]["organization"]["repositories"]["nodes"]
-                .as_array()
-                .ok_or_else(|| GitHubError::RequestFailed("Invalid GraphQL response format".to_string()))?;
-
-            // Process each repository
-            for repo in repos {
-                let repo_name = repo["name"]
-                    .as_str()
-                    .ok_or_else(|| GitHubError::RequestFailed("Invalid repository name in response".to_string()))?
-                    .to_string();
-
-                // Check if the file exists (object will be null if file doesn't exist)
-                let file_exists = !repo["object"].is_null();
-
-                // Get the file content if it exists
-                let content = if file_exists {
-                    repo["object"]["text"].as_str().map(|s| s.to_string())
-                } else {
-                    None
-                };
-
-                result.push(RepositoryFileContent {
-                    repo_name,
-                    exists: file_exists,
-                    content,
-                });
+            let response = self
+                .execute_graphql_with_retries(&serde_json::json!({ "query": query }))
+                .await?;
+            let commit = &response["data"]["repository"]["object"];
+            if commit.is_null() {
+                return Err(GitHubError::RefNotFound(format!("{} in {}", git_ref, repo_name)));
             }
 
-            // Check if there are more pages
-            let has_next_page = response["data"]["organization"]["repositories"]["pageInfo"]["hasNextPage"]
-                .as_bool()
-                .unwrap_or(false);
-
-            if !has_next_page {
-                break;
+            for (index, file_path) in batch.iter().enumerate() {
+                if let Some(history) = FileHistory::from_graphql(&commit[format!("file{}", index)]) {
+                    result.insert(file_path.clone(), history);
+                }
             }
-
-            // Update cursor for next page
-            cursor = response["data"]["organization"]["repositories"]["pageInfo"]["endCursor"]
-                .as_str()
-                .map(|s| s.to_string());
         }
 
         Ok(result)
     }
 
+    async fn batch_check_config_file_exists(&self) -> Result<HashMap<String, bool>, GitHubError> {
+        let repositories = self.batch_config_files(false).await?;
+
+        Ok(repositories
+            .into_iter()
+            .map(|repo| (repo.repo_name, repo.exists))
+            .collect())
+    }
+
+    async fn batch_fetch_config_file_content(&self) -> Result<Vec<RepositoryFileContent>, GitHubError> {
+        self.batch_config_files(true).await
+    }
+
     async fn batch_fetch_files_multi_repo(
         &self,
         repo_file_map: &HashMap<String, Vec<String>>,
@@ -692,7 +1650,7 @@ impl Client for GitHubClient {
         let mut result = HashMap::new();
 
         for (repo_name, file_paths) in repo_file_map {
-            let files = self.batch_fetch_files(repo_name, file_paths).await?;
+            let files = self.batch_fetch_files(repo_name, file_paths, None).await?;
             result.insert(repo_name.to_string(), files);
         }
 
@@ -706,7 +1664,7 @@ impl Client for GitHubClient {
         let mut result = HashMap::new();
 
         for (repo_name, files) in file_references {
-            let files = self.batch_fetch_files(repo_name, files).await?;
+            let files = self.batch_fetch_files(repo_name, files, None).await?;
 
             // Convert content results to existence checks
             let existence_map: HashMap<String, bool> = files
@@ -723,29 +1681,90 @@ impl Client for GitHubClient {
 
 #[cfg(any(test, feature = "integration-tests"))]
 pub mod tests {
-    use crate::github::{Client, GitHubError, RepositoryFile, RepositoryFileContent};
+    use crate::github::{
+        Client, FileHistory, GitHubError, RateLimit, RepositoryFile, RepositoryFileContent,
+        RepositoryTree,
+    };
     use async_trait::async_trait;
     use std::collections::HashMap;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
-    // Mock implementation of the Client trait for testing
-    pub struct MockGitHubClient {
+    /// Name of the default branch of the mock repository.
+    pub const DEFAULT_BRANCH: &str = "main";
+
+    /// The files of the mock repository at one ref.
+    #[derive(Default)]
+    struct MockRef {
+        sha: String,
         file_contents: HashMap<String, String>,
         files: Vec<RepositoryFile>,
+        history: HashMap<String, FileHistory>,
+    }
+
+    // Mock implementation of the Client trait for testing
+    pub struct MockGitHubClient {
+        refs: HashMap<String, MockRef>,
+        tree_truncated: bool,
+        tree_requests: AtomicUsize,
+        listing_requests: AtomicUsize,
     }
 
     impl MockGitHubClient {
         pub fn new() -> Self {
-            Self {
-                file_contents: HashMap::new(),
-                files: Vec::new(),
-            }
+            let mut client = Self {
+                refs: HashMap::new(),
+                tree_truncated: false,
+                tree_requests: AtomicUsize::new(0),
+                listing_requests: AtomicUsize::new(0),
+            };
+            client.add_ref(DEFAULT_BRANCH);
+            client
+        }
+
+        /// Make `get_tree` report its trees as truncated, like GitHub does for huge repositories.
+        pub fn set_tree_truncated(&mut self, truncated: bool) {
+            self.tree_truncated = truncated;
+        }
+
+        /// Number of `get_tree` calls so far.
+        pub fn tree_requests(&self) -> usize {
+            self.tree_requests.load(Ordering::SeqCst)
+        }
+
+        /// Number of `list_repository_files` calls so far.
+        pub fn listing_requests(&self) -> usize {
+            self.listing_requests.load(Ordering::SeqCst)
+        }
+
+        /// Add an empty branch or tag with a made-up commit SHA, unless it already exists.
+        pub fn add_ref(&mut self, git_ref: &str) {
+            let sha = format!("{:0>40}", self.refs.len() + 1);
+            self.refs.entry(git_ref.to_string()).or_insert(MockRef {
+                sha,
+                ..MockRef::default()
+            });
+        }
+
+        /// The fixtures at a branch, tag or commit SHA, or the default branch.
+        fn at(&self, git_ref: Option<&str>) -> Option<&MockRef> {
+            let git_ref = git_ref.unwrap_or(DEFAULT_BRANCH);
+            self.refs
+                .get(git_ref)
+                .or_else(|| self.refs.values().find(|mock_ref| mock_ref.sha == git_ref))
         }
 
         pub fn add_file(&mut self, path: &str, content: &str) {
-            self.file_contents
+            self.add_file_at(DEFAULT_BRANCH, path, content);
+        }
+
+        pub fn add_file_at(&mut self, git_ref: &str, path: &str, content: &str) {
+            self.add_ref(git_ref);
+            let mock_ref = self.refs.get_mut(git_ref).unwrap();
+            mock_ref
+                .file_contents
                 .insert(path.to_string(), content.to_string());
-            self.files.push(RepositoryFile {
+            mock_ref.files.push(RepositoryFile {
                 path: path.to_string(),
                 name: path.split('/').last().unwrap_or(path).to_string(),
                 size: Some(content.len() as u64),
@@ -754,13 +1773,32 @@ pub mod tests {
         }
 
         pub fn add_directory(&mut self, path: &str) {
-            self.files.push(RepositoryFile {
+            self.add_directory_at(DEFAULT_BRANCH, path);
+        }
+
+        /// Set the latest commit of a file on the default branch.
+        pub fn set_history(&mut self, path: &str, history: FileHistory) {
+            self.refs
+                .get_mut(DEFAULT_BRANCH)
+                .unwrap()
+                .history
+                .insert(path.to_string(), history);
+        }
+
+        pub fn add_directory_at(&mut self, git_ref: &str, path: &str) {
+            self.add_ref(git_ref);
+            self.refs.get_mut(git_ref).unwrap().files.push(RepositoryFile {
                 path: path.to_string(),
                 name: path.split('/').last().unwrap_or(path).to_string(),
                 size: None,
                 file_type: "dir".to_string(),
             });
         }
+
+        fn content(&self, file_path: &str, git_ref: Option<&str>) -> Option<&String> {
+            self.at(git_ref)
+                .and_then(|mock_ref| mock_ref.file_contents.get(file_path))
+        }
     }
 
     #[async_trait]
@@ -769,10 +1807,26 @@ pub mod tests {
             Ok("test-user".to_string())
         }
 
+        fn auth_mode(&self) -> String {
+            "personal access token".to_string()
+        }
+
+        fn organization(&self) -> &str {
+            "test-org"
+        }
+
         async fn handle_rate_limits(&self) -> Result<(), GitHubError> {
             Ok(())
         }
 
+        async fn rate_limit(&self) -> Result<RateLimit, GitHubError> {
+            Ok(RateLimit {
+                limit: 5000,
+                remaining: 5000,
+                reset_at: chrono::Utc::now() + chrono::Duration::hours(1),
+            })
+        }
+
         async fn repositories(&self) -> Result<Vec<String>, GitHubError> {
             Ok(vec!["test-repo".to_string()])
         }
@@ -784,13 +1838,39 @@ pub mod tests {
             Ok(Some("documents.toml".to_string()))
         }
 
-        async fn read_config_file(&self, _repo_name: &str) -> Result<String, GitHubError> {
-            Ok("[project]\nname = \"Test Project\"\ndescription = \"A test project\"".to_string())
+        async fn resolve_ref(
+            &self,
+            repo_name: &str,
+            git_ref: Option<&str>,
+        ) -> Result<String, GitHubError> {
+            self.at(git_ref)
+                .map(|mock_ref| mock_ref.sha.clone())
+                .ok_or_else(|| {
+                    GitHubError::RefNotFound(format!(
+                        "{} in {}",
+                        git_ref.unwrap_or(DEFAULT_BRANCH),
+                        repo_name
+                    ))
+                })
+        }
+
+        async fn read_config_file(
+            &self,
+            _repo_name: &str,
+            git_ref: Option<&str>,
+        ) -> Result<String, GitHubError> {
+            Ok(self
+                .content("documents.toml", git_ref)
+                .cloned()
+                .unwrap_or_else(|| {
+                    "[project]\nname = \"Test Project\"\ndescription = \"A test project\"".to_string()
+                }))
         }
 
         async fn get_project_config(
             &self,
             _repo_name: &str,
+            _git_ref: Option<&str>,
         ) -> Result<crate::ProjectConfig, GitHubError> {
             let mut documents = HashMap::new();
             documents.insert(
@@ -815,8 +1895,9 @@ pub mod tests {
             &self,
             _repo_name: &str,
             file_path: &str,
+            git_ref: Option<&str>,
         ) -> Result<String, GitHubError> {
-            match self.file_contents.get(file_path) {
+            match self.content(file_path, git_ref) {
                 Some(content) => Ok(content.clone()),
                 None => Err(GitHubError::FileNotFound(format!(
                     "File not found: {}",
@@ -829,19 +1910,23 @@ pub mod tests {
             &self,
             _repo_name: &str,
             file_path: &str,
+            git_ref: Option<&str>,
         ) -> Result<bool, GitHubError> {
-            Ok(self.file_contents.contains_key(file_path))
+            Ok(self.content(file_path, git_ref).is_some())
         }
 
         async fn list_repository_files(
             &self,
             _: &str,
             path: Option<&str>,
+            git_ref: Option<&str>,
         ) -> Result<Vec<RepositoryFile>, GitHubError> {
+            self.listing_requests.fetch_add(1, Ordering::SeqCst);
             let search_path = path.unwrap_or("");
             let mut result = Vec::new();
+            let files = self.at(git_ref).map(|mock_ref| mock_ref.files.as_slice());
 
-            for file in &self.files {
+            for file in files.unwrap_or_default() {
                 let file_dir = if file.path.contains('/') {
                     file.path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
                 } else {
@@ -856,21 +1941,55 @@ pub mod tests {
             Ok(result)
         }
 
+        async fn get_tree(
+            &self,
+            repo_name: &str,
+            git_ref: Option<&str>,
+        ) -> Result<RepositoryTree, GitHubError> {
+            self.tree_requests.fetch_add(1, Ordering::SeqCst);
+            let sha = self.resolve_ref(repo_name, git_ref).await?;
+            let files = self.at(git_ref).map(|mock_ref| mock_ref.files.clone());
+
+            Ok(RepositoryTree {
+                sha,
+                files: files.unwrap_or_default(),
+                truncated: self.tree_truncated,
+            })
+        }
+
         async fn batch_fetch_files(
             &self,
             _repo_name: &str,
             file_paths: &[String],
+            git_ref: Option<&str>,
         ) -> Result<HashMap<String, Option<String>>, GitHubError> {
             let mut result = HashMap::new();
 
             for file_path in file_paths {
-                let content = self.file_contents.get(file_path).cloned();
+                let content = self.content(file_path, git_ref).cloned();
                 result.insert(file_path.clone(), content);
             }
 
             Ok(result)
         }
 
+        async fn batch_fetch_file_history(
+            &self,
+            _repo_name: &str,
+            file_paths: &[String],
+            git_ref: Option<&str>,
+        ) -> Result<HashMap<String, FileHistory>, GitHubError> {
+            let history = self.at(git_ref).map(|mock_ref| &mock_ref.history);
+
+            Ok(file_paths
+                .iter()
+                .filter_map(|path| {
+                    let file_history = history?.get(path)?;
+                    Some((path.clone(), file_history.clone()))
+                })
+                .collect())
+        }
+
         async fn batch_check_config_file_exists(&self) -> Result<HashMap<String, bool>, GitHubError> {
             let mut result = HashMap::new();
 
@@ -897,7 +2016,7 @@ pub mod tests {
             let mut result = HashMap::new();
 
             for (repo_name, file_paths) in repo_file_map {
-                let files = self.batch_fetch_files(repo_name, file_paths).await?;
+                let files = self.batch_fetch_files(repo_name, file_paths, None).await?;
                 result.insert(repo_name.clone(), files);
             }
 
@@ -913,7 +2032,7 @@ pub mod tests {
             for (repo_name, files) in file_references {
                 let mut existence_map = HashMap::new();
                 for file in files {
-                    existence_map.insert(file.clone(), self.file_contents.contains_key(file));
+                    existence_map.insert(file.clone(), self.content(file, None).is_some());
                 }
                 result.insert(repo_name.clone(), existence_map);
             }
@@ -950,7 +2069,7 @@ mod batch_content_tests {
         client.add_file("docs/file2.md", "Content of file 2");
 
         let file_paths = vec!["docs/file1.md".to_string(), "docs/file2.md".to_string(), "docs/missing.md".to_string()];
-        let results = client.batch_fetch_files("test-repo", &file_paths).await.unwrap();
+        let results = client.batch_fetch_files("test-repo", &file_paths, None).await.unwrap();
 
         assert_eq!(results.len(), 3);
         assert_eq!(results.get("docs/file1.md").unwrap().as_deref(), Some("Content of file 1"));
@@ -958,6 +2077,32 @@ mod batch_content_tests {
         assert!(results.get("docs/file3.md").is_none());
     }
 
+    #[tokio::test]
+    async fn test_files_at_refs() {
+        let mut client = MockGitHubClient::new();
+        client.add_file("docs/guide.md", "Current guide");
+        client.add_file_at("v1.0", "docs/guide.md", "Guide for 1.0");
+
+        let paths = vec!["docs/guide.md".to_string()];
+        let current = client.batch_fetch_files("test-repo", &paths, None).await.unwrap();
+        assert_eq!(current["docs/guide.md"].as_deref(), Some("Current guide"));
+
+        let tagged = client.batch_fetch_files("test-repo", &paths, Some("v1.0")).await.unwrap();
+        assert_eq!(tagged["docs/guide.md"].as_deref(), Some("Guide for 1.0"));
+
+        // A commit SHA reads the same files as the ref it was resolved from
+        let sha = client.resolve_ref("test-repo", Some("v1.0")).await.unwrap();
+        assert_ne!(sha, client.resolve_ref("test-repo", None).await.unwrap());
+        let content = client.get_file_content("test-repo", "docs/guide.md", Some(&sha)).await;
+        assert_eq!(content.unwrap(), "Guide for 1.0");
+
+        assert!(matches!(
+            client.resolve_ref("test-repo", Some("missing")).await,
+            Err(GitHubError::RefNotFound(_))
+        ));
+        assert!(!client.file_exists("test-repo", "docs/guide.md", Some("missing")).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_batch_validate_referenced_files() {
         let mut client = MockGitHubClient::new();
@@ -976,3 +2121,437 @@ mod batch_content_tests {
         assert_eq!(existence_map.get("docs/missing.md").unwrap(), &false);
     }
 }
+
+#[cfg(test)]
+mod repository_filter_tests {
+    use super::*;
+
+    fn repo(archived: bool, fork: bool, visibility: Visibility, topics: &[&str]) -> RepositoryAttributes {
+        RepositoryAttributes {
+            name: "repo".to_string(),
+            archived,
+            fork,
+            visibility,
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_filter_matches_everything() {
+        let filter = RepositoryFilter::default();
+
+        assert!(filter.matches(&repo(true, true, Visibility::Private, &[])));
+        assert!(filter.matches(&repo(false, false, Visibility::Public, &["docs"])));
+    }
+
+    #[test]
+    fn test_filter_excludes_repositories() {
+        let filter = RepositoryFilter {
+            skip_archived: true,
+            skip_forks: true,
+            visibility: vec![Visibility::Public, Visibility::Internal],
+            topics: vec!["documentation".to_string(), "docs".to_string()],
+            excluded_topics: vec!["deprecated".to_string()],
+        };
+
+        assert!(filter.matches(&repo(false, false, Visibility::Public, &["docs"])));
+        assert!(filter.matches(&repo(false, false, Visibility::Internal, &["Documentation"])));
+        assert!(!filter.matches(&repo(true, false, Visibility::Public, &["docs"])));
+        assert!(!filter.matches(&repo(false, true, Visibility::Public, &["docs"])));
+        assert!(!filter.matches(&repo(false, false, Visibility::Private, &["docs"])));
+        assert!(!filter.matches(&repo(false, false, Visibility::Public, &["rust"])));
+        assert!(!filter.matches(&repo(false, false, Visibility::Public, &["docs", "deprecated"])));
+    }
+
+    #[test]
+    fn test_attributes_from_graphql() {
+        let node = serde_json::json!({
+            "name": "handbook",
+            "isArchived": true,
+            "isFork": false,
+            "visibility": "INTERNAL",
+            "repositoryTopics": {
+                "nodes": [
+                    {"topic": {"name": "docs"}},
+                    {"topic": {"name": "handbook"}}
+                ]
+            },
+            "object": null
+        });
+
+        assert_eq!(
+            RepositoryAttributes::from_graphql(&node).unwrap(),
+            repo_named("handbook", true, false, Visibility::Internal, &["docs", "handbook"])
+        );
+        assert!(RepositoryAttributes::from_graphql(&serde_json::json!({})).is_err());
+    }
+
+    fn repo_named(
+        name: &str,
+        archived: bool,
+        fork: bool,
+        visibility: Visibility,
+        topics: &[&str],
+    ) -> RepositoryAttributes {
+        RepositoryAttributes {
+            name: name.to_string(),
+            ..repo(archived, fork, visibility, topics)
+        }
+    }
+
+    #[test]
+    fn test_tree_from_response() {
+        let tree: GitTree = serde_json::from_value(serde_json::json!({
+            "sha": "abc",
+            "url": "https://api.github.com/repos/o/r/git/trees/abc",
+            "tree": [
+                {"path": "README.md", "mode": "100644", "type": "blob", "sha": "1", "size": 12},
+                {"path": "docs", "mode": "040000", "type": "tree", "sha": "2"},
+                {"path": "docs/guide.md", "mode": "100644", "type": "blob", "sha": "3", "size": 5},
+                {"path": "vendor/lib", "mode": "160000", "type": "commit", "sha": "4"}
+            ],
+            "truncated": false
+        }))
+        .unwrap();
+
+        let tree = RepositoryTree::from(tree);
+        assert_eq!(tree.sha, "abc");
+        assert!(!tree.truncated);
+        let files: Vec<_> = tree
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.name.as_str(), file.file_type.as_str()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("README.md", "README.md", "file"),
+                ("docs", "docs", "dir"),
+                ("docs/guide.md", "guide.md", "file"),
+            ]
+        );
+        assert_eq!(tree.files[2].size, Some(5));
+    }
+
+    #[test]
+    fn test_file_history_from_graphql() {
+        let history = serde_json::json!({
+            "nodes": [
+                {
+                    "oid": "c3",
+                    "committedDate": "2024-05-01T10:00:00Z",
+                    "author": {"name": "Ada", "user": {"login": "ada"}}
+                },
+                {
+                    "oid": "c2",
+                    "committedDate": "2024-04-01T10:00:00Z",
+                    "author": {"name": "Grace", "user": null}
+                },
+                {
+                    "oid": "c1",
+                    "committedDate": "2024-03-01T10:00:00Z",
+                    "author": {"name": "Ada Lovelace", "user": {"login": "Ada"}}
+                }
+            ]
+        });
+
+        assert_eq!(
+            FileHistory::from_graphql(&history),
+            Some(FileHistory {
+                last_modified: "2024-05-01T10:00:00Z".parse().unwrap(),
+                commit_sha: "c3".to_string(),
+                author: Some("ada".to_string()),
+                contributors: 2,
+            })
+        );
+        assert_eq!(FileHistory::from_graphql(&serde_json::json!({"nodes": []})), None);
+        assert_eq!(FileHistory::from_graphql(&serde_json::Value::Null), None);
+    }
+
+    #[tokio::test]
+    async fn test_worker_clients_share_the_budget() {
+        let state = crate::web::test_state(
+            crate::Database::connect_lazy("postgres://localhost/test").unwrap(),
+        )
+        .await;
+        let interactive = state.github.get("test-org").unwrap();
+        let background = state.github.with_priority(Priority::Background);
+        let background = background.get("test-org").unwrap();
+        assert_eq!(interactive.priority, Priority::Interactive);
+        assert_eq!(background.priority, Priority::Background);
+
+        let reset_at = chrono::Utc::now() + chrono::Duration::minutes(10);
+        interactive.budget.seed(
+            RateLimitResource::Core,
+            Quota {
+                limit: 5000,
+                remaining: RATE_LIMIT_RESERVE + 1,
+                reset_at,
+            },
+        );
+        background
+            .spend("contents", RateLimitResource::Core)
+            .await
+            .unwrap();
+        assert!(matches!(
+            background.spend("contents", RateLimitResource::Core).await,
+            Err(GitHubError::RateLimitExceeded { reset_at: at }) if at == reset_at
+        ));
+        interactive
+            .spend("contents", RateLimitResource::Core)
+            .await
+            .unwrap();
+
+        let rate_limit = interactive.rate_limit().await.unwrap();
+        assert_eq!(rate_limit.remaining, RATE_LIMIT_RESERVE - 1);
+        assert_eq!(rate_limit.reset_at, reset_at);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_files_are_served_from_the_http_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let client = GitHubClient {
+            client: OctocrabBuilder::new()
+                .base_uri(server.url())
+                .unwrap()
+                .personal_token("token".to_string())
+                .build()
+                .unwrap(),
+            organization: "test-org".to_string(),
+            repository_filter: RepositoryFilter::default(),
+            app: None,
+            auth_mode: "personal access token".to_string(),
+            budget: Arc::new(RateLimitBudget::new(RATE_LIMIT_RESERVE)),
+            priority: Priority::Interactive,
+            cache: Some(HttpCache::new(cache_dir.path())),
+        };
+        let reset_at = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp() + 600, 0)
+            .unwrap();
+        client.budget.seed(
+            RateLimitResource::Core,
+            Quota {
+                limit: 5000,
+                remaining: 5000,
+                reset_at,
+            },
+        );
+
+        let route = "/repos/test-org/test-repo/contents/README.md";
+        let body = serde_json::json!({
+            "name": "README.md",
+            "path": "README.md",
+            "sha": "abc",
+            "encoding": "base64",
+            "content": "aGVsbG8=",
+            "size": 5,
+            "url": format!("{}{}", server.url(), route),
+            "type": "file",
+            "_links": {"self": format!("{}{}", server.url(), route)},
+        });
+        let fetched = server
+            .mock("GET", route)
+            .match_query(mockito::Matcher::UrlEncoded("ref".into(), "v1".into()))
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_header("x-ratelimit-resource", "core")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "4999")
+            .with_header("x-ratelimit-reset", &reset_at.timestamp().to_string())
+            .with_body(body.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let revalidated = server
+            .mock("GET", route)
+            .match_query(mockito::Matcher::UrlEncoded("ref".into(), "v1".into()))
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .with_header("x-ratelimit-resource", "core")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "4999")
+            .with_header("x-ratelimit-reset", &reset_at.timestamp().to_string())
+            .expect(2)
+            .create_async()
+            .await;
+
+        for _ in 0..3 {
+            let content = client
+                .get_file_content("test-repo", "README.md", Some("v1"))
+                .await
+                .unwrap();
+            assert_eq!(content, "hello");
+        }
+        fetched.assert_async().await;
+        revalidated.assert_async().await;
+
+        // Only the first request counted against the rate limit
+        let quota = client.budget.quota(RateLimitResource::Core).unwrap();
+        assert_eq!(quota.remaining, 4999);
+        let entries = client.cache.as_ref().unwrap().entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].route, format!("{}?ref=v1", route));
+    }
+
+    #[test]
+    fn test_parse_visibility() {
+        assert_eq!("PUBLIC".parse(), Ok(Visibility::Public));
+        assert_eq!(" private ".parse(), Ok(Visibility::Private));
+        assert!("secret".parse::<Visibility>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refs_and_paths_are_escaped() {
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let files = server
+            .mock("POST", "/graphql")
+            .match_body(Matcher::Regex(regex::escape(
+                r#"object(expression: \"v1\\\"){x}:docs/\\\"a\\\".md\")"#,
+            )))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data": {"repository": {"file0": {"id": "b1", "text": "Text"}}}}"#)
+            .create_async()
+            .await;
+        let tree = server
+            .mock("GET", "/repos/test-org/test-repo/git/trees/feature%2Fa%3Fb")
+            .match_query(Matcher::UrlEncoded("recursive".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"sha": "t1", "truncated": false, "tree": []}"#)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_uri("test-org", &server.url());
+        let paths = vec![r#"docs/"a".md"#.to_string()];
+        let fetched = client
+            .fetch_files("test-repo", &paths, Some(r#"v1"){x}"#))
+            .await
+            .unwrap();
+        assert_eq!(fetched[&paths[0]].as_deref(), Some("Text"));
+        let listed = client
+            .get_git_tree("test-repo", Some("feature/a?b"))
+            .await
+            .unwrap();
+        assert_eq!(listed.sha, "t1");
+
+        files.assert_async().await;
+        tree.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_cached_files_are_read_at_the_resolved_commit() {
+        use mockito::Matcher;
+
+        const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+        let mut server = mockito::Server::new_async().await;
+        let commit = serde_json::json!({"data": {"repository": {"object": {"oid": COMMIT}}}});
+        let resolved = server
+            .mock("POST", "/graphql")
+            .match_body(Matcher::Regex(regex::escape("Commit { oid }")))
+            .with_header("content-type", "application/json")
+            .with_body(commit.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let tree_route = format!("/repos/test-org/test-repo/git/trees/{}", COMMIT);
+        let listing = serde_json::json!({
+            "sha": "t1",
+            "truncated": false,
+            "tree": [{"path": "docs/a.md", "type": "blob", "sha": "b1"}]
+        });
+        let tree = server
+            .mock("GET", tree_route.as_str())
+            .match_query(Matcher::UrlEncoded("recursive".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_body(listing.to_string())
+            .create_async()
+            .await;
+        // The branch may have moved since it was resolved, so it is not read by name
+        let expression = format!("{}:docs/a.md", COMMIT);
+        let files = server
+            .mock("POST", "/graphql")
+            .match_body(Matcher::Regex(regex::escape(&expression)))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data": {"repository": {"file0": {"id": "b1", "text": "Text"}}}}"#)
+            .create_async()
+            .await;
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let client = GitHubClient::with_base_uri("test-org", &server.url())
+            .with_http_cache(HttpCache::new(cache_dir.path()));
+        let paths = vec!["docs/a.md".to_string()];
+        let fetched = client
+            .batch_fetch_files("test-repo", &paths, Some("main"))
+            .await
+            .unwrap();
+        assert_eq!(fetched["docs/a.md"].as_deref(), Some("Text"));
+
+        resolved.assert_async().await;
+        tree.assert_async().await;
+        files.assert_async().await;
+    }
+
+    #[sqlx::test]
+    async fn test_clients_sharing_a_credential_share_a_budget(pool: sqlx::PgPool) {
+        let state = crate::web::test_state(crate::database::Database::from_pool(pool)).await;
+        let organization = |name: &str, token: &str| OrganizationConfig {
+            name: name.to_string(),
+            auth: GitHubAuth::Token(token.to_string()),
+        };
+        let mut config = (*state.config).clone();
+        config.github_organizations = vec![
+            organization("org-a", "shared"),
+            organization("org-b", "shared"),
+            organization("org-c", "other"),
+        ];
+
+        let clients = GitHubClients::new(&config).await.unwrap();
+        let budgets: Vec<&Arc<RateLimitBudget>> =
+            clients.iter().map(|client| &client.budget).collect();
+
+        assert!(Arc::ptr_eq(budgets[0], budgets[1]));
+        assert!(!Arc::ptr_eq(budgets[0], budgets[2]));
+    }
+
+    #[tokio::test]
+    async fn test_repository_listing_records_rate_limit_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let reset_at = chrono::Utc::now().timestamp() + 600;
+        let next = format!(
+            "<{}/organizations/1/repos?page=2>; rel=\"next\"",
+            server.url()
+        );
+        let first = server
+            .mock("GET", "/orgs/test-org/repos")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "per_page".into(),
+                REPOSITORIES_PER_PAGE.to_string(),
+            ))
+            .with_header("link", &next)
+            .with_header("x-ratelimit-resource", "core")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "1235")
+            .with_header("x-ratelimit-reset", &reset_at.to_string())
+            .with_body("[]")
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", "/organizations/1/repos")
+            .match_query(mockito::Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_header("x-ratelimit-resource", "core")
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "1234")
+            .with_header("x-ratelimit-reset", &reset_at.to_string())
+            .with_body("[]")
+            .create_async()
+            .await;
+        let client = GitHubClient::with_base_uri("test-org", &server.url());
+
+        assert!(client.repositories().await.unwrap().is_empty());
+
+        first.assert_async().await;
+        second.assert_async().await;
+        assert_eq!(client.rate_limit().await.unwrap().remaining, 1234);
+    }
+}
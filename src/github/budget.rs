@@ -0,0 +1,281 @@
+//! The rate limit budget shared by every clone of a `GitHubClient`.
+//!
+//! GitHub counts REST, GraphQL and search requests against separate quotas. The budget keeps
+//! an estimate of each one: it is seeded from `/rate_limit` once per window, spent before
+//! every request, and corrected from the `x-ratelimit-*` headers of responses. Background
+//! requests leave a reserve of each quota to interactive ones, so that a long job cannot use
+//! up the requests an API caller is waiting on.
+
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A quota GitHub tracks separately from the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitResource {
+    /// The REST API
+    Core,
+    GraphQL,
+    Search,
+}
+
+impl RateLimitResource {
+    /// The resource named by an `x-ratelimit-resource` header.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "core" => Some(Self::Core),
+            "graphql" => Some(Self::GraphQL),
+            "search" => Some(Self::Search),
+            _ => None,
+        }
+    }
+}
+
+/// Who a request is made for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    /// Someone is waiting on the answer, such as an API caller or a CLI command.
+    #[default]
+    Interactive,
+    /// Jobs run by the worker, which are rescheduled when their share of a quota runs out.
+    Background,
+}
+
+/// What is known about one quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset_at: DateTime<Utc>,
+}
+
+/// The answer to asking the budget for a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spend {
+    /// The request is counted and can be made.
+    Granted,
+    /// Nothing is known about the quota in its current window; seed it and ask again.
+    Unknown,
+    /// Nothing is left for requests of this priority until the quota resets.
+    Exhausted { reset_at: DateTime<Utc> },
+}
+
+#[derive(Debug)]
+pub struct RateLimitBudget {
+    quotas: Mutex<HashMap<RateLimitResource, Quota>>,
+    /// Requests of each quota that background requests leave to interactive ones
+    reserve: u64,
+    /// Held while seeding, so that concurrent requests read `/rate_limit` once
+    pub(super) seeding: tokio::sync::Mutex<()>,
+}
+
+impl RateLimitBudget {
+    pub fn new(reserve: u64) -> Self {
+        Self {
+            quotas: Mutex::new(HashMap::new()),
+            reserve,
+            seeding: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Count one request against `resource` if enough of it is left for `priority`.
+    pub fn spend(
+        &self,
+        resource: RateLimitResource,
+        priority: Priority,
+        now: DateTime<Utc>,
+    ) -> Spend {
+        let mut quotas = self.quotas.lock().unwrap();
+        let Some(quota) = quotas
+            .get_mut(&resource)
+            .filter(|quota| quota.reset_at > now)
+        else {
+            return Spend::Unknown;
+        };
+
+        // Small quotas, such as search, keep a proportionate reserve
+        let floor = match priority {
+            Priority::Interactive => 0,
+            Priority::Background => self.reserve.min(quota.limit / 10),
+        };
+        if quota.remaining > floor {
+            quota.remaining -= 1;
+            Spend::Granted
+        } else {
+            Spend::Exhausted {
+                reset_at: quota.reset_at,
+            }
+        }
+    }
+
+    /// Give back a request GitHub did not count, such as one answered with 304.
+    pub fn refund(&self, resource: RateLimitResource) {
+        if let Some(quota) = self.quotas.lock().unwrap().get_mut(&resource) {
+            quota.remaining = (quota.remaining + 1).min(quota.limit);
+        }
+    }
+
+    /// Whether `resource` has to be seeded before it can be spent.
+    pub fn needs_seeding(&self, resource: RateLimitResource, now: DateTime<Utc>) -> bool {
+        self.quota(resource)
+            .is_none_or(|quota| quota.reset_at <= now)
+    }
+
+    /// Replace what is known about a quota with GitHub's own figures.
+    pub fn seed(&self, resource: RateLimitResource, quota: Quota) {
+        self.quotas.lock().unwrap().insert(resource, quota);
+    }
+
+    /// Correct a quota from the `x-ratelimit-*` headers of a response, returning the quota
+    /// they describe. Responses without them, such as some errors, change nothing.
+    pub fn update_from_headers(&self, headers: &HeaderMap) -> Option<RateLimitResource> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let number = |name: &str| header(name).and_then(|value| value.parse::<u64>().ok());
+
+        let resource = RateLimitResource::from_name(header("x-ratelimit-resource")?)?;
+        let quota = Quota {
+            limit: number("x-ratelimit-limit")?,
+            remaining: number("x-ratelimit-remaining")?,
+            reset_at: DateTime::from_timestamp(number("x-ratelimit-reset")? as i64, 0)?,
+        };
+
+        let mut quotas = self.quotas.lock().unwrap();
+        match quotas.get_mut(&resource) {
+            // Responses to concurrent requests arrive in any order, and requests still in
+            // flight have already been spent, so within a window the lowest count is kept
+            Some(known) if known.reset_at == quota.reset_at => {
+                known.limit = quota.limit;
+                known.remaining = known.remaining.min(quota.remaining);
+            }
+            _ => {
+                quotas.insert(resource, quota);
+            }
+        }
+        Some(resource)
+    }
+
+    /// The current estimate of a quota.
+    pub fn quota(&self, resource: RateLimitResource) -> Option<Quota> {
+        self.quotas.lock().unwrap().get(&resource).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn quota(limit: u64, remaining: u64, reset_at: DateTime<Utc>) -> Quota {
+        Quota {
+            limit,
+            remaining,
+            reset_at,
+        }
+    }
+
+    #[test]
+    fn test_background_requests_leave_a_reserve() {
+        let now = Utc::now();
+        let reset_at = now + Duration::minutes(30);
+        let budget = RateLimitBudget::new(100);
+        budget.seed(RateLimitResource::Core, quota(5000, 102, reset_at));
+        budget.seed(RateLimitResource::Search, quota(30, 4, reset_at));
+
+        let core = RateLimitResource::Core;
+        assert_eq!(
+            budget.spend(core, Priority::Background, now),
+            Spend::Granted
+        );
+        assert_eq!(
+            budget.spend(core, Priority::Background, now),
+            Spend::Granted
+        );
+        assert_eq!(
+            budget.spend(core, Priority::Background, now),
+            Spend::Exhausted { reset_at }
+        );
+        assert_eq!(budget.quota(core).unwrap().remaining, 100);
+        budget.refund(core);
+        assert_eq!(
+            budget.spend(core, Priority::Background, now),
+            Spend::Granted
+        );
+
+        // Interactive requests spend the reserve, down to the last request
+        for _ in 0..100 {
+            assert_eq!(
+                budget.spend(core, Priority::Interactive, now),
+                Spend::Granted
+            );
+        }
+        assert_eq!(
+            budget.spend(core, Priority::Interactive, now),
+            Spend::Exhausted { reset_at }
+        );
+
+        // Quotas are spent separately, and a search reserve is a tenth of its limit
+        let search = RateLimitResource::Search;
+        assert_eq!(
+            budget.spend(search, Priority::Background, now),
+            Spend::Granted
+        );
+        assert_eq!(
+            budget.spend(search, Priority::Background, now),
+            Spend::Exhausted { reset_at }
+        );
+        assert_eq!(
+            budget.spend(RateLimitResource::GraphQL, Priority::Interactive, now),
+            Spend::Unknown
+        );
+    }
+
+    #[test]
+    fn test_quotas_are_seeded_again_after_they_reset() {
+        let now = Utc::now();
+        let budget = RateLimitBudget::new(100);
+        budget.seed(RateLimitResource::Core, quota(5000, 0, now));
+
+        assert!(budget.needs_seeding(RateLimitResource::Core, now));
+        assert_eq!(
+            budget.spend(RateLimitResource::Core, Priority::Interactive, now),
+            Spend::Unknown
+        );
+        assert!(!budget.needs_seeding(RateLimitResource::Core, now - Duration::seconds(1)));
+    }
+
+    #[test]
+    fn test_headers_correct_the_estimate() {
+        let reset_at = DateTime::from_timestamp(Utc::now().timestamp() + 600, 0).unwrap();
+        let headers = |resource: &str, remaining: u64, reset_at: DateTime<Utc>| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-resource", resource.parse().unwrap());
+            headers.insert("x-ratelimit-limit", "5000".parse().unwrap());
+            headers.insert("x-ratelimit-remaining", remaining.into());
+            headers.insert("x-ratelimit-reset", reset_at.timestamp().into());
+            headers
+        };
+
+        let budget = RateLimitBudget::new(100);
+        let graphql = RateLimitResource::GraphQL;
+        assert_eq!(
+            budget.update_from_headers(&headers("graphql", 4000, reset_at)),
+            Some(graphql)
+        );
+        assert_eq!(budget.quota(graphql), Some(quota(5000, 4000, reset_at)));
+
+        // A response that was overtaken by later ones does not raise the estimate
+        budget.update_from_headers(&headers("graphql", 4500, reset_at));
+        assert_eq!(budget.quota(graphql).unwrap().remaining, 4000);
+        budget.update_from_headers(&headers("graphql", 3000, reset_at));
+        assert_eq!(budget.quota(graphql).unwrap().remaining, 3000);
+
+        // A new window replaces the old one
+        let next = reset_at + Duration::hours(1);
+        budget.update_from_headers(&headers("graphql", 4999, next));
+        assert_eq!(budget.quota(graphql), Some(quota(5000, 4999, next)));
+
+        assert_eq!(budget.update_from_headers(&HeaderMap::new()), None);
+        assert_eq!(budget.quota(RateLimitResource::Core), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! An on-disk cache of GitHub REST responses, revalidated with conditional requests.
+//!
+//! A response that carries an `ETag` or `Last-Modified` header is stored in the cache
+//! directory, in a file named after a hash of its route. The next request for the route
+//! sends `If-None-Match` or `If-Modified-Since`, and a `304 Not Modified` answer is served
+//! from the stored body. GitHub does not count 304s against the rate limit, so re-reading
+//! files that have not changed costs nothing.
+//!
+//! File contents read with GraphQL are stored by blob SHA. A blob never changes, so once a
+//! tree names a blob that is in the cache, its content is served without a request.
+//!
+//! Entries hold file contents of private repositories, so they are only readable by their
+//! owner.
+
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+use http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Extension of the files the cache writes, so that clearing it leaves other files alone.
+const ENTRY_EXTENSION: &str = "json";
+
+/// A stored response and the validators GitHub sent with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// The route requested, including its query
+    pub route: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: DateTime<Utc>,
+    pub body: String,
+}
+
+impl CachedResponse {
+    /// The headers that ask GitHub whether this response is still current.
+    pub fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self.etag.as_ref().and_then(|etag| etag.parse().ok()) {
+            headers.insert(IF_NONE_MATCH, etag);
+        } else if let Some(date) = self
+            .last_modified
+            .as_ref()
+            .and_then(|date| date.parse().ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, date);
+        }
+        headers
+    }
+}
+
+/// A response in the cache, as listed by `http-cache inspect`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub route: String,
+    pub etag: Option<String>,
+    pub stored_at: DateTime<Utc>,
+    /// Size of the entry on disk, in bytes
+    pub size: u64,
+}
+
+/// The route a blob is stored under. It is not a REST route, so no request is ever
+/// revalidated against it.
+fn blob_route(sha: &str) -> String {
+    format!("blob:{}", sha)
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, route: &str) -> PathBuf {
+        let key = hex::encode(Sha256::digest(route.as_bytes()));
+        self.dir.join(format!("{}.{}", key, ENTRY_EXTENSION))
+    }
+
+    /// The stored response for a route. An entry that cannot be read is treated as missing
+    /// and replaced by the next response.
+    pub async fn get(&self, route: &str) -> Option<CachedResponse> {
+        let bytes = tokio::fs::read(self.entry_path(route)).await.ok()?;
+        serde_json::from_slice::<CachedResponse>(&bytes)
+            .ok()
+            .filter(|response| response.route == route)
+    }
+
+    /// Store a response, replacing the previous one for its route.
+    pub async fn put(&self, response: &CachedResponse) -> std::io::Result<()> {
+        let mut dir = tokio::fs::DirBuilder::new();
+        dir.recursive(true);
+        #[cfg(unix)]
+        dir.mode(0o700);
+        dir.create(&self.dir).await?;
+        let path = self.entry_path(&response.route);
+
+        // Written next to the entry and renamed over it, so readers never see half of it.
+        // Each writer has its own file, so concurrent writes of a route cannot interleave.
+        let partial = path.with_extension(format!("{}.partial", uuid::Uuid::new_v4().simple()));
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&partial).await?;
+        file.write_all(&serde_json::to_vec(response)?).await?;
+        file.flush().await?;
+        drop(file);
+        tokio::fs::rename(&partial, &path).await
+    }
+
+    /// The text of a blob stored with [`HttpCache::put_blob`].
+    pub async fn get_blob(&self, sha: &str) -> Option<String> {
+        self.get(&blob_route(sha))
+            .await
+            .map(|response| response.body)
+    }
+
+    /// Store the text of a blob, which is the same wherever the blob appears.
+    pub async fn put_blob(&self, sha: &str, text: &str) -> std::io::Result<()> {
+        self.put(&CachedResponse {
+            route: blob_route(sha),
+            etag: Some(format!("\"{}\"", sha)),
+            last_modified: None,
+            stored_at: Utc::now(),
+            body: text.to_string(),
+        })
+        .await
+    }
+
+    /// Every stored response, ordered by route. A cache directory that does not exist yet
+    /// is empty.
+    pub async fn entries(&self) -> std::io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for path in self.entry_paths().await? {
+            let Ok(bytes) = tokio::fs::read(&path).await else {
+                continue;
+            };
+            let Ok(response) = serde_json::from_slice::<CachedResponse>(&bytes) else {
+                continue;
+            };
+            entries.push(CacheEntry {
+                route: response.route,
+                etag: response.etag,
+                stored_at: response.stored_at,
+                size: bytes.len() as u64,
+            });
+        }
+        entries.sort_by(|a, b| a.route.cmp(&b.route));
+        Ok(entries)
+    }
+
+    /// Remove every stored response, returning how many there were.
+    pub async fn clear(&self) -> std::io::Result<usize> {
+        let paths = self.entry_paths().await?;
+        for path in &paths {
+            tokio::fs::remove_file(path).await?;
+        }
+        Ok(paths.len())
+    }
+
+    async fn entry_paths(&self) -> std::io::Result<Vec<PathBuf>> {
+        let mut directory = match tokio::fs::read_dir(&self.dir).await {
+            Ok(directory) => directory,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut paths = Vec::new();
+        while let Some(entry) = directory.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == ENTRY_EXTENSION)
+            {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(route: &str, etag: Option<&str>) -> CachedResponse {
+        CachedResponse {
+            route: route.to_string(),
+            etag: etag.map(str::to_string),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+            stored_at: Utc::now(),
+            body: r#"{"sha": "abc"}"#.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_stored_by_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::new(dir.path().join("http"));
+        assert_eq!(cache.get("/repos/o/r/git/trees/main").await, None);
+        assert!(cache.entries().await.unwrap().is_empty());
+
+        let tree = response("/repos/o/r/git/trees/main", Some("\"abc\""));
+        let readme = response("/repos/o/r/contents/README.md", None);
+        cache.put(&tree).await.unwrap();
+        cache.put(&readme).await.unwrap();
+        assert_eq!(cache.get(&tree.route).await, Some(tree.clone()));
+
+        let entries = cache.entries().await.unwrap();
+        let routes: Vec<_> = entries.iter().map(|entry| entry.route.as_str()).collect();
+        assert_eq!(
+            routes,
+            vec!["/repos/o/r/contents/README.md", "/repos/o/r/git/trees/main"]
+        );
+        assert!(entries[1].size > 0);
+
+        // Files the cache did not write are left alone
+        std::fs::write(dir.path().join("http").join("notes.txt"), "keep").unwrap();
+        assert_eq!(cache.clear().await.unwrap(), 2);
+        assert_eq!(cache.get(&tree.route).await, None);
+        assert!(dir.path().join("http").join("notes.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_blobs_are_stored_by_sha() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::new(dir.path());
+        assert_eq!(cache.get_blob("abc").await, None);
+
+        cache.put_blob("abc", "# Guide").await.unwrap();
+        assert_eq!(cache.get_blob("abc").await.as_deref(), Some("# Guide"));
+        assert_eq!(cache.get_blob("def").await, None);
+        assert_eq!(cache.entries().await.unwrap()[0].route, "blob:abc");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_entries_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::new(dir.path().join("http"));
+        let tree = response("/repos/o/r/git/trees/main", Some("\"abc\""));
+        cache.put(&tree).await.unwrap();
+        // Replacing an entry keeps it private
+        cache.put(&tree).await.unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir.path().join("http")), 0o700);
+        assert_eq!(mode(&cache.entry_path(&tree.route)), 0o600);
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("http")).unwrap().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_conditional_headers_prefer_the_etag() {
+        let headers = response("/", Some("W/\"abc\"")).conditional_headers();
+        assert_eq!(headers[IF_NONE_MATCH], "W/\"abc\"");
+        assert!(!headers.contains_key(IF_MODIFIED_SINCE));
+
+        let headers = response("/", None).conditional_headers();
+        assert_eq!(headers[IF_MODIFIED_SINCE], "Wed, 21 Oct 2015 07:28:00 GMT");
+    }
+}
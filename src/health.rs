@@ -0,0 +1,259 @@
+//! Dependency checks shared by `/health/ready` and the `health-check` command.
+
+use crate::database::Database;
+use crate::github::Client;
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How long a single check may take before its component is reported as down.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long [`GitHubHealthCache`] reuses the result of checking GitHub.
+pub const GITHUB_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// The result of checking one dependency.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub details: String,
+}
+
+impl ComponentHealth {
+    fn up(name: &'static str, details: impl Into<String>) -> Self {
+        Self {
+            name,
+            healthy: true,
+            details: details.into(),
+        }
+    }
+
+    fn down(name: &'static str, details: impl Into<String>) -> Self {
+        Self {
+            name,
+            healthy: false,
+            details: details.into(),
+        }
+    }
+}
+
+/// The results of checking every dependency.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.components.iter().all(|component| component.healthy)
+    }
+}
+
+/// Check the database, its migrations and the output directory concurrently with `github`,
+/// a check of GitHub such as [`check_github`].
+pub async fn check_all(
+    database: &Database,
+    github: impl Future<Output = ComponentHealth>,
+    output_dir: &Path,
+) -> HealthReport {
+    let (database, migrations, github, storage) = tokio::join!(
+        check_database(database),
+        check_migrations(database),
+        github,
+        check_storage(output_dir),
+    );
+
+    HealthReport {
+        components: vec![database, migrations, github, storage],
+    }
+}
+
+pub async fn check_database(database: &Database) -> ComponentHealth {
+    const NAME: &str = "database";
+
+    match with_timeout(NAME, database.health_check()).await {
+        Ok(Ok(())) => ComponentHealth::up(NAME, "Connection successful, queries working"),
+        Ok(Err(e)) => ComponentHealth::down(NAME, format!("Health check failed: {}", e)),
+        Err(down) => down,
+    }
+}
+
+pub async fn check_migrations(database: &Database) -> ComponentHealth {
+    const NAME: &str = "migrations";
+
+    match with_timeout(NAME, database.pending_migrations()).await {
+        Ok(Ok(pending)) if pending.is_empty() => {
+            ComponentHealth::up(NAME, "All migrations applied")
+        }
+        Ok(Ok(pending)) => ComponentHealth::down(
+            NAME,
+            format!(
+                "Pending migrations: {}",
+                pending
+                    .iter()
+                    .map(i64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        Ok(Err(e)) => ComponentHealth::down(NAME, format!("Could not read migrations: {}", e)),
+        Err(down) => down,
+    }
+}
+
+/// Check every organization's client with [`check_organization`]. GitHub is healthy when
+/// all of them are.
+pub async fn check_github(clients: &[impl Client + Sync]) -> ComponentHealth {
+    let mut healthy = true;
+    let mut details = Vec::with_capacity(clients.len());
+
+    for github in clients {
+        let component = check_organization(github).await;
+        healthy &= component.healthy;
+        details.push(format!("{}: {}", github.organization(), component.details));
+    }
+
+    ComponentHealth {
+        name: "github",
+        healthy,
+        details: details.join("; "),
+    }
+}
+
+/// The last result of [`check_github`], reused for [`GITHUB_CHECK_TTL`] so that frequent
+/// probes do not spend a request per organization each time.
+#[derive(Debug, Clone, Default)]
+pub struct GitHubHealthCache {
+    last: Arc<Mutex<Option<(Instant, ComponentHealth)>>>,
+}
+
+impl GitHubHealthCache {
+    /// The cached result while it is fresh, otherwise the result of checking `clients`.
+    /// Concurrent probes wait for a single check.
+    pub async fn check(&self, clients: &[impl Client + Sync]) -> ComponentHealth {
+        let mut last = self.last.lock().await;
+        if let Some((checked_at, component)) = last.as_ref()
+            && checked_at.elapsed() < GITHUB_CHECK_TTL
+        {
+            return component.clone();
+        }
+
+        let component = check_github(clients).await;
+        *last = Some((Instant::now(), component.clone()));
+        component
+    }
+}
+
+/// Check that GitHub accepts the credentials of one organization and that its rate limit is
+/// not exhausted.
+pub async fn check_organization(github: &(impl Client + Sync)) -> ComponentHealth {
+    const NAME: &str = "github";
+
+    let check = async {
+        let user = github.current_user().await?;
+        let rate_limit = github.rate_limit().await?;
+        Ok::<_, crate::github::GitHubError>((user, rate_limit))
+    };
+
+    match with_timeout(NAME, check).await {
+        Ok(Ok((user, rate_limit))) if rate_limit.remaining == 0 => ComponentHealth::down(
+            NAME,
+            format!(
+                "Authenticated as {} via {}, but the rate limit is exhausted until {}",
+                user,
+                github.auth_mode(),
+                rate_limit.reset_at
+            ),
+        ),
+        Ok(Ok((user, rate_limit))) => ComponentHealth::up(
+            NAME,
+            format!(
+                "Authenticated as {} via {}, {}/{} requests remaining",
+                user,
+                github.auth_mode(),
+                rate_limit.remaining,
+                rate_limit.limit
+            ),
+        ),
+        Ok(Err(e)) => ComponentHealth::down(NAME, format!("Error: {}", e)),
+        Err(down) => down,
+    }
+}
+
+/// Check that the output directory exists, or can be created, and is writable.
+pub async fn check_storage(output_dir: &Path) -> ComponentHealth {
+    const NAME: &str = "storage";
+
+    let probe = output_dir.join(format!(".health-check-{}", Uuid::new_v4()));
+    let check = async {
+        tokio::fs::create_dir_all(output_dir).await?;
+        tokio::fs::write(&probe, b"ok").await?;
+        tokio::fs::remove_file(&probe).await
+    };
+
+    match with_timeout(NAME, check).await {
+        Ok(Ok(())) => ComponentHealth::up(NAME, format!("{} is writable", output_dir.display())),
+        Ok(Err(e)) => ComponentHealth::down(
+            NAME,
+            format!("{} is not writable: {}", output_dir.display(), e),
+        ),
+        Err(down) => down,
+    }
+}
+
+async fn with_timeout<T>(
+    name: &'static str,
+    check: impl Future<Output = T>,
+) -> Result<T, ComponentHealth> {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .map_err(|_| {
+            ComponentHealth::down(
+                name,
+                format!("Timed out after {} seconds", CHECK_TIMEOUT.as_secs()),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::tests::MockGitHubClient;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_check_all(pool: PgPool) {
+        let output_dir = tempfile::tempdir().unwrap();
+
+        let report = check_all(
+            &Database::from_pool(pool),
+            check_github(&[MockGitHubClient::new(), MockGitHubClient::new()]),
+            output_dir.path(),
+        )
+        .await;
+
+        assert!(report.is_healthy(), "{:?}", report);
+        let names: Vec<_> = report.components.iter().map(|c| c.name).collect();
+        assert_eq!(names, ["database", "migrations", "github", "storage"]);
+        let github = &report.components[2].details;
+        assert!(github.starts_with("test-org: Authenticated as test-user via personal access token"));
+        assert_eq!(github.matches("test-org: ").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_check_storage_not_writable() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        // A path below a regular file can never be created
+        let health = check_storage(&file.path().join("output")).await;
+
+        assert!(!health.healthy);
+        assert_eq!(health.name, "storage");
+    }
+}
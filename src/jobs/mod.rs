@@ -0,0 +1,257 @@
+pub mod scheduler;
+pub mod worker;
+
+pub use scheduler::{Schedule, ScheduledTask, Scheduler};
+pub use worker::{JobOutcome, JobRun, Worker};
+
+use crate::database::{Database, DatabaseError, ProcessingJob};
+use crate::web::AppError;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const SCAN_ORGANIZATION: &str = "scan_organization";
+pub const SCAN_REPOSITORY: &str = "scan_repository";
+pub const PROCESS_ORGANIZATION: &str = "process_organization";
+pub const PROCESS_REPOSITORY: &str = "process_repository";
+pub const WEBHOOK_DELIVERY: &str = "webhook_delivery";
+
+/// Delay before the first retry of a failed job. It doubles with every further attempt.
+const RETRY_BASE_DELAY_SECONDS: i64 = 30;
+
+/// Longest delay between two attempts of a job.
+const RETRY_MAX_DELAY_SECONDS: i64 = 60 * 60;
+
+/// How long a running job keeps its worker without a heartbeat. Workers send one every
+/// third of the lease; a job whose lease ran out is claimed by another worker.
+pub const JOB_LEASE: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Work that can be queued in the `processing_jobs` table and run by a [`Worker`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Job {
+    /// Find the repositories of an organization, or of every configured organization when
+    /// none is given, that have a `documents.toml` and queue each of them for processing
+    ScanOrganization { organization: Option<String> },
+    /// Check one repository for a `documents.toml` and queue it for processing if it has one.
+    /// Repositories are named `owner/name`; a bare name belongs to the first configured
+    /// organization.
+    ScanRepository { repository: String },
+    /// Process every repository of an organization, or of every configured organization
+    /// when none is given, that has a `documents.toml`, one after the other
+    ProcessOrganization { organization: Option<String> },
+    /// Process one repository and store its documents
+    ProcessRepository { repository: String },
+    /// Run a recorded GitHub webhook delivery through the webhook handler
+    WebhookDelivery { delivery_id: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    #[error("Unknown job type: {0}")]
+    UnknownJobType(String),
+    #[error("Invalid parameters for {job_type} job: {message}")]
+    InvalidParameters { job_type: String, message: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct OrganizationParameters {
+    organization: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RepositoryParameters {
+    repository: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeliveryParameters {
+    delivery_id: String,
+}
+
+impl Job {
+    /// Value stored in `processing_jobs.job_type`.
+    pub fn job_type(&self) -> &'static str {
+        match self {
+            Job::ScanOrganization { .. } => SCAN_ORGANIZATION,
+            Job::ScanRepository { .. } => SCAN_REPOSITORY,
+            Job::ProcessOrganization { .. } => PROCESS_ORGANIZATION,
+            Job::ProcessRepository { .. } => PROCESS_REPOSITORY,
+            Job::WebhookDelivery { .. } => WEBHOOK_DELIVERY,
+        }
+    }
+
+    /// JSON stored in `processing_jobs.parameters`.
+    pub fn parameters(&self) -> Option<String> {
+        match self {
+            Job::ScanOrganization { organization } | Job::ProcessOrganization { organization } => {
+                organization.as_ref().and_then(|organization| {
+                    serde_json::to_string(&OrganizationParameters {
+                        organization: organization.clone(),
+                    })
+                    .ok()
+                })
+            }
+            Job::ScanRepository { repository } | Job::ProcessRepository { repository } => {
+                serde_json::to_string(&RepositoryParameters {
+                    repository: repository.clone(),
+                })
+                .ok()
+            }
+            Job::WebhookDelivery { delivery_id } => serde_json::to_string(&DeliveryParameters {
+                delivery_id: delivery_id.clone(),
+            })
+            .ok(),
+        }
+    }
+
+    /// Row that queues the job, due now.
+    pub fn pending_row(&self, repository_id: Option<Uuid>) -> ProcessingJob {
+        let now = Utc::now();
+        ProcessingJob {
+            id: Uuid::new_v4(),
+            repository_id,
+            job_type: self.job_type().to_string(),
+            status: "pending".to_string(),
+            parameters: self.parameters(),
+            error_message: None,
+            started_at: None,
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+            attempts: 0,
+            run_after: now,
+            heartbeat_at: None,
+        }
+    }
+
+    /// Read a job back from its `processing_jobs` row.
+    pub fn from_row(row: &ProcessingJob) -> Result<Self, JobError> {
+        let parameters = row.parameters.as_deref().unwrap_or("{}");
+        let invalid = |e: serde_json::Error| JobError::InvalidParameters {
+            job_type: row.job_type.clone(),
+            message: e.to_string(),
+        };
+        // Organization jobs queued without parameters cover every organization
+        let organization = || match row.parameters.as_deref() {
+            Some(parameters) => serde_json::from_str::<OrganizationParameters>(parameters)
+                .map(|params| Some(params.organization))
+                .map_err(invalid),
+            None => Ok(None),
+        };
+
+        match row.job_type.as_str() {
+            SCAN_ORGANIZATION => Ok(Job::ScanOrganization {
+                organization: organization()?,
+            }),
+            SCAN_REPOSITORY => {
+                let params: RepositoryParameters =
+                    serde_json::from_str(parameters).map_err(invalid)?;
+                Ok(Job::ScanRepository {
+                    repository: params.repository,
+                })
+            }
+            PROCESS_ORGANIZATION => Ok(Job::ProcessOrganization {
+                organization: organization()?,
+            }),
+            PROCESS_REPOSITORY => {
+                let params: RepositoryParameters =
+                    serde_json::from_str(parameters).map_err(invalid)?;
+                Ok(Job::ProcessRepository {
+                    repository: params.repository,
+                })
+            }
+            WEBHOOK_DELIVERY => {
+                let params: DeliveryParameters =
+                    serde_json::from_str(parameters).map_err(invalid)?;
+                Ok(Job::WebhookDelivery {
+                    delivery_id: params.delivery_id,
+                })
+            }
+            other => Err(JobError::UnknownJobType(other.to_string())),
+        }
+    }
+}
+
+/// Add a job to the queue. It is picked up by the next idle worker.
+pub async fn enqueue(
+    database: &Database,
+    job: &Job,
+    repository_id: Option<Uuid>,
+) -> Result<ProcessingJob, DatabaseError> {
+    let saved = database
+        .create_processing_job(&job.pending_row(repository_id))
+        .await?;
+    tracing::info!("Queued {} job {}", saved.job_type, saved.id);
+
+    Ok(saved)
+}
+
+/// How long to wait before retrying a job that failed its `attempts`th attempt.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    Duration::seconds((RETRY_BASE_DELAY_SECONDS << exponent).min(RETRY_MAX_DELAY_SECONDS))
+}
+
+impl From<JobError> for AppError {
+    fn from(error: JobError) -> Self {
+        AppError::BadRequest(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_round_trips_through_row() {
+        let jobs = [
+            Job::ScanOrganization { organization: None },
+            Job::ScanOrganization {
+                organization: Some("test-org".to_string()),
+            },
+            Job::ScanRepository {
+                repository: "test-org/test-repo".to_string(),
+            },
+            Job::ProcessOrganization { organization: None },
+            Job::ProcessOrganization {
+                organization: Some("test-org".to_string()),
+            },
+            Job::ProcessRepository {
+                repository: "test-repo".to_string(),
+            },
+            Job::WebhookDelivery {
+                delivery_id: "delivery-1".to_string(),
+            },
+        ];
+
+        for job in jobs {
+            assert_eq!(Job::from_row(&job.pending_row(None)).unwrap(), job);
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(3), Duration::seconds(120));
+        assert_eq!(retry_delay(10), Duration::hours(1));
+        assert_eq!(retry_delay(i32::MAX), Duration::hours(1));
+    }
+
+    #[test]
+    fn test_job_from_invalid_row() {
+        let mut unknown = Job::ScanOrganization { organization: None }.pending_row(None);
+        unknown.job_type = "unknown".to_string();
+        assert!(matches!(
+            Job::from_row(&unknown),
+            Err(JobError::UnknownJobType(_))
+        ));
+
+        let mut missing = Job::ScanOrganization { organization: None }.pending_row(None);
+        missing.job_type = PROCESS_REPOSITORY.to_string();
+        assert!(matches!(
+            Job::from_row(&missing),
+            Err(JobError::InvalidParameters { .. })
+        ));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::ApplicationConfig;
+use crate::database::ScheduleRun;
+use crate::jobs::{Job, enqueue};
+use crate::web::{AppError, AppState};
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use tokio::sync::watch;
+use tracing::{debug, error, info};
+
+/// Work a schedule queues when it fires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduledTask {
+    /// Queue a scan of every organization, which in turn queues every documented repository
+    ScanOrganization,
+    /// Queue processing of every repository of the organizations that has a `documents.toml`
+    ProcessOrganization,
+}
+
+impl ScheduledTask {
+    /// Value stored in `schedule_runs.schedule_name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScheduledTask::ScanOrganization => "scan_organization",
+            ScheduledTask::ProcessOrganization => "process_organization",
+        }
+    }
+
+    /// The job queued when the schedule fires.
+    pub fn job(&self) -> Job {
+        match self {
+            ScheduledTask::ScanOrganization => Job::ScanOrganization { organization: None },
+            ScheduledTask::ProcessOrganization => Job::ProcessOrganization { organization: None },
+        }
+    }
+}
+
+/// A task and the cron expression it runs on.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub task: ScheduledTask,
+    pub expression: String,
+    cron: Cron,
+}
+
+impl Schedule {
+    pub fn new(task: ScheduledTask, expression: &str) -> Result<Self, croner::errors::CronError> {
+        let cron = Cron::new(expression).parse()?;
+        Ok(Self {
+            task,
+            expression: expression.to_string(),
+            cron,
+        })
+    }
+
+    /// Schedules set in the configuration.
+    pub fn configured(config: &ApplicationConfig) -> Vec<Self> {
+        [
+            (ScheduledTask::ScanOrganization, &config.scan_schedule),
+            (ScheduledTask::ProcessOrganization, &config.process_schedule),
+        ]
+        .into_iter()
+        .filter_map(|(task, expression)| {
+            let expression = expression.as_deref()?;
+            match Schedule::new(task, expression) {
+                Ok(schedule) => Some(schedule),
+                Err(e) => {
+                    error!("Invalid {} schedule '{}': {}", task.name(), expression, e);
+                    None
+                }
+            }
+        })
+        .collect()
+    }
+
+    /// The first occurrence strictly after `time`.
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.cron.find_next_occurrence(&time, false).ok()
+    }
+}
+
+/// Queues jobs when configured schedules come due.
+///
+/// Every `serve` and `worker` process runs a scheduler. Each occurrence of a schedule is
+/// recorded in `schedule_runs` under an advisory lock, so only the first instance to reach
+/// it queues any jobs.
+#[derive(Clone, Debug)]
+pub struct Scheduler {
+    state: AppState,
+    schedules: Vec<Schedule>,
+}
+
+impl Scheduler {
+    pub fn new(state: AppState) -> Self {
+        let schedules = Schedule::configured(&state.config);
+        Self { state, schedules }
+    }
+
+    pub fn schedules(&self) -> &[Schedule] {
+        &self.schedules
+    }
+
+    /// Fire schedules as they come due until `shutdown` is set.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        if self.schedules.is_empty() {
+            debug!("No schedules configured");
+            return;
+        }
+
+        for schedule in &self.schedules {
+            info!(
+                "Scheduled {} at '{}'",
+                schedule.task.name(),
+                schedule.expression
+            );
+        }
+
+        let mut after = Utc::now();
+        while !*shutdown.borrow() {
+            let Some((due_at, due)) = self.next_due(after) else {
+                return;
+            };
+
+            let wait = (due_at - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = shutdown.changed() => return,
+            }
+
+            for schedule in due {
+                if let Err(e) = self.fire(schedule, due_at).await {
+                    error!("Scheduled {} failed: {}", schedule.task.name(), e);
+                }
+            }
+            after = due_at;
+        }
+    }
+
+    /// The earliest occurrence after `time` and the schedules that are due then.
+    fn next_due(&self, time: DateTime<Utc>) -> Option<(DateTime<Utc>, Vec<&Schedule>)> {
+        let occurrences: Vec<(DateTime<Utc>, &Schedule)> = self
+            .schedules
+            .iter()
+            .filter_map(|schedule| Some((schedule.next_after(time)?, schedule)))
+            .collect();
+        let due_at = occurrences.iter().map(|(at, _)| *at).min()?;
+
+        Some((
+            due_at,
+            occurrences
+                .into_iter()
+                .filter(|(at, _)| *at == due_at)
+                .map(|(_, schedule)| schedule)
+                .collect(),
+        ))
+    }
+
+    /// Queue the jobs of one occurrence of a schedule. Returns `None` if another instance
+    /// already fired it.
+    ///
+    /// The occurrence is skipped, and recorded as such, while the job the schedule queued
+    /// last is still pending or running. Jobs queued through the API do not hold it up.
+    pub async fn fire(
+        &self,
+        schedule: &Schedule,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<Option<ScheduleRun>, AppError> {
+        let name = schedule.task.name();
+        let Some(run) = self
+            .state
+            .database
+            .start_schedule_run(name, scheduled_for)
+            .await?
+        else {
+            debug!(
+                "{} at {} was fired by another instance",
+                name, scheduled_for
+            );
+            return Ok(None);
+        };
+
+        // A run that takes longer than the interval, or waits in a backed up queue, must
+        // not have more of the same work queued on top of it
+        if let Some(active) = self.state.database.find_active_scheduled_job(name).await? {
+            let reason = format!("{} job {} is still {}", name, active.id, active.status);
+            info!("Skipped {} at {}: {}", name, scheduled_for, reason);
+            self.state
+                .database
+                .skip_schedule_run(run.id, &reason)
+                .await?;
+            return Ok(Some(run));
+        }
+
+        let result = enqueue(&self.state.database, &schedule.task.job(), None).await;
+        let (job_id, error_message) = match &result {
+            Ok(job) => (Some(job.id), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        self.state
+            .database
+            .finish_schedule_run(run.id, job_id, error_message.as_deref())
+            .await?;
+        let job = result?;
+
+        info!("Scheduled {} queued job {}", name, job.id);
+        Ok(Some(run))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::web::test_state;
+    use sqlx::PgPool;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().into()
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        let schedule = Schedule::new(ScheduledTask::ScanOrganization, "0 */6 * * *").unwrap();
+
+        assert_eq!(
+            schedule.next_after(time("2025-01-01T05:59:00Z")),
+            Some(time("2025-01-01T06:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(time("2025-01-01T06:00:00Z")),
+            Some(time("2025-01-01T12:00:00Z"))
+        );
+        assert!(Schedule::new(ScheduledTask::ScanOrganization, "every hour").is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_scheduler_fires_each_occurrence_once(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let mut state = test_state(database.clone()).await;
+        let mut config = (*state.config).clone();
+        config.scan_schedule = Some("0 */6 * * *".to_string());
+        config.process_schedule = Some("0 0 * * *".to_string());
+        state.config = std::sync::Arc::new(config);
+
+        let scheduler = Scheduler::new(state.clone());
+        let other_instance = Scheduler::new(state);
+
+        let (due_at, due) = scheduler.next_due(time("2025-01-01T18:30:00Z")).unwrap();
+        assert_eq!(due_at, time("2025-01-02T00:00:00Z"));
+        assert_eq!(due.len(), 2);
+
+        let scan = &scheduler.schedules()[0];
+        assert!(scheduler.fire(scan, due_at).await.unwrap().is_some());
+        assert!(other_instance.fire(scan, due_at).await.unwrap().is_none());
+
+        let jobs = database.get_active_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_type, "scan_organization");
+
+        let runs = database
+            .list_schedule_runs(Some("scan_organization"), 10)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].jobs_enqueued, 1);
+
+        // The scan queued last time has not finished
+        let next_at = scan.next_after(due_at).unwrap();
+        assert!(scheduler.fire(scan, next_at).await.unwrap().is_some());
+        assert_eq!(database.get_active_jobs().await.unwrap().len(), 1);
+
+        let runs = database
+            .list_schedule_runs(Some("scan_organization"), 10)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].scheduled_for, next_at);
+        assert_eq!(runs[0].jobs_enqueued, 0);
+        assert!(runs[0].completed_at.is_some());
+        let reason = runs[0].skipped_reason.as_deref().unwrap();
+        assert!(reason.contains("still pending"));
+
+        // The process schedule is not held up by the scan, nor by processing queued through
+        // the API
+        let queued = Job::ProcessOrganization {
+            organization: Some("test-org".to_string()),
+        };
+        enqueue(&database, &queued, None).await.unwrap();
+        let process = &scheduler.schedules()[1];
+        assert!(scheduler.fire(process, due_at).await.unwrap().is_some());
+        assert_eq!(database.get_active_jobs().await.unwrap().len(), 3);
+        let runs = database
+            .list_schedule_runs(Some("process_organization"), 10)
+            .await
+            .unwrap();
+        assert!(runs[0].job_id.is_some());
+    }
+}
@@ -0,0 +1,811 @@
+use crate::database::{ProcessingJob, Repository};
+use crate::github::{Client, GitHubClient, GitHubError, Priority};
+use crate::jobs::{JOB_LEASE, Job, enqueue, retry_delay};
+use crate::metrics::METRICS;
+use crate::processing::persistence::{process_repository, with_config};
+use crate::processing::{ProcessingError, RepositoryProcessor};
+use crate::web::webhook::process_delivery;
+use crate::web::{AppError, AppState};
+use crate::{ProjectConfig, RepoStatus};
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// Runs queued jobs. Any number of workers, in one process or many, can share a queue.
+#[derive(Clone, Debug)]
+pub struct Worker {
+    state: AppState,
+    poll_interval: Duration,
+    lease: Duration,
+}
+
+/// What happened to a job after a worker ran it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobOutcome {
+    Completed,
+    /// The job failed and is retried after a backoff
+    Retrying,
+    /// The GitHub rate limit ran out. The job runs again when it resets, and the attempt
+    /// is not counted.
+    RateLimited,
+    /// The job failed its last attempt, or failed in a way retrying cannot fix
+    Dead,
+    /// The job was cancelled while it ran
+    Cancelled,
+}
+
+impl JobOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobOutcome::Completed => "completed",
+            JobOutcome::Retrying => "retrying",
+            JobOutcome::RateLimited => "rate_limited",
+            JobOutcome::Dead => "dead",
+            JobOutcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A job a worker claimed and ran.
+#[derive(Debug)]
+pub struct JobRun {
+    /// The job as it was claimed
+    pub job: ProcessingJob,
+    pub outcome: JobOutcome,
+    /// Summary of what the job did, or why it failed
+    pub result: Result<String, String>,
+}
+
+impl Worker {
+    /// A worker whose GitHub requests give way to the interactive requests of `state`.
+    pub fn new(state: AppState, poll_interval: Duration) -> Self {
+        let github = state.github.with_priority(Priority::Background);
+        Self {
+            state: AppState { github, ..state },
+            poll_interval,
+            lease: JOB_LEASE,
+        }
+    }
+
+    /// Take over running jobs whose worker sent no heartbeat for `lease` instead of
+    /// [`JOB_LEASE`].
+    pub fn with_lease(self, lease: Duration) -> Self {
+        Self { lease, ..self }
+    }
+
+    /// Claim and run the oldest pending job. Returns `None` when the queue is empty.
+    pub async fn run_next(&self) -> Result<Option<JobRun>, AppError> {
+        let Some(job) = self.state.database.claim_next_job(self.lease).await? else {
+            return Ok(None);
+        };
+
+        // Attempts are counted when they are claimed, so only a job whose last attempt
+        // was abandoned by a worker that stopped gets past the maximum
+        let max_attempts = self.state.config.max_attempts(&job.job_type);
+        if job.attempts > max_attempts {
+            let message = format!(
+                "The worker running attempt {} stopped before it finished",
+                max_attempts
+            );
+            error!(
+                "Abandoned {} job {}, giving up: {}",
+                job.job_type, job.id, message
+            );
+            let updated = self
+                .state
+                .database
+                .update_job_status(job.id, "dead", Some(&message))
+                .await?;
+            return Ok(Some(JobRun {
+                job,
+                outcome: if updated {
+                    JobOutcome::Dead
+                } else {
+                    JobOutcome::Cancelled
+                },
+                result: Err(message),
+            }));
+        }
+
+        info!(
+            "Running {} job {} (attempt {})",
+            job.job_type, job.id, job.attempts
+        );
+        let started = Instant::now();
+        let result = self.execute_with_heartbeat(&job).await;
+
+        // Recording the outcome only changes a job that is still running, so a job
+        // cancelled at any point before this keeps its cancelled status
+        let outcome = match &result {
+            Ok(summary) => {
+                let updated = self
+                    .state
+                    .database
+                    .update_job_status(job.id, "completed", None)
+                    .await?;
+                if updated {
+                    info!("Completed {} job {}: {}", job.job_type, job.id, summary);
+                    JobOutcome::Completed
+                } else {
+                    JobOutcome::Cancelled
+                }
+            }
+            Err(error) => self.record_failure(&job, error).await?,
+        };
+        if outcome == JobOutcome::Cancelled {
+            info!("Cancelled {} job {}", job.job_type, job.id);
+        }
+        METRICS
+            .job_duration
+            .with_label_values(&[job.job_type.as_str(), outcome.name()])
+            .observe(started.elapsed().as_secs_f64());
+
+        Ok(Some(JobRun {
+            job,
+            outcome,
+            result: result.map_err(|e| e.to_string()),
+        }))
+    }
+
+    /// Decide whether a failed job is retried, rescheduled or dead, and record it.
+    async fn record_failure(
+        &self,
+        job: &ProcessingJob,
+        error: &AppError,
+    ) -> Result<JobOutcome, AppError> {
+        let database = &self.state.database;
+        let message = error.to_string();
+
+        if let Some(reset_at) = rate_limit_reset(error) {
+            warn!(
+                "{} job {} hit the GitHub rate limit, rescheduled for {}",
+                job.job_type, job.id, reset_at
+            );
+            if !database.reschedule_job(job.id, &message, reset_at).await? {
+                return Ok(JobOutcome::Cancelled);
+            }
+            return Ok(JobOutcome::RateLimited);
+        }
+
+        // Bad parameters, payloads or configuration fail the same way on every attempt
+        let retryable = !matches!(error, AppError::BadRequest(_));
+        let max_attempts = self.state.config.max_attempts(&job.job_type);
+
+        if retryable && job.attempts < max_attempts {
+            let run_after = Utc::now() + retry_delay(job.attempts);
+            warn!(
+                "Failed {} job {} (attempt {}/{}), retrying at {}: {}",
+                job.job_type, job.id, job.attempts, max_attempts, run_after, message
+            );
+            if !database.retry_job(job.id, &message, run_after).await? {
+                return Ok(JobOutcome::Cancelled);
+            }
+            Ok(JobOutcome::Retrying)
+        } else {
+            error!(
+                "Failed {} job {} (attempt {}/{}), giving up: {}",
+                job.job_type, job.id, job.attempts, max_attempts, message
+            );
+            if !database
+                .update_job_status(job.id, "dead", Some(&message))
+                .await?
+            {
+                return Ok(JobOutcome::Cancelled);
+            }
+            Ok(JobOutcome::Dead)
+        }
+    }
+
+    /// Run jobs until `shutdown` is set, polling for new jobs when the queue is empty.
+    ///
+    /// A job that is already running is always finished before the worker stops.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        while !*shutdown.borrow() {
+            let idle = match self.run_next().await {
+                Ok(run) => run.is_none(),
+                Err(e) => {
+                    error!("Worker could not run the next job: {}", e);
+                    true
+                }
+            };
+
+            if idle {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                    _ = shutdown.changed() => {}
+                }
+            }
+        }
+    }
+
+    /// Run a job, extending its lease until it finishes.
+    async fn execute_with_heartbeat(&self, job: &ProcessingJob) -> Result<String, AppError> {
+        let execute = self.execute(job);
+        tokio::pin!(execute);
+        let mut heartbeat = tokio::time::interval((self.lease / 3).max(Duration::from_secs(1)));
+        heartbeat.tick().await;
+
+        loop {
+            tokio::select! {
+                result = &mut execute => return result,
+                _ = heartbeat.tick() => {
+                    if let Err(e) = self.state.database.heartbeat_job(job.id).await {
+                        warn!("Could not extend the lease of {} job {}: {}", job.job_type, job.id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn execute(&self, job: &ProcessingJob) -> Result<String, AppError> {
+        match Job::from_row(job)? {
+            Job::ScanOrganization { organization } => {
+                let mut summaries = Vec::new();
+                for client in self.state.github.select(organization.as_deref())? {
+                    summaries.push(self.scan_organization(job, client).await?);
+                }
+                Ok(summaries.join("; "))
+            }
+            Job::ScanRepository { repository } => {
+                let (client, name) = self.state.github.resolve(&repository)?;
+                let config = match client.get_project_config(name, None).await {
+                    Ok(config) => Some(config),
+                    Err(GitHubError::ConfigFileNotFound(_)) => None,
+                    Err(e) => return Err(e.into()),
+                };
+                let queued = self.save_scan(job, client, name, config).await?;
+                Ok(format!(
+                    "Scanned {}/{}, {}",
+                    client.organization(),
+                    name,
+                    if queued {
+                        "queued for processing"
+                    } else {
+                        "no documents.toml found"
+                    }
+                ))
+            }
+            Job::ProcessOrganization { organization } => {
+                let mut summaries = Vec::new();
+                for client in self.state.github.select(organization.as_deref())? {
+                    summaries.push(self.process_organization(job, client).await?);
+                }
+                Ok(summaries.join("; "))
+            }
+            Job::ProcessRepository { repository } => {
+                let (client, name) = self.state.github.resolve(&repository)?;
+                let full_name = format!("{}/{}", client.organization(), name);
+                self.report(job, &full_name, RepoStatus::Processing).await?;
+                let result = match client.get_project_config(name, None).await {
+                    Ok(config) => self.process_repository(client, name, config).await,
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(summary) => {
+                        self.report(job, &full_name, RepoStatus::Success).await?;
+                        Ok(summary)
+                    }
+                    Err(e) => {
+                        self.report(job, &full_name, RepoStatus::Error(e.to_string()))
+                            .await?;
+                        Err(e)
+                    }
+                }
+            }
+            Job::WebhookDelivery { delivery_id } => {
+                let delivery = self
+                    .state
+                    .database
+                    .get_webhook_delivery(&delivery_id)
+                    .await?;
+                let outcome = process_delivery(&self.state, &delivery).await?;
+                Ok(outcome.to_string())
+            }
+        }
+    }
+
+    async fn scan_organization(
+        &self,
+        job: &ProcessingJob,
+        client: &GitHubClient,
+    ) -> Result<String, AppError> {
+        let repositories = client.batch_fetch_config_file_content().await?;
+        let mut queued = 0;
+
+        for (scanned, repository) in repositories.iter().enumerate() {
+            if self.is_cancelled(job).await? {
+                return Ok(format!(
+                    "Cancelled after scanning {} repositories of {}",
+                    scanned,
+                    client.organization()
+                ));
+            }
+
+            let name = &repository.repo_name;
+            let full_name = format!("{}/{}", client.organization(), name);
+            let config = match repository.content.as_deref().filter(|_| repository.exists) {
+                Some(content) => match toml::from_str::<ProjectConfig>(content) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        warn!("Invalid documents.toml in {}: {}", full_name, e);
+                        let status = RepoStatus::Error(format!("Invalid configuration: {}", e));
+                        self.report(job, &full_name, status).await?;
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            if self.save_scan(job, client, name, config).await? {
+                queued += 1;
+            }
+        }
+
+        Ok(format!(
+            "Scanned {} repositories of {}, queued {} for processing",
+            repositories.len(),
+            client.organization(),
+            queued
+        ))
+    }
+
+    /// Store what a scan found in a repository and queue it for processing if it has a
+    /// configuration. Returns whether it was queued.
+    async fn save_scan(
+        &self,
+        job: &ProcessingJob,
+        client: &GitHubClient,
+        name: &str,
+        config: Option<ProjectConfig>,
+    ) -> Result<bool, AppError> {
+        let full_name = format!("{}/{}", client.organization(), name);
+        let existing = self.find_repository(&full_name).await?;
+        if config.is_none() && existing.is_none() {
+            let status = RepoStatus::Skipped("No documents.toml found".to_string());
+            self.report(job, &full_name, status).await?;
+            return Ok(false);
+        }
+
+        let mut row = with_config(self.repository_row(client, name, existing), config.as_ref());
+        row.last_scanned_at = Some(row.updated_at);
+        let row = self.state.database.upsert_repository(&row).await?;
+
+        if config.is_none() {
+            let status = RepoStatus::Skipped("documents.toml was removed".to_string());
+            self.report(job, &full_name, status).await?;
+            return Ok(false);
+        }
+
+        let process = Job::ProcessRepository {
+            repository: full_name.clone(),
+        };
+        enqueue(&self.state.database, &process, Some(row.id)).await?;
+        self.report(job, &full_name, RepoStatus::Success).await?;
+        Ok(true)
+    }
+
+    async fn process_organization(
+        &self,
+        job: &ProcessingJob,
+        client: &GitHubClient,
+    ) -> Result<String, AppError> {
+        let repositories = client.batch_fetch_config_file_content().await?;
+        let (mut processed, mut failed, mut skipped) = (0, 0, 0);
+
+        for repository in &repositories {
+            if self.is_cancelled(job).await? {
+                return Ok(format!(
+                    "Cancelled after processing {} repositories of {}",
+                    processed + failed,
+                    client.organization()
+                ));
+            }
+
+            let name = &repository.repo_name;
+            let full_name = format!("{}/{}", client.organization(), name);
+            let content = match repository.content.as_deref() {
+                Some(content) if repository.exists => content,
+                _ => {
+                    let status = RepoStatus::Skipped("No documents.toml found".to_string());
+                    self.report(job, &full_name, status).await?;
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            self.report(job, &full_name, RepoStatus::Processing).await?;
+            let result = match toml::from_str::<ProjectConfig>(content) {
+                Ok(config) => self.process_repository(client, name, config).await,
+                Err(e) => Err(AppError::BadRequest(format!(
+                    "Invalid configuration: {}",
+                    e
+                ))),
+            };
+
+            match result {
+                Ok(_) => {
+                    self.report(job, &full_name, RepoStatus::Success).await?;
+                    processed += 1;
+                }
+                // The rest of the organization would hit the same limit
+                Err(e) if rate_limit_reset(&e).is_some() => return Err(e),
+                Err(e) => {
+                    self.report(job, &full_name, RepoStatus::Error(e.to_string()))
+                        .await?;
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok(format!(
+            "Processed {} repositories of {}, {} failed, {} skipped",
+            processed,
+            client.organization(),
+            failed,
+            skipped
+        ))
+    }
+
+    async fn process_repository(
+        &self,
+        client: &GitHubClient,
+        repository: &str,
+        config: ProjectConfig,
+    ) -> Result<String, AppError> {
+        let full_name = format!("{}/{}", client.organization(), repository);
+        let existing = self.find_repository(&full_name).await?;
+        let row = self.repository_row(client, repository, existing);
+
+        let processor = RepositoryProcessor::new(client.clone(), config, repository.to_string());
+        let (_, saved, removed) = process_repository(&self.state.database, row, &processor).await?;
+
+        Ok(format!(
+            "Processed {}: {} documents saved, {} removed",
+            full_name, saved, removed
+        ))
+    }
+
+    /// Record the progress of a job on one repository, for `/api/jobs/{id}/events`.
+    async fn report(
+        &self,
+        job: &ProcessingJob,
+        repository: &str,
+        status: RepoStatus,
+    ) -> Result<(), AppError> {
+        self.state
+            .database
+            .record_job_event(job.id, repository, status.name(), status.message())
+            .await?;
+        Ok(())
+    }
+
+    async fn is_cancelled(&self, job: &ProcessingJob) -> Result<bool, AppError> {
+        let current = self.state.database.get_processing_job(job.id).await?;
+        Ok(current.status == "cancelled")
+    }
+
+    async fn find_repository(&self, full_name: &str) -> Result<Option<Repository>, AppError> {
+        Ok(self
+            .state
+            .database
+            .find_repository_by_full_name(full_name)
+            .await?)
+    }
+
+    /// Row for a repository of the client's organization. GitHub metadata is kept from the
+    /// existing row; webhook events keep it up to date.
+    fn repository_row(
+        &self,
+        client: &GitHubClient,
+        name: &str,
+        existing: Option<Repository>,
+    ) -> Repository {
+        existing.unwrap_or_else(|| Repository::new(client.organization(), name))
+    }
+}
+
+/// When a job failed because the GitHub rate limit ran out, the time the limit resets.
+fn rate_limit_reset(error: &AppError) -> Option<DateTime<Utc>> {
+    match error {
+        AppError::GitHubApiError(GitHubError::RateLimitExceeded { reset_at })
+        | AppError::ProcessingError(ProcessingError::GitHub(GitHubError::RateLimitExceeded {
+            reset_at,
+        })) => Some(*reset_at),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::github::GitHubClients;
+    use crate::web::test_state;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    /// Make a job that waits for its backoff due now.
+    async fn skip_backoff(database: &Database, job_id: Uuid) {
+        sqlx::query!(
+            "UPDATE processing_jobs SET run_after = NOW() WHERE id = $1",
+            job_id
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn test_worker_runs_and_records_jobs(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let worker = Worker::new(
+            test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+
+        assert!(worker.run_next().await.unwrap().is_none());
+
+        database
+            .record_webhook_delivery("delivery-1", "ping", "{}")
+            .await
+            .unwrap();
+        let delivery_job = enqueue(
+            &database,
+            &Job::WebhookDelivery {
+                delivery_id: "delivery-1".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let missing_job = enqueue(
+            &database,
+            &Job::WebhookDelivery {
+                delivery_id: "missing".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.job.id, delivery_job.id);
+        assert_eq!(run.outcome, JobOutcome::Completed);
+        assert_eq!(run.result, Ok("Processed: pong".to_string()));
+
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.job.id, missing_job.id);
+        assert_eq!(run.outcome, JobOutcome::Retrying);
+        assert!(run.result.is_err());
+
+        // The failed job waits for its backoff before it is claimed again
+        assert!(worker.run_next().await.unwrap().is_none());
+        let retried = database.get_processing_job(missing_job.id).await.unwrap();
+        assert_eq!(retried.status, "pending");
+        assert_eq!(retried.attempts, 1);
+        assert!(retried.run_after > Utc::now());
+        assert!(retried.error_message.is_some());
+
+        let completed = database.get_processing_job(delivery_job.id).await.unwrap();
+        assert_eq!(completed.status, "completed");
+        assert!(completed.completed_at.is_some());
+        let delivery = database.get_webhook_delivery("delivery-1").await.unwrap();
+        assert_eq!(delivery.outcome, "processed");
+    }
+
+    #[sqlx::test]
+    async fn test_failed_jobs_retry_until_dead(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let mut state = test_state(database.clone()).await;
+        let mut config = (*state.config).clone();
+        config
+            .job_max_attempts
+            .insert(crate::jobs::WEBHOOK_DELIVERY.to_string(), 2);
+        state.config = std::sync::Arc::new(config);
+        let worker = Worker::new(state, Duration::from_millis(10));
+
+        let job = enqueue(
+            &database,
+            &Job::WebhookDelivery {
+                delivery_id: "missing".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.outcome, JobOutcome::Retrying);
+
+        skip_backoff(&database, job.id).await;
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.outcome, JobOutcome::Dead);
+
+        let dead = database.get_processing_job(job.id).await.unwrap();
+        assert_eq!(dead.status, "dead");
+        assert_eq!(dead.attempts, 2);
+        assert!(worker.run_next().await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_rate_limited_jobs_keep_their_attempt(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let worker = Worker::new(
+            test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+        enqueue(
+            &database,
+            &Job::ScanOrganization { organization: None },
+            None,
+        )
+        .await
+        .unwrap();
+        let job = database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+
+        let reset_at = Utc::now() + chrono::Duration::minutes(10);
+        let error =
+            AppError::ProcessingError(ProcessingError::GitHub(GitHubError::RateLimitExceeded {
+                reset_at,
+            }));
+        let outcome = worker.record_failure(&job, &error).await.unwrap();
+        assert_eq!(outcome, JobOutcome::RateLimited);
+
+        let rescheduled = database.get_processing_job(job.id).await.unwrap();
+        assert_eq!(rescheduled.status, "pending");
+        assert_eq!(rescheduled.attempts, 0);
+        assert_eq!(rescheduled.run_after.timestamp(), reset_at.timestamp());
+
+        // Errors retrying cannot fix are dead on the first attempt
+        skip_backoff(&database, job.id).await;
+        let job = database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        let error = AppError::BadRequest("Invalid push payload".to_string());
+        let outcome = worker.record_failure(&job, &error).await.unwrap();
+        assert_eq!(outcome, JobOutcome::Dead);
+    }
+
+    #[sqlx::test]
+    async fn test_cancelled_jobs_keep_their_status(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let worker = Worker::new(
+            test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+        enqueue(
+            &database,
+            &Job::ScanOrganization { organization: None },
+            None,
+        )
+        .await
+        .unwrap();
+        let job = database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+
+        // Cancelled after the worker last checked
+        database.cancel_job(job.id).await.unwrap().unwrap();
+        let error = AppError::InternalServerError("boom".to_string());
+        let outcome = worker.record_failure(&job, &error).await.unwrap();
+        assert_eq!(outcome, JobOutcome::Cancelled);
+        assert!(
+            !database
+                .update_job_status(job.id, "completed", None)
+                .await
+                .unwrap()
+        );
+
+        let cancelled = database.get_processing_job(job.id).await.unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+        assert!(cancelled.error_message.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_abandoned_jobs_are_taken_over(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let mut state = test_state(database.clone()).await;
+        let mut config = (*state.config).clone();
+        config
+            .job_max_attempts
+            .insert(crate::jobs::WEBHOOK_DELIVERY.to_string(), 2);
+        state.config = std::sync::Arc::new(config);
+        let worker = Worker::new(state, Duration::from_millis(10)).with_lease(Duration::ZERO);
+
+        database
+            .record_webhook_delivery("delivery-1", "ping", "{}")
+            .await
+            .unwrap();
+        let job = enqueue(
+            &database,
+            &Job::WebhookDelivery {
+                delivery_id: "delivery-1".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        // A worker claims the job and stops without finishing it
+        database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.job.id, job.id);
+        assert_eq!(run.job.attempts, 2);
+        assert_eq!(run.outcome, JobOutcome::Completed);
+
+        // Abandoning the last attempt uses the job up
+        let job = enqueue(
+            &database,
+            &Job::WebhookDelivery {
+                delivery_id: "delivery-1".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        database
+            .retry_job(job.id, "boom", Utc::now())
+            .await
+            .unwrap();
+        database.claim_next_job(JOB_LEASE).await.unwrap().unwrap();
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.outcome, JobOutcome::Dead);
+        let dead = database.get_processing_job(job.id).await.unwrap();
+        assert_eq!(dead.status, "dead");
+        assert_eq!(dead.attempts, 3);
+    }
+
+    #[sqlx::test]
+    async fn test_failed_config_fetch_reports_an_error(pool: PgPool) {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .with_status(404)
+            .with_body(r#"{"message": "Not Found"}"#)
+            .create_async()
+            .await;
+        let database = Database::from_pool(pool);
+        let mut state = test_state(database.clone()).await;
+        state.github = GitHubClients::from_clients(vec![GitHubClient::with_base_uri(
+            "test-org",
+            &server.url(),
+        )]);
+        let worker = Worker::new(state, Duration::from_millis(10));
+
+        let job = enqueue(
+            &database,
+            &Job::ProcessRepository {
+                repository: "test-org/test-repo".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert!(run.result.is_err());
+
+        let statuses: Vec<_> = database
+            .list_job_events(job.id, 0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.status)
+            .collect();
+        assert_eq!(statuses, vec!["processing", "error"]);
+    }
+
+    #[sqlx::test]
+    async fn test_worker_stops_on_shutdown(pool: PgPool) {
+        let worker = Worker::new(
+            test_state(Database::from_pool(pool)).await,
+            Duration::from_secs(60),
+        );
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        let handle = tokio::spawn(async move { worker.run(shutdown_rx).await });
+        shutdown_tx.send(true).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .expect("Worker did not stop")
+            .unwrap();
+    }
+}
@@ -7,11 +7,17 @@ pub mod config;
 pub mod console;
 pub mod database;
 pub mod github;
+pub mod health;
+pub mod jobs;
+pub mod mcp;
+pub mod metrics;
 pub mod output;
 pub mod processing;
 pub mod web;
 
-pub use config::{ApplicationConfig, ApplicationConfigError};
+pub use config::{
+    ApplicationConfig, ApplicationConfigError, GitHubAuth, OrganizationConfig, TlsConfig,
+};
 pub use console::{Console, RepoStatus};
 pub use database::{Database, DatabaseError};
 
@@ -34,6 +40,20 @@ pub struct ProjectConfig {
     pub documents: HashMap<String, DocumentConfig>,
 }
 
+impl ProjectConfig {
+    /// All file paths referenced by the configuration, including nested sub-documents,
+    /// sorted and without duplicates.
+    pub fn document_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for document in self.documents.values() {
+            collect_document_paths(document, &mut paths);
+        }
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum OutputFormat {
     Files,
@@ -54,3 +74,15 @@ pub fn count_document_paths(document: &DocumentConfig) -> usize {
 
     count
 }
+
+fn collect_document_paths(document: &DocumentConfig, paths: &mut Vec<String>) {
+    if let Some(path) = &document.path {
+        paths.push(path.display().to_string());
+    }
+
+    if let Some(sub_docs) = &document.sub_documents {
+        for sub_doc in sub_docs {
+            collect_document_paths(sub_doc, paths);
+        }
+    }
+}
@@ -0,0 +1,665 @@
+//! A Model Context Protocol server exposing the organization's documents to LLM tools.
+//!
+//! The protocol is JSON-RPC 2.0. [`McpServer`] handles messages independently of the
+//! transport; [`stdio`] serves it over stdin/stdout for the `mcp` command and
+//! `crate::web::mcp` over streamable HTTP at `/mcp`.
+
+pub mod stdio;
+
+use crate::database::{Database, DatabaseError, Repository};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Protocol versions this server speaks, newest first.
+pub const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Scheme of the resource URIs of documents: `documents://{owner}/{repo}/{path}`.
+pub const RESOURCE_SCHEME: &str = "documents://";
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<DatabaseError> for RpcError {
+    fn from(error: DatabaseError) -> Self {
+        RpcError::new(INTERNAL_ERROR, error.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryArgs {
+    repository: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentArgs {
+    repository: String,
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArgs {
+    query: String,
+    repository: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Serves the documents of the configured organizations over MCP.
+#[derive(Clone, Debug)]
+pub struct McpServer {
+    database: Database,
+    organizations: Vec<String>,
+}
+
+impl McpServer {
+    pub fn new(database: Database, organizations: Vec<String>) -> Self {
+        Self {
+            database,
+            organizations,
+        }
+    }
+
+    /// Handle a serialized JSON-RPC message or batch. Returns the serialized response, or
+    /// `None` when the input only held notifications.
+    pub async fn handle_json(&self, input: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(input) {
+            Ok(message) => self.handle_message(message).await?,
+            Err(e) => error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+        };
+        Some(response.to_string())
+    }
+
+    /// Handle a JSON-RPC message or batch of messages.
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        match message {
+            Value::Array(messages) if messages.is_empty() => Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, "Empty batch"),
+            )),
+            Value::Array(messages) => {
+                let mut responses = Vec::new();
+                for message in messages {
+                    if let Some(response) = self.handle_single(message).await {
+                        responses.push(response);
+                    }
+                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_single(message).await,
+        }
+    }
+
+    async fn handle_single(&self, message: Value) -> Option<Value> {
+        // Responses from the client (we never send requests) are accepted and ignored
+        if message.get("method").is_none()
+            && (message.get("result").is_some() || message.get("error").is_some())
+        {
+            return None;
+        }
+
+        let request: Request = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(INVALID_REQUEST, e.to_string()),
+                ));
+            }
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(error_response(
+                request.id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+            ));
+        }
+
+        let result = self.dispatch(&request.method, request.params).await;
+        let id = request.id?;
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => {
+                let call: ToolCall = parse_params(params)?;
+                self.call_tool(call).await
+            }
+            "resources/list" => self.list_resources().await,
+            "resources/templates/list" => Ok(json!({
+                "resourceTemplates": [{
+                    "uriTemplate": format!("{}{{owner}}/{{repo}}/{{path}}", RESOURCE_SCHEME),
+                    "name": "Document",
+                    "description": "A processed document of a repository, by its path",
+                    "mimeType": "text/markdown",
+                }]
+            })),
+            "resources/read" => {
+                let uri = params
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "uri is required"))?;
+                self.read_resource(uri).await
+            }
+            method if method.starts_with("notifications/") => Ok(Value::Null),
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    async fn call_tool(&self, call: ToolCall) -> Result<Value, RpcError> {
+        let outcome = match call.name.as_str() {
+            "list_repositories" => self.list_repositories().await?,
+            "list_documents" => {
+                let args: RepositoryArgs = parse_params(call.arguments)?;
+                self.list_documents(&args.repository).await?
+            }
+            "read_document" => {
+                let args: DocumentArgs = parse_params(call.arguments)?;
+                self.read_document(&args.repository, &args.path).await?
+            }
+            "search_documents" => {
+                let args: SearchArgs = parse_params(call.arguments)?;
+                self.search(args).await?
+            }
+            name => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("Unknown tool: {}", name),
+                ));
+            }
+        };
+
+        // Failures the model can act on are tool results, not protocol errors
+        Ok(match outcome {
+            Ok(text) => json!({"content": [{"type": "text", "text": text}], "isError": false}),
+            Err(message) => {
+                json!({"content": [{"type": "text", "text": message}], "isError": true})
+            }
+        })
+    }
+
+    async fn list_repositories(&self) -> Result<Result<String, String>, RpcError> {
+        let repos: Vec<Value> = self
+            .database
+            .list_repositories_with_documents()
+            .await?
+            .into_iter()
+            .map(|repo| {
+                json!({
+                    "name": repo.full_name,
+                    "description": repo.description,
+                    "last_processed_at": repo.last_processed_at,
+                })
+            })
+            .collect();
+
+        Ok(Ok(pretty(&repos)))
+    }
+
+    async fn list_documents(&self, name: &str) -> Result<Result<String, String>, RpcError> {
+        let Some(repo) = self.find_repository(name).await? else {
+            return Ok(Err(format!("Repository {} not found", name)));
+        };
+
+        let docs: Vec<Value> = self
+            .database
+            .get_documents_by_repository(repo.id)
+            .await?
+            .into_iter()
+            .map(|doc| {
+                json!({
+                    "path": doc.file_path,
+                    "title": doc.title,
+                    "uri": resource_uri(&repo.full_name, &doc.file_path),
+                    "updated_at": doc.updated_at,
+                })
+            })
+            .collect();
+
+        Ok(Ok(pretty(&docs)))
+    }
+
+    async fn read_document(
+        &self,
+        name: &str,
+        path: &str,
+    ) -> Result<Result<String, String>, RpcError> {
+        let Some(repo) = self.find_repository(name).await? else {
+            return Ok(Err(format!("Repository {} not found", name)));
+        };
+
+        match self.database.get_document_by_path(repo.id, path).await {
+            Ok(doc) => Ok(Ok(doc.content)),
+            Err(DatabaseError::DocumentNotFound(_)) => Ok(Err(format!(
+                "Document {} not found in repository {}",
+                path, name
+            ))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn search(&self, args: SearchArgs) -> Result<Result<String, String>, RpcError> {
+        let query = args.query.trim();
+        if query.is_empty() {
+            return Ok(Err("The query must not be empty".to_string()));
+        }
+
+        let limit = args
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        let results = self
+            .database
+            .search_documents(query, args.repository.as_deref(), &[], limit)
+            .await?;
+
+        Ok(Ok(pretty(&results)))
+    }
+
+    async fn list_resources(&self) -> Result<Value, RpcError> {
+        let mut resources = Vec::new();
+        for repo in self.database.list_repositories_with_documents().await? {
+            for doc in self.database.get_documents_by_repository(repo.id).await? {
+                resources.push(json!({
+                    "uri": resource_uri(&repo.full_name, &doc.file_path),
+                    "name": format!("{}/{}", repo.full_name, doc.file_path),
+                    "title": doc.title,
+                    "mimeType": "text/markdown",
+                    "size": doc.file_size,
+                }));
+            }
+        }
+
+        Ok(json!({ "resources": resources }))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<Value, RpcError> {
+        let not_found =
+            || RpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri));
+        let (name, path) = parse_resource_uri(uri).ok_or_else(not_found)?;
+
+        let repo = self.find_repository(name).await?.ok_or_else(not_found)?;
+        let doc = match self.database.get_document_by_path(repo.id, path).await {
+            Ok(doc) => doc,
+            Err(DatabaseError::DocumentNotFound(_)) => return Err(not_found()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": "text/markdown",
+                "text": doc.content,
+            }]
+        }))
+    }
+
+    /// Look up a documented repository of the organizations by `owner/name`, or by its short
+    /// name in the first organization that has one.
+    async fn find_repository(&self, name: &str) -> Result<Option<Repository>, DatabaseError> {
+        self.database
+            .find_documented_repository(&self.organizations, name)
+            .await
+    }
+}
+
+/// The URI of a document of the repository `full_name` (`owner/name`).
+pub fn resource_uri(full_name: &str, path: &str) -> String {
+    format!("{}{}/{}", RESOURCE_SCHEME, full_name, path)
+}
+
+/// Split a `documents://{owner}/{repo}/{path}` URI into the repository's `owner/name` and
+/// the document path.
+pub fn parse_resource_uri(uri: &str) -> Option<(&str, &str)> {
+    let rest = uri.strip_prefix(RESOURCE_SCHEME)?;
+    let (owner, after_owner) = rest.split_once('/')?;
+    let (name, path) = after_owner.split_once('/')?;
+    let full_name = &rest[..owner.len() + 1 + name.len()];
+    (!owner.is_empty() && !name.is_empty() && !path.is_empty()).then_some((full_name, path))
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "tools": {},
+            "resources": {},
+        },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "instructions": "Documentation of the organization's repositories. Use search_documents \
+            to find relevant documents, then read_document to read them in full.",
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "list_repositories",
+            "description": "List the repositories that have documentation",
+            "inputSchema": {"type": "object", "properties": {}},
+        },
+        {
+            "name": "list_documents",
+            "description": "List the documents of a repository with their paths and titles",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repository": {"type": "string", "description": "Repository as owner/name"},
+                },
+                "required": ["repository"],
+            },
+        },
+        {
+            "name": "read_document",
+            "description": "Read the markdown content of a document",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repository": {"type": "string", "description": "Repository as owner/name"},
+                    "path": {"type": "string", "description": "Path of the document in the repository"},
+                },
+                "required": ["repository", "path"],
+            },
+        },
+        {
+            "name": "search_documents",
+            "description": "Full-text search over all documents. Supports \"exact phrases\", \
+                `or` and -excluded words.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string"},
+                    "repository": {"type": "string", "description": "Only search this repository, as owner/name"},
+                    "limit": {"type": "integer", "minimum": 1, "maximum": MAX_SEARCH_LIMIT},
+                },
+                "required": ["query"],
+            },
+        },
+    ])
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+fn pretty(value: &impl serde::Serialize) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Document;
+    use crate::processing::persistence::content_hash;
+    use chrono::Utc;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn server(pool: PgPool) -> McpServer {
+        let database = Database::from_pool(pool);
+        for (owner, content) in [
+            ("test-org", "# Guide\n\nHow to rotate the signing token."),
+            ("other-org", "# Other guide\n\nHow to deploy."),
+        ] {
+            seed(&database, owner, content).await;
+        }
+
+        McpServer::new(
+            database,
+            vec!["test-org".to_string(), "other-org".to_string()],
+        )
+    }
+
+    /// A `test-repo` of `owner` with a `docs/guide.md` holding `content`.
+    async fn seed(database: &Database, owner: &str, content: &str) {
+        let repo = database
+            .upsert_repository(&Repository {
+                id: Uuid::new_v4(),
+                name: "test-repo".to_string(),
+                full_name: format!("{}/test-repo", owner),
+                owner: owner.to_string(),
+                description: Some("A test repository".to_string()),
+                default_branch: "main".to_string(),
+                is_private: false,
+                is_archived: false,
+                is_fork: false,
+                has_documents_config: true,
+                documents_config: None,
+                last_scanned_at: None,
+                last_processed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                github_id: None,
+                deleted_at: None,
+            })
+            .await
+            .unwrap();
+        database
+            .upsert_document(&Document {
+                id: Uuid::new_v4(),
+                repository_id: repo.id,
+                file_path: "docs/guide.md".to_string(),
+                title: "Guide".to_string(),
+                content: content.to_string(),
+                content_hash: content_hash(content),
+                metadata: None,
+                file_size: content.len() as i64,
+                last_modified_at: Utc::now(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                headings: "Guide".to_string(),
+                tags: vec![],
+                last_commit_sha: None,
+                last_author: None,
+                contributors: 0,
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn call(server: &McpServer, method: &str, params: Value) -> Value {
+        server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_resource_uri() {
+        assert_eq!(
+            parse_resource_uri("documents://org/repo/docs/a.md"),
+            Some(("org/repo", "docs/a.md"))
+        );
+        assert_eq!(parse_resource_uri("documents://org/repo/"), None);
+        assert_eq!(parse_resource_uri("documents://repo/a.md"), None);
+        assert_eq!(parse_resource_uri("file:///repo/a.md"), None);
+    }
+
+    #[sqlx::test]
+    async fn test_initialize_and_notifications(pool: PgPool) {
+        let server = server(pool).await;
+
+        let response = call(
+            &server,
+            "initialize",
+            json!({"protocolVersion": "2025-03-26"}),
+        )
+        .await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(server.handle_message(notification).await.is_none());
+
+        let response = call(&server, "unknown/method", Value::Null).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = server.handle_json("{not json").await.unwrap();
+        assert!(response.contains("-32700"));
+    }
+
+    #[sqlx::test]
+    async fn test_tools(pool: PgPool) {
+        let server = server(pool).await;
+
+        let response = call(&server, "tools/list", Value::Null).await;
+        assert_eq!(response["result"]["tools"].as_array().unwrap().len(), 4);
+
+        let response = call(
+            &server,
+            "tools/call",
+            json!({"name": "read_document", "arguments": {"repository": "other-org/test-repo", "path": "docs/guide.md"}}),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], false);
+        assert!(
+            response["result"]["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("# Other guide")
+        );
+
+        // A bare name is looked up in the first organization that has it
+        let response = call(
+            &server,
+            "tools/call",
+            json!({"name": "read_document", "arguments": {"repository": "test-repo", "path": "docs/guide.md"}}),
+        )
+        .await;
+        assert!(
+            response["result"]["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("# Guide")
+        );
+
+        let response = call(
+            &server,
+            "tools/call",
+            json!({"name": "list_documents", "arguments": {"repository": "missing"}}),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = call(
+            &server,
+            "tools/call",
+            json!({"name": "search_documents", "arguments": {"query": "signing token"}}),
+        )
+        .await;
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("docs/guide.md"));
+        assert!(text.contains("\"test-org/test-repo\""));
+    }
+
+    #[sqlx::test]
+    async fn test_resources(pool: PgPool) {
+        let server = server(pool).await;
+
+        let response = call(&server, "resources/list", Value::Null).await;
+        let mut uris: Vec<&str> = response["result"]["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resource| resource["uri"].as_str().unwrap())
+            .collect();
+        uris.sort();
+        assert_eq!(
+            uris,
+            [
+                "documents://other-org/test-repo/docs/guide.md",
+                "documents://test-org/test-repo/docs/guide.md"
+            ]
+        );
+
+        let response = call(
+            &server,
+            "resources/read",
+            json!({"uri": "documents://other-org/test-repo/docs/guide.md"}),
+        )
+        .await;
+        assert_eq!(
+            response["result"]["contents"][0]["mimeType"],
+            "text/markdown"
+        );
+        assert!(
+            response["result"]["contents"][0]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("# Other guide")
+        );
+
+        let response = call(
+            &server,
+            "resources/read",
+            json!({"uri": "documents://test-org/test-repo/docs/missing.md"}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], RESOURCE_NOT_FOUND);
+    }
+}
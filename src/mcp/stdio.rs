@@ -0,0 +1,62 @@
+use crate::mcp::McpServer;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Serve MCP over stdin and stdout, one JSON-RPC message per line, until stdin closes.
+///
+/// Nothing else may be written to stdout while this runs; logs go to stderr.
+pub async fn serve(server: &McpServer) -> std::io::Result<()> {
+    serve_streams(server, tokio::io::stdin(), tokio::io::stdout()).await
+}
+
+async fn serve_streams(
+    server: &McpServer,
+    input: impl tokio::io::AsyncRead + Unpin,
+    mut output: impl AsyncWrite + Unpin,
+) -> std::io::Result<()> {
+    let mut lines = BufReader::new(input).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = server.handle_json(&line).await {
+            output.write_all(response.as_bytes()).await?;
+            output.write_all(b"\n").await?;
+            output.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_serve_streams(pool: PgPool) {
+        let server = McpServer::new(Database::from_pool(pool), vec!["test-org".to_string()]);
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+
+        serve_streams(&server, input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], r#"{"id":1,"jsonrpc":"2.0","result":{}}"#);
+        assert!(lines[1].contains("list_repositories"));
+    }
+}
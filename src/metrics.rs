@@ -0,0 +1,148 @@
+//! Prometheus metrics, exported by `serve` at `/metrics`.
+//!
+//! Metrics are recorded in the process doing the work, so jobs run by a separate `worker`
+//! process only show up in the queue depth, which is read from the database on every scrape.
+
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::LazyLock;
+
+/// Metrics of this process.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    /// HTTP requests by method, matched route and status code
+    pub http_requests: IntCounterVec,
+    /// HTTP request latency by method and matched route
+    pub http_request_duration: HistogramVec,
+    /// Verified GitHub webhook deliveries by event type
+    pub webhook_deliveries: IntCounterVec,
+    /// Jobs in the queue by status, refreshed on every scrape
+    pub jobs: IntGaugeVec,
+    /// Time taken to run a job, by job type and outcome
+    pub job_duration: HistogramVec,
+    /// Requests made to the GitHub API, by endpoint
+    pub github_requests: IntCounterVec,
+    /// Remaining GitHub REST API requests, as of the last rate limit check
+    pub github_rate_limit_remaining: IntGauge,
+    /// Documents written to the database, by repository
+    pub documents_processed: IntCounterVec,
+    /// Database pool connections by state (idle or in use), refreshed on every scrape
+    pub database_connections: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("documents".to_string()), None)
+            .expect("metric namespace is valid");
+
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests handled"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+                &["method", "route"],
+            )
+            .unwrap(),
+            webhook_deliveries: IntCounterVec::new(
+                Opts::new(
+                    "webhook_deliveries_total",
+                    "Verified GitHub webhook deliveries",
+                ),
+                &["event_type"],
+            )
+            .unwrap(),
+            jobs: IntGaugeVec::new(Opts::new("jobs", "Jobs in the queue"), &["status"]).unwrap(),
+            job_duration: HistogramVec::new(
+                HistogramOpts::new("job_duration_seconds", "Time taken to run a job").buckets(
+                    vec![
+                        0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                    ],
+                ),
+                &["job_type", "outcome"],
+            )
+            .unwrap(),
+            github_requests: IntCounterVec::new(
+                Opts::new("github_requests_total", "Requests made to the GitHub API"),
+                &["endpoint"],
+            )
+            .unwrap(),
+            github_rate_limit_remaining: IntGauge::new(
+                "github_rate_limit_remaining",
+                "Remaining GitHub REST API requests as of the last rate limit check",
+            )
+            .unwrap(),
+            documents_processed: IntCounterVec::new(
+                Opts::new(
+                    "documents_processed_total",
+                    "Documents written to the database",
+                ),
+                &["repository"],
+            )
+            .unwrap(),
+            database_connections: IntGaugeVec::new(
+                Opts::new("database_connections", "Database pool connections"),
+                &["state"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: [Box<dyn Collector>; 9] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.webhook_deliveries.clone()),
+            Box::new(metrics.jobs.clone()),
+            Box::new(metrics.job_duration.clone()),
+            Box::new(metrics.github_requests.clone()),
+            Box::new(metrics.github_rate_limit_remaining.clone()),
+            Box::new(metrics.documents_processed.clone()),
+            Box::new(metrics.database_connections.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        metrics
+    }
+
+    /// Count a request to the GitHub API.
+    pub fn github_request(&self, endpoint: &str) {
+        self.github_requests.with_label_values(&[endpoint]).inc();
+    }
+
+    /// Render every metric in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding into a Vec cannot fail");
+        String::from_utf8(buffer).expect("text encoding is UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        METRICS.github_request("graphql");
+        METRICS.github_rate_limit_remaining.set(4321);
+
+        let text = METRICS.encode();
+
+        assert!(text.contains("documents_github_requests_total{endpoint=\"graphql\"}"));
+        assert!(text.contains("documents_github_rate_limit_remaining 4321"));
+    }
+}
@@ -1,10 +1,14 @@
 use crate::processing::pipeline::{DiscoveredFile, PipelineError, ProcessingContext};
 use regex::Regex;
 use std::collections::HashSet;
+use tokio::sync::OnceCell;
 use tracing::debug;
 
 pub struct FileDiscoverer<'a> {
     context: &'a ProcessingContext,
+    /// Paths of every file in the repository, fetched once for all patterns. Holds `None`
+    /// when the tree was truncated or could not be fetched, and directories are walked.
+    tree: OnceCell<Option<Vec<String>>>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,7 +32,49 @@ impl PatternType {
 
 impl<'a> FileDiscoverer<'a> {
     pub fn new(context: &'a ProcessingContext) -> Self {
-        Self { context }
+        Self {
+            context,
+            tree: OnceCell::new(),
+        }
+    }
+
+    /// Paths of every file in the repository from a single recursive tree listing, or
+    /// `None` if the files have to be found by walking directories.
+    async fn tree_paths(&self) -> Option<&[String]> {
+        self.tree
+            .get_or_init(|| async {
+                let tree = self
+                    .context
+                    .github_client
+                    .get_tree(&self.context.repository, self.context.git_ref.as_deref())
+                    .await;
+
+                match tree {
+                    Ok(tree) if tree.truncated => {
+                        debug!(
+                            "Tree of {} is truncated, listing directories instead",
+                            self.context.repository
+                        );
+                        None
+                    }
+                    Ok(tree) => Some(
+                        tree.files
+                            .into_iter()
+                            .filter(|file| file.file_type == "file")
+                            .map(|file| file.path)
+                            .collect(),
+                    ),
+                    Err(e) => {
+                        debug!(
+                            "Error fetching the tree of {}, listing directories instead: {}",
+                            self.context.repository, e
+                        );
+                        None
+                    }
+                }
+            })
+            .await
+            .as_deref()
     }
 
     pub async fn discover(&self) -> Result<Vec<DiscoveredFile>, PipelineError> {
@@ -107,10 +153,14 @@ impl<'a> FileDiscoverer<'a> {
     ) -> Result<Vec<String>, PipelineError> {
         match pattern {
             PatternType::Exact(path) => {
+                if let Some(paths) = self.tree_paths().await {
+                    return Ok(paths.iter().filter(|file| *file == path).cloned().collect());
+                }
+
                 match self
                     .context
                     .github_client
-                    .file_exists(&self.context.repository, path)
+                    .file_exists(&self.context.repository, path, self.context.git_ref.as_deref())
                     .await
                 {
                     Ok(true) => Ok(vec![path.clone()]),
@@ -138,9 +188,13 @@ impl<'a> FileDiscoverer<'a> {
             PipelineError::InvalidPattern(format!("Invalid glob pattern '{}': {}", glob_pattern, e))
         })?;
 
-        // Start recursive search from root
-        self.search_directory_recursive("", &pattern, &mut matching_files, &mut visited_paths)
-            .await?;
+        if let Some(paths) = self.tree_paths().await {
+            matching_files.extend(paths.iter().filter(|path| pattern.matches(path)).cloned());
+        } else {
+            // Start recursive search from root
+            self.search_directory_recursive("", &pattern, &mut matching_files, &mut visited_paths)
+                .await?;
+        }
 
         debug!(
             "Found {} files matching glob pattern '{}'",
@@ -161,11 +215,15 @@ impl<'a> FileDiscoverer<'a> {
         let mut matching_files = Vec::new();
         let mut visited_paths = HashSet::new();
 
-        // Start recursive search from root
-        let pin = self
-            .search_directory_recursive_regex("", &regex, &mut matching_files, &mut visited_paths)
-            .await;
-        pin.await?;
+        if let Some(paths) = self.tree_paths().await {
+            matching_files.extend(paths.iter().filter(|path| regex.is_match(path)).cloned());
+        } else {
+            // Start recursive search from root
+            let pin = self
+                .search_directory_recursive_regex("", &regex, &mut matching_files, &mut visited_paths)
+                .await;
+            pin.await?;
+        }
 
         debug!(
             "Found {} files matching regex pattern '{}'",
@@ -191,7 +249,11 @@ impl<'a> FileDiscoverer<'a> {
             let files = match self
                 .context
                 .github_client
-                .list_repository_files(&self.context.repository, Some(current_path))
+                .list_repository_files(
+                    &self.context.repository,
+                    Some(current_path),
+                    self.context.git_ref.as_deref(),
+                )
                 .await
             {
                 Ok(files) => files,
@@ -244,7 +306,11 @@ impl<'a> FileDiscoverer<'a> {
             let files = match self
                 .context
                 .github_client
-                .list_repository_files(&self.context.repository, Some(current_path))
+                .list_repository_files(
+                    &self.context.repository,
+                    Some(current_path),
+                    self.context.git_ref.as_deref(),
+                )
                 .await
             {
                 Ok(files) => files,
@@ -322,22 +388,15 @@ mod tests {
 
         ProcessingContext {
             repository: "test-repo".to_string(),
+            git_ref: None,
             github_client,
             config,
             processor,
         }
     }
 
-    fn create_test_context_with_files() -> ProcessingContext {
-        let config = crate::ProjectConfig {
-            project: ProjectDetails {
-                name: "Test Project".to_string(),
-                description: "A test project".to_string(),
-            },
-            documents: HashMap::new(),
-        };
-
-        // Create a mock GitHub client with test files
+    // A mock GitHub client with test files
+    fn mock_client_with_files() -> MockGitHubClient {
         let mut mock_client = MockGitHubClient::new();
         mock_client.add_file("README.md", "# Test Project");
         mock_client.add_file("CHANGELOG.md", "# Changelog");
@@ -346,8 +405,19 @@ mod tests {
         mock_client.add_file("docs/api.md", "# API");
         mock_client.add_directory("docs/tutorials");
         mock_client.add_file("docs/tutorials/getting-started.md", "# Getting Started");
+        mock_client
+    }
 
-        let github_client = Arc::new(mock_client);
+    fn create_test_context_with_files() -> ProcessingContext {
+        let config = crate::ProjectConfig {
+            project: ProjectDetails {
+                name: "Test Project".to_string(),
+                description: "A test project".to_string(),
+            },
+            documents: HashMap::new(),
+        };
+
+        let github_client = Arc::new(mock_client_with_files());
 
         let processor = crate::processing::RepositoryProcessor::new(
             MockGitHubClient::new(),
@@ -357,6 +427,7 @@ mod tests {
 
         ProcessingContext {
             repository: "test-repo".to_string(),
+            git_ref: None,
             github_client,
             config,
             processor,
@@ -517,4 +588,46 @@ mod tests {
             "Expected to find CHANGELOG.md"
         );
     }
+
+    #[tokio::test]
+    async fn test_patterns_share_one_tree_listing() {
+        let client = Arc::new(mock_client_with_files());
+        let mut context = create_test_context_with_files();
+        context.github_client = client.clone();
+        let discoverer = FileDiscoverer::new(&context);
+
+        let files = discoverer.discover_with_patterns().await.unwrap();
+        let paths: HashSet<_> = files.iter().map(|file| file.path.as_str()).collect();
+        assert!(paths.contains("README.md"));
+        assert!(paths.contains("docs/tutorials/getting-started.md"));
+        assert!(!paths.contains("docs"));
+
+        assert_eq!(client.tree_requests(), 1);
+        assert_eq!(client.listing_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_tree_falls_back_to_listing_directories() {
+        let mut client = mock_client_with_files();
+        client.set_tree_truncated(true);
+        let client = Arc::new(client);
+        let mut context = create_test_context_with_files();
+        context.github_client = client.clone();
+        let discoverer = FileDiscoverer::new(&context);
+
+        let pattern = PatternType::Glob("docs/**/*.md".to_string());
+        let mut result = discoverer.find_files_by_pattern(&pattern).await.unwrap();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                "docs/api.md",
+                "docs/guide.md",
+                "docs/tutorials/getting-started.md"
+            ]
+        );
+
+        assert_eq!(client.tree_requests(), 1);
+        assert!(client.listing_requests() > 0);
+    }
 }
@@ -1,6 +1,7 @@
 pub mod discovery;
 pub mod output_handler;
 pub mod path_normalization;
+pub mod persistence;
 pub mod pipeline;
 pub mod processor;
 pub mod validate_config;
@@ -15,7 +16,7 @@ pub use pipeline::{
 pub use validate_config::ConfigValidator;
 
 use crate::ProjectConfig;
-use crate::github::Client;
+use crate::github::{Client, FileHistory, GitHubError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -29,13 +30,23 @@ pub enum ProcessingError {
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] crate::database::DatabaseError),
     #[error("Processing error: {0}")]
     Processing(String),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingResult {
     pub repository: String,
+    /// The branch, tag or commit SHA that was asked for, or `None` for the default branch
+    #[serde(default)]
+    pub git_ref: Option<String>,
+    /// SHA of the commit the documents were read from
+    #[serde(default)]
+    pub commit_sha: String,
     pub processed_at: chrono::DateTime<chrono::Utc>,
     pub file_processed: usize,
     pub fragments_generated: usize,
@@ -53,6 +64,15 @@ pub struct DocumentFragment {
     pub metadata: HashMap<String, String>,
     pub word_count: usize,
     pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+    /// SHA of the latest commit touching the file
+    #[serde(default)]
+    pub last_commit_sha: Option<String>,
+    /// GitHub login of the author of that commit
+    #[serde(default)]
+    pub last_author: Option<String>,
+    /// Distinct authors of recent commits to the file
+    #[serde(default)]
+    pub contributors: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,11 +81,49 @@ pub enum FragmentType {
     Navigation,
 }
 
+/// Add the history of a file to its metadata, next to its frontmatter. Values set in the
+/// frontmatter are kept.
+pub fn add_history_metadata(metadata: &mut HashMap<String, String>, history: &FileHistory) {
+    let values = [
+        ("last_modified", Some(history.last_modified.to_rfc3339())),
+        ("last_commit", Some(history.commit_sha.clone())),
+        ("last_author", history.author.clone()),
+        ("contributors", Some(history.contributors.to_string())),
+    ];
+    for (key, value) in values {
+        if let Some(value) = value {
+            metadata.entry(key.to_string()).or_insert(value);
+        }
+    }
+}
+
+/// The history of files, or none if it cannot be fetched. Documents are still worth
+/// processing without it, but running out of rate limit stops processing.
+pub(crate) async fn fetch_file_history(
+    github: &(dyn Client + Send + Sync),
+    repository: &str,
+    file_paths: &[String],
+    git_ref: Option<&str>,
+) -> Result<HashMap<String, FileHistory>, GitHubError> {
+    match github
+        .batch_fetch_file_history(repository, file_paths, git_ref)
+        .await
+    {
+        Ok(history) => Ok(history),
+        Err(e @ GitHubError::RateLimitExceeded { .. }) => Err(e),
+        Err(e) => {
+            tracing::warn!("Failed to fetch the history of {}: {}", repository, e);
+            Ok(HashMap::new())
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RepositoryProcessor {
     github: Arc<dyn Client + Send + Sync>,
     config: ProjectConfig,
     repository: String,
+    git_ref: Option<String>,
 }
 
 impl std::fmt::Debug for RepositoryProcessor {
@@ -73,6 +131,7 @@ impl std::fmt::Debug for RepositoryProcessor {
         f.debug_struct("RepositoryProcessor")
             .field("config", &self.config)
             .field("repository", &self.repository)
+            .field("git_ref", &self.git_ref)
             .finish()
     }
 }
@@ -87,12 +146,25 @@ impl RepositoryProcessor {
             github: Arc::new(github),
             config,
             repository,
+            git_ref: None,
         }
     }
 
-    pub async fn process(&self, verbose: bool) -> Result<ProcessingResult, ProcessingError> {
-        let start_time = std::time::Instant::now();
+    /// Read the documents at a branch, tag or commit SHA instead of the default branch.
+    pub fn with_ref(mut self, git_ref: Option<String>) -> Self {
+        self.git_ref = git_ref;
+        self
+    }
+
+    pub fn git_ref(&self) -> Option<&str> {
+        self.git_ref.as_deref()
+    }
+
+    pub fn config(&self) -> &ProjectConfig {
+        &self.config
+    }
 
+    pub async fn process(&self, verbose: bool) -> Result<ProcessingResult, ProcessingError> {
         tracing::info!("Starting processing of repository {}", self.repository);
 
         // Step 1: Discover markdown files
@@ -105,17 +177,54 @@ impl RepositoryProcessor {
             }
         }
 
+        self.process_files(&markdown_files, verbose).await
+    }
+
+    /// Process only the given markdown files instead of everything referenced by the
+    /// configuration. Used for incremental updates when just a few files changed.
+    pub async fn process_files(
+        &self,
+        markdown_files: &[String],
+        verbose: bool,
+    ) -> Result<ProcessingResult, ProcessingError> {
+        let start_time = std::time::Instant::now();
+
+        // Pin the ref to a commit so every file is read from the same one, even if the
+        // branch moves while processing
+        let commit_sha = self
+            .github
+            .resolve_ref(&self.repository, self.git_ref.as_deref())
+            .await
+            .map_err(ProcessingError::GitHub)?;
+
         // Step 2: Batch fetch all markdown file contents
         if verbose {
-            tracing::debug!("Batch fetching {} markdown files", markdown_files.len());
+            tracing::debug!(
+                "Batch fetching {} markdown files at {}",
+                markdown_files.len(),
+                commit_sha
+            );
         }
 
         let file_contents = self
             .github
-            .batch_fetch_files(&self.repository, &markdown_files)
+            .batch_fetch_files(&self.repository, markdown_files, Some(&commit_sha))
             .await
             .map_err(ProcessingError::GitHub)?;
 
+        let found: Vec<String> = markdown_files
+            .iter()
+            .filter(|path| matches!(file_contents.get(*path), Some(Some(_))))
+            .cloned()
+            .collect();
+        let history = fetch_file_history(
+            self.github.as_ref(),
+            &self.repository,
+            &found,
+            Some(&commit_sha),
+        )
+        .await?;
+
         // Step 3: Process each markdown file with its content
         let mut fragments = Vec::new();
         let mut files_processed = 0;
@@ -125,9 +234,10 @@ impl RepositoryProcessor {
                 tracing::debug!("Processing file: {}", file_path);
             }
 
-            match file_contents.get(&file_path) {
+            match file_contents.get(file_path) {
                 Some(Some(content)) => {
-                    match self.process_markdown_file_with_content(&file_path, content) {
+                    let file_history = history.get(file_path);
+                    match self.process_markdown_file_with_content(file_path, content, file_history) {
                         Ok(mut file_fragments) => {
                             files_processed += 1;
                             fragments.append(&mut file_fragments);
@@ -154,6 +264,8 @@ impl RepositoryProcessor {
 
         let result = ProcessingResult {
             repository: self.repository.clone(),
+            git_ref: self.git_ref.clone(),
+            commit_sha,
             processed_at: chrono::Utc::now(),
             file_processed: files_processed,
             fragments_generated: fragments.len(),
@@ -171,19 +283,8 @@ impl RepositoryProcessor {
             self.repository
         );
 
-        let patterns = self.config.documents.clone();
-        let mut discovered_files = Vec::new();
-
-        for document in patterns.values() {
-            if let Some(path) = &document.path {
-                discovered_files.push(path.display().to_string());
-            } else if let Some(sub_documents) = &document.sub_documents {
-                for sub_doc in sub_documents {
-                    if let Some(sub_path) = &sub_doc.path {
-                        discovered_files.push(sub_path.display().to_string());
-                    }
-                }
-            } else {
+        for document in self.config.documents.values() {
+            if document.path.is_none() && document.sub_documents.is_none() {
                 tracing::warn!(
                     "Document configuration for {} does not specify a path or sub-documents",
                     document.title
@@ -191,8 +292,8 @@ impl RepositoryProcessor {
             }
         }
 
-        discovered_files.sort();
-        discovered_files.dedup();
+        // The same paths push planning watches, so both agree on what a repository holds
+        let discovered_files = self.config.document_paths();
 
         tracing::debug!("Discovered {} markdown files", discovered_files.len());
 
@@ -203,10 +304,15 @@ impl RepositoryProcessor {
         &self,
         file_path: &str,
         content: &str,
+        history: Option<&FileHistory>,
     ) -> Result<Vec<DocumentFragment>, ProcessingError> {
         tracing::debug!("Processing markdown file with content: {}", file_path);
 
         let (frontmatter, markdown_content) = self.extract_frontmatter(content);
+        let mut metadata = frontmatter.clone();
+        if let Some(history) = history {
+            add_history_metadata(&mut metadata, history);
+        }
 
         // Generate fragments
         let mut fragments = Vec::new();
@@ -220,9 +326,12 @@ impl RepositoryProcessor {
                 .cloned()
                 .unwrap_or_else(|| "Untitled".to_string()),
             content: markdown_content.clone(),
-            metadata: frontmatter.clone(),
+            metadata,
             word_count: self.count_words(&markdown_content),
-            last_modified: None,
+            last_modified: history.map(|history| history.last_modified),
+            last_commit_sha: history.map(|history| history.commit_sha.clone()),
+            last_author: history.and_then(|history| history.author.clone()),
+            contributors: history.map_or(0, |history| history.contributors),
         };
         fragments.push(content_fragment);
 
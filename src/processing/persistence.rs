@@ -0,0 +1,301 @@
+use crate::ProjectConfig;
+use crate::database::{Database, DatabaseError, Document, Repository};
+use crate::metrics::METRICS;
+use crate::processing::{
+    ConfigValidator, DocumentFragment, FragmentType, ProcessingError, ProcessingResult,
+    RepositoryProcessor,
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// SHA-256 hex digest of document content, as stored in `documents.content_hash`.
+pub fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Collect the ATX headings of a markdown document, one per line and without the leading
+/// `#`s, skipping anything inside fenced code blocks.
+pub fn extract_headings(content: &str) -> String {
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let rest = &trimmed[level..];
+        if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            let text = rest.trim().trim_end_matches('#').trim();
+            if !text.is_empty() {
+                headings.push(text.to_string());
+            }
+        }
+    }
+
+    headings.join("\n")
+}
+
+/// Parse the `tags` frontmatter value. Both `[a, b]` and `a, b` are accepted.
+pub fn parse_tags(value: &str) -> Vec<String> {
+    let mut tags: Vec<String> = value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|tag| tag.trim().trim_matches(|c| c == '"' || c == '\'').trim())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Convert a content fragment into a row for the `documents` table.
+pub fn document_from_fragment(
+    repository_id: Uuid,
+    fragment: &DocumentFragment,
+    processed_at: DateTime<Utc>,
+) -> Document {
+    let metadata = if fragment.metadata.is_empty() {
+        None
+    } else {
+        serde_json::to_string(&fragment.metadata).ok()
+    };
+
+    Document {
+        id: Uuid::new_v4(),
+        repository_id,
+        file_path: fragment.file_path.clone(),
+        title: fragment.title.clone(),
+        content: fragment.content.clone(),
+        content_hash: content_hash(&fragment.content),
+        metadata,
+        file_size: fragment.content.len() as i64,
+        last_modified_at: fragment.last_modified.unwrap_or(processed_at),
+        created_at: processed_at,
+        updated_at: processed_at,
+        deleted_at: None,
+        headings: extract_headings(&fragment.content),
+        tags: fragment
+            .metadata
+            .get("tags")
+            .map(|tags| parse_tags(tags))
+            .unwrap_or_default(),
+        last_commit_sha: fragment.last_commit_sha.clone(),
+        last_author: fragment.last_author.clone(),
+        contributors: fragment.contributors as i32,
+    }
+}
+
+/// Store every content fragment of a processing result in the `documents` table.
+///
+/// Returns the number of documents written.
+pub async fn save_processing_result(
+    database: &Database,
+    repository_id: Uuid,
+    result: &ProcessingResult,
+) -> Result<usize, DatabaseError> {
+    let mut saved = 0;
+
+    for fragment in &result.fragments {
+        if !matches!(fragment.fragment_type, FragmentType::Content) {
+            continue;
+        }
+
+        let document = document_from_fragment(repository_id, fragment, result.processed_at);
+        database.upsert_document(&document).await?;
+        saved += 1;
+    }
+
+    tracing::info!(
+        "Saved {} documents for repository {}",
+        saved,
+        result.repository
+    );
+    METRICS
+        .documents_processed
+        .with_label_values(&[&result.repository])
+        .inc_by(saved as u64);
+
+    Ok(saved)
+}
+
+/// Process a whole repository and store its documents, deleting documents that are no
+/// longer produced by its configuration.
+///
+/// Returns the number of documents saved and removed.
+pub async fn sync_repository_documents(
+    database: &Database,
+    repository_id: Uuid,
+    processor: &RepositoryProcessor,
+) -> Result<(usize, u64), ProcessingError> {
+    let result = processor.process(false).await?;
+    let saved = save_processing_result(database, repository_id, &result).await?;
+
+    let kept_paths: Vec<String> = result
+        .fragments
+        .iter()
+        .map(|fragment| fragment.file_path.clone())
+        .collect();
+    let removed = database
+        .delete_documents_except(repository_id, &kept_paths)
+        .await?;
+
+    Ok((saved, removed))
+}
+
+/// Store a repository's configuration on its row, or clear it when the repository has none.
+pub fn with_config(row: Repository, config: Option<&ProjectConfig>) -> Repository {
+    Repository {
+        has_documents_config: config.is_some(),
+        documents_config: config.and_then(|c| serde_json::to_string(c).ok()),
+        updated_at: Utc::now(),
+        ..row
+    }
+}
+
+/// Validate the processor's configuration, save it on the repository row and replace the
+/// repository's documents with a full run of the processor.
+///
+/// Returns the saved row and the number of documents saved and removed.
+pub async fn process_repository(
+    database: &Database,
+    row: Repository,
+    processor: &RepositoryProcessor,
+) -> Result<(Repository, usize, u64), ProcessingError> {
+    let validation = ConfigValidator::new().validate(processor.config()).await;
+    if !validation.is_valid {
+        let error_count = validation.errors.len() + validation.errors_with_context.len();
+        return Err(ProcessingError::InvalidConfig(format!(
+            "documents.toml in {} failed validation with {} errors",
+            row.full_name, error_count
+        )));
+    }
+
+    let mut row = with_config(row, Some(processor.config()));
+    row.last_processed_at = Some(row.updated_at);
+    let row = database.upsert_repository(&row).await?;
+    let (saved, removed) = sync_repository_documents(database, row.id, processor).await?;
+
+    Ok((row, saved, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash("hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(content_hash("hello").len(), 64);
+    }
+
+    #[test]
+    fn test_document_from_fragment() {
+        let repository_id = Uuid::new_v4();
+        let processed_at = Utc::now();
+        let mut metadata = HashMap::new();
+        metadata.insert("title".to_string(), "Guide".to_string());
+        metadata.insert("tags".to_string(), "[setup, guide]".to_string());
+
+        let fragment = DocumentFragment {
+            id: "test-repo#docs/guide.md".to_string(),
+            file_path: "docs/guide.md".to_string(),
+            fragment_type: FragmentType::Content,
+            title: "Guide".to_string(),
+            content: "# Guide\n\nSome text.\n\n## Install".to_string(),
+            metadata,
+            word_count: 4,
+            last_modified: None,
+            last_commit_sha: None,
+            last_author: None,
+            contributors: 0,
+        };
+
+        let document = document_from_fragment(repository_id, &fragment, processed_at);
+
+        assert_eq!(document.repository_id, repository_id);
+        assert_eq!(document.file_path, "docs/guide.md");
+        assert_eq!(document.title, "Guide");
+        assert_eq!(document.file_size, fragment.content.len() as i64);
+        assert_eq!(document.content_hash, content_hash(&fragment.content));
+        assert_eq!(document.last_modified_at, processed_at);
+        assert!(
+            document
+                .metadata
+                .as_deref()
+                .unwrap()
+                .contains(r#""title":"Guide""#)
+        );
+        assert_eq!(document.headings, "Guide\nInstall");
+        assert_eq!(document.tags, vec!["guide", "setup"]);
+        assert_eq!(document.last_commit_sha, None);
+
+        let last_modified = "2024-05-01T10:00:00Z".parse().unwrap();
+        let fragment = DocumentFragment {
+            last_modified: Some(last_modified),
+            last_commit_sha: Some("c3".to_string()),
+            last_author: Some("ada".to_string()),
+            contributors: 2,
+            ..fragment
+        };
+        let document = document_from_fragment(repository_id, &fragment, processed_at);
+        assert_eq!(document.last_modified_at, last_modified);
+        assert_eq!(document.last_commit_sha.as_deref(), Some("c3"));
+        assert_eq!(document.last_author.as_deref(), Some("ada"));
+        assert_eq!(document.contributors, 2);
+    }
+
+    #[test]
+    fn test_extract_headings_skips_code_blocks() {
+        let content =
+            "# Title\n\ntext\n\n```bash\n# not a heading\n```\n\n### Section ###\n#hashtag-only\n";
+        assert_eq!(extract_headings(content), "Title\nSection");
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags("[api, \"rest\", api]"), vec!["api", "rest"]);
+        assert_eq!(parse_tags("setup, guide"), vec!["guide", "setup"]);
+        assert!(parse_tags("[]").is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_invalid_configuration_is_not_saved(pool: sqlx::PgPool) {
+        let database = Database::from_pool(pool);
+        let content = "[project]\nname = \"\"\ndescription = \"A test project\"\n\n[documents]\n";
+        let config: ProjectConfig = toml::from_str(content).unwrap();
+        let client = crate::github::GitHubClient::with_base_uri("test-org", "http://127.0.0.1:9");
+        let processor = RepositoryProcessor::new(client, config, "test-repo".to_string());
+
+        let result = process_repository(
+            &database,
+            Repository::new("test-org", "test-repo"),
+            &processor,
+        )
+        .await;
+
+        assert!(matches!(result, Err(ProcessingError::InvalidConfig(_))));
+        assert!(
+            database
+                .find_repository_by_full_name("test-org/test-repo")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
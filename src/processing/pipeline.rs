@@ -1,4 +1,4 @@
-use crate::github::Client;
+use crate::github::{Client, FileHistory};
 use crate::processing::RepositoryProcessor;
 use crate::processing::discovery::FileDiscoverer;
 use crate::processing::processor::ContentProcessor;
@@ -24,6 +24,8 @@ pub enum PipelineError {
 #[derive(Clone)]
 pub struct ProcessingContext {
     pub repository: String,
+    /// Branch, tag or commit SHA to read, or the default branch when `None`
+    pub git_ref: Option<String>,
     pub github_client: Arc<dyn Client + Send + Sync>,
     pub config: crate::ProjectConfig,
     pub processor: RepositoryProcessor,
@@ -96,6 +98,8 @@ pub struct ValidatedFile {
     pub frontmatter: HashMap<String, String>,
     pub markdown_content: String,
     pub validation_warnings: Vec<String>,
+    /// The latest commit touching the file, when GitHub returned it
+    pub history: Option<FileHistory>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -190,6 +194,7 @@ mod tests {
 
         ProcessingContext {
             repository: "test-repo".to_string(),
+            git_ref: None,
             github_client,
             config,
             processor,
@@ -328,6 +333,7 @@ mod tests {
             frontmatter,
             markdown_content: "# Test Document\n\nThis is a test document.".to_string(),
             validation_warnings: vec![],
+            history: None,
         }];
 
         // Process the files
@@ -477,4 +483,221 @@ mod tests {
         let validation_error = PipelineError::Validation("test error".to_string());
         assert_eq!(validation_error.to_string(), "Validation error: test error");
     }
+
+    #[tokio::test]
+    async fn test_files_are_read_at_the_ref() {
+        let mut client = MockGitHubClient::new();
+        client.add_file("docs/doc1.md", "# Current\n\nOn the default branch.");
+        client.add_file_at("v1.0", "docs/doc1.md", "# Release\n\nAs released in 1.0.");
+
+        let mut context = create_test_context();
+        context.github_client = Arc::new(client);
+        context.git_ref = Some("v1.0".to_string());
+        let pipeline = DocumentProcessingPipeline::new(context);
+
+        let validated = pipeline
+            .validate_files(vec![DiscoveredFile {
+                path: "docs/doc1.md".to_string(),
+                pattern_source: "exact".to_string(),
+                estimated_size: None,
+            }])
+            .await
+            .unwrap();
+        assert_eq!(validated.len(), 1);
+        assert!(validated[0].content.contains("As released in 1.0"));
+    }
+
+    #[tokio::test]
+    async fn test_processing_result_records_commit() {
+        let mut client = MockGitHubClient::new();
+        client.add_file("docs/doc1.md", "# Current\n\nOn the default branch.");
+        client.add_file_at("v1.0", "docs/doc1.md", "# Release\n\nAs released in 1.0.");
+        let tag_sha = client.resolve_ref("test-repo", Some("v1.0")).await.unwrap();
+        let head_sha = client.resolve_ref("test-repo", None).await.unwrap();
+
+        let config = create_test_context().config;
+        let processor = RepositoryProcessor::new(client, config, "test-repo".to_string())
+            .with_ref(Some("v1.0".to_string()));
+        let result = processor.process(false).await.unwrap();
+        assert_eq!(result.git_ref.as_deref(), Some("v1.0"));
+        assert_eq!(result.commit_sha, tag_sha);
+        assert!(result.fragments[0].content.contains("As released in 1.0"));
+
+        let processor = processor.with_ref(None);
+        let result = processor.process(false).await.unwrap();
+        assert_eq!(result.commit_sha, head_sha);
+        assert!(result.fragments[0].content.contains("On the default branch"));
+    }
+
+    #[tokio::test]
+    async fn test_history_flows_into_documents() {
+        let history = FileHistory {
+            last_modified: "2024-05-01T10:00:00Z".parse().unwrap(),
+            commit_sha: "c3".to_string(),
+            author: Some("ada".to_string()),
+            contributors: 2,
+        };
+        let client = || {
+            let mut client = MockGitHubClient::new();
+            client.add_file("docs/doc1.md", "---\nlast_author:grace\n---\n# Guide\n\nText.");
+            client.set_history("docs/doc1.md", history.clone());
+            client
+        };
+
+        let config = create_test_context().config;
+        let processor = RepositoryProcessor::new(client(), config, "test-repo".to_string());
+        let result = processor.process(false).await.unwrap();
+        let fragment = &result.fragments[0];
+        assert_eq!(fragment.last_modified, Some(history.last_modified));
+        assert_eq!(fragment.last_commit_sha.as_deref(), Some("c3"));
+        assert_eq!(fragment.last_author.as_deref(), Some("ada"));
+        assert_eq!(fragment.contributors, 2);
+        assert_eq!(fragment.metadata["last_commit"], "c3");
+        assert_eq!(fragment.metadata["contributors"], "2");
+        // Frontmatter wins over the history
+        assert_eq!(fragment.metadata["last_author"], "grace");
+
+        let mut context = create_test_context();
+        context.github_client = Arc::new(client());
+        let pipeline = DocumentProcessingPipeline::new(context);
+        let documents = pipeline.execute().await.unwrap();
+        let document = documents
+            .iter()
+            .find(|document| document.file_path == "docs/doc1.md")
+            .unwrap();
+        assert_eq!(document.last_modified, Some(history.last_modified));
+        assert_eq!(document.frontmatter["last_modified"], "2024-05-01T10:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_doubly_nested_documents_are_processed() {
+        let document = |title: &str, path: Option<&str>, sub_documents| crate::DocumentConfig {
+            title: title.to_string(),
+            path: path.map(Into::into),
+            sub_documents,
+        };
+        let mut config = create_test_context().config;
+        config.documents.insert(
+            "guide".to_string(),
+            document(
+                "Guide",
+                None,
+                Some(vec![document(
+                    "Setup",
+                    Some("guide/setup.md"),
+                    Some(vec![document("Linux", Some("guide/setup/linux.md"), None)]),
+                )]),
+            ),
+        );
+
+        let mut client = MockGitHubClient::new();
+        client.add_file("docs/doc1.md", "# Document 1\n\nText.");
+        client.add_file("guide/setup.md", "# Setup\n\nText.");
+        client.add_file("guide/setup/linux.md", "# Linux\n\nText.");
+
+        let processor = RepositoryProcessor::new(client, config.clone(), "test-repo".to_string());
+        let result = processor.process(false).await.unwrap();
+        let mut paths: Vec<_> = result
+            .fragments
+            .iter()
+            .map(|fragment| fragment.file_path.clone())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths, config.document_paths());
+        assert!(paths.contains(&"guide/setup/linux.md".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_files_are_processed_from_the_http_cache() {
+        use crate::github::{GitHubClient, HttpCache};
+        use mockito::Matcher;
+
+        // Files are read at the resolved commit without resolving it again
+        const COMMIT: &str = "c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1c1";
+
+        let mut server = mockito::Server::new_async().await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        fn graphql(
+            server: &mut mockito::Server,
+            query: &str,
+            body: serde_json::Value,
+        ) -> mockito::Mock {
+            server
+                .mock("POST", "/graphql")
+                .match_body(Matcher::Regex(regex::escape(query)))
+                .with_header("content-type", "application/json")
+                .with_body(body.to_string())
+        }
+        let resolved = graphql(
+            &mut server,
+            "Commit { oid }",
+            serde_json::json!({"data": {"repository": {"object": {"oid": COMMIT}}}}),
+        )
+        .expect(2)
+        .create_async()
+        .await;
+        let history = graphql(
+            &mut server,
+            "history(first",
+            serde_json::json!({"data": {"repository": {"object": {"file0": {"nodes": []}}}}}),
+        )
+        .expect(2)
+        .create_async()
+        .await;
+        // Only the first run reads the file; the second finds its blob in the cache
+        let contents = graphql(
+            &mut server,
+            "on Blob",
+            serde_json::json!({"data": {"repository": {"file0": {"id": "b1", "text": "# Guide\n\nText."}}}}),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let tree_route = format!("/repos/test-org/test-repo/git/trees/{}", COMMIT);
+        let tree_route = tree_route.as_str();
+        let tree = serde_json::json!({
+            "sha": "t1",
+            "truncated": false,
+            "tree": [
+                {"path": "docs", "type": "tree", "sha": "t2"},
+                {"path": "docs/doc1.md", "type": "blob", "sha": "b1", "size": 15},
+            ],
+        });
+        let listed = server
+            .mock("GET", tree_route)
+            .match_query(Matcher::UrlEncoded("recursive".into(), "1".into()))
+            .match_header("if-none-match", Matcher::Missing)
+            .with_header("etag", "\"t1\"")
+            .with_body(tree.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let revalidated = server
+            .mock("GET", tree_route)
+            .match_query(Matcher::UrlEncoded("recursive".into(), "1".into()))
+            .match_header("if-none-match", "\"t1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = GitHubClient::with_base_uri("test-org", &server.url())
+            .with_http_cache(HttpCache::new(cache_dir.path()));
+        let config = create_test_context().config;
+        let processor = RepositoryProcessor::new(client, config, "test-repo".to_string());
+        for _ in 0..2 {
+            let result = processor.process(false).await.unwrap();
+            assert_eq!(result.commit_sha, COMMIT);
+            assert_eq!(result.file_processed, 1);
+            assert!(result.fragments[0].content.contains("Text."));
+        }
+
+        resolved.assert_async().await;
+        history.assert_async().await;
+        contents.assert_async().await;
+        listed.assert_async().await;
+        revalidated.assert_async().await;
+    }
 }
@@ -1,3 +1,4 @@
+use crate::processing::add_history_metadata;
 use crate::processing::pipeline::{
     CodeBlock, Heading, Image, Link, PipelineError, ProcessedDocument, ProcessingMetadata,
     ValidatedFile,
@@ -48,17 +49,23 @@ impl ContentProcessor {
 
         let processing_time = start_time.elapsed();
 
+        let title = self.extract_title(&file.frontmatter, &headings);
+        let mut frontmatter = file.frontmatter;
+        if let Some(history) = &file.history {
+            add_history_metadata(&mut frontmatter, history);
+        }
+
         Ok(ProcessedDocument {
             file_path: file.discovered.path,
-            title: self.extract_title(&file.frontmatter, &headings),
+            title,
             content: file.markdown_content,
-            frontmatter: file.frontmatter,
+            frontmatter,
             word_count,
             headings,
             links,
             images,
             code_blocks,
-            last_modified: None, // TODO: Get from GitHub API
+            last_modified: file.history.as_ref().map(|history| history.last_modified),
             processing_metadata: ProcessingMetadata {
                 processed_at: chrono::Utc::now(),
                 processing_time_ms: processing_time.as_millis() as u64,
@@ -558,6 +565,7 @@ mod tests {
                 frontmatter: HashMap::new(),
                 markdown_content: "Test content".to_string(),
                 validation_warnings: warnings,
+                history: None,
             }
         };
 
@@ -650,6 +658,7 @@ mod tests {
             frontmatter,
             markdown_content: markdown_content.to_string(),
             validation_warnings: vec!["Test warning".to_string()],
+            history: None,
         };
 
         let start_time = std::time::Instant::now();
@@ -425,7 +425,7 @@ impl<'a> ConfigValidator<'a> {
                 format!("{}/{}", base, normalized_path)
             };
 
-            match client.file_exists(repo, &full_path).await {
+            match client.file_exists(repo, &full_path, None).await {
                 Ok(false) => {
                     result.add_error(context.create_missing_file_error(normalized_path));
                 }
@@ -1,3 +1,4 @@
+use crate::processing::fetch_file_history;
 use crate::processing::pipeline::{
     DiscoveredFile, PipelineError, ProcessingContext, ValidatedFile,
 };
@@ -21,8 +22,22 @@ impl<'a> ContentValidator<'a> {
         let fetched_files = self.context.github_client.batch_fetch_files(
             self.context.repository.as_str(),
             files.iter().map(|f| f.path.clone()).collect::<Vec<String>>().as_slice(),
+            self.context.git_ref.as_deref(),
         ).await?;
 
+        let found: Vec<String> = files
+            .iter()
+            .filter(|file| matches!(fetched_files.get(&file.path), Some(Some(_))))
+            .map(|file| file.path.clone())
+            .collect();
+        let history = fetch_file_history(
+            self.context.github_client.as_ref(),
+            &self.context.repository,
+            &found,
+            self.context.git_ref.as_deref(),
+        )
+        .await?;
+
         for file in &files {
             if let Some(content) = fetched_files.get(&file.path) {
                 if content.is_none() {
@@ -32,7 +47,10 @@ impl<'a> ContentValidator<'a> {
 
                 let content = content.as_ref().unwrap();
                 match self.validate_file(file, content).await {
-                    Ok(validated) => validated_files.push(validated),
+                    Ok(mut validated) => {
+                        validated.history = history.get(&file.path).cloned();
+                        validated_files.push(validated);
+                    }
                     Err(e) => {
                         tracing::warn!("Validation failed for file {}: {}", file.path, e);
                     }
@@ -58,6 +76,7 @@ impl<'a> ContentValidator<'a> {
             frontmatter,
             markdown_content,
             validation_warnings,
+            history: None,
         })
     }
 
@@ -174,6 +193,7 @@ mod tests {
 
         ProcessingContext {
             repository: "test-repo".to_string(),
+            git_ref: None,
             github_client,
             config,
             processor,
@@ -0,0 +1,539 @@
+use crate::database::{Database, JobEvent, ProcessingJob};
+use crate::jobs::{self, Job};
+use crate::web::api::json_column;
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::Json;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::{DateTime, Utc};
+use futures_lite::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// How often the event stream checks for new progress.
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Job statuses after which a job makes no more progress.
+const FINISHED_STATUSES: [&str; 4] = ["completed", "failed", "dead", "cancelled"];
+
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Scan,
+    Process,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CreateJobRequest {
+    pub kind: JobKind,
+    /// Organization to scan or process. Every configured organization when omitted.
+    pub organization: Option<String>,
+    /// Repository to scan or process, as `owner/name` or a name within `organization` (the
+    /// first configured organization by default). Whole organizations when omitted.
+    pub repository: Option<String>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct JobsQuery {
+    /// Only list jobs with this status
+    status: Option<String>,
+    /// Maximum number of jobs, at most 500
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct JobResponse {
+    pub id: Uuid,
+    pub job_type: String,
+    pub status: String,
+    pub parameters: Option<serde_json::Value>,
+    pub attempts: i32,
+    pub error_message: Option<String>,
+    pub run_after: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ProcessingJob> for JobResponse {
+    fn from(job: ProcessingJob) -> Self {
+        Self {
+            parameters: json_column(job.parameters.as_deref()),
+            id: job.id,
+            job_type: job.job_type,
+            status: job.status,
+            attempts: job.attempts,
+            error_message: job.error_message,
+            run_after: job.run_after,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+/// Data of a `progress` event: what a job did with one repository.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ProgressEvent {
+    pub repository: String,
+    pub status: String,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<JobEvent> for ProgressEvent {
+    fn from(event: JobEvent) -> Self {
+        Self {
+            repository: event.repository,
+            status: event.status,
+            message: event.message,
+            created_at: event.created_at,
+        }
+    }
+}
+
+/// Data of a `status` event, sent whenever the status of the job changes.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct StatusEvent {
+    pub status: String,
+    pub attempts: i32,
+    pub error_message: Option<String>,
+}
+
+/// Queue a scan or processing job for the organizations, one of them, or a repository.
+#[utoipa::path(
+    post,
+    path = "/api/jobs",
+    tag = "jobs",
+    request_body = CreateJobRequest,
+    responses(
+        (status = 202, description = "The job was queued", body = JobResponse),
+        (status = 400, description = "The request body is invalid", body = ErrorResponse),
+    ),
+    security(("bearer" = ["jobs:write"]))
+)]
+pub(crate) async fn create_job(
+    State(state): State<AppState>,
+    request: Result<Json<CreateJobRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<JobResponse>), AppError> {
+    let Json(request) = request?;
+
+    let organization = request
+        .organization
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if let Some(organization) = &organization
+        && !state.config.has_organization(organization)
+    {
+        return Err(AppError::BadRequest(format!(
+            "Organization {} is not configured",
+            organization
+        )));
+    }
+
+    let repository = request
+        .repository
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .map(|name| match &organization {
+            Some(organization) if !name.contains('/') => format!("{}/{}", organization, name),
+            _ => name,
+        })
+        .map(|name| {
+            state
+                .github
+                .full_name(&name)
+                .map_err(|e| AppError::BadRequest(e.to_string()))
+        })
+        .transpose()?;
+    let job = match (request.kind, repository) {
+        (JobKind::Scan, None) => Job::ScanOrganization { organization },
+        (JobKind::Scan, Some(repository)) => Job::ScanRepository { repository },
+        (JobKind::Process, None) => Job::ProcessOrganization { organization },
+        (JobKind::Process, Some(repository)) => Job::ProcessRepository { repository },
+    };
+
+    let repository_id = match &job {
+        Job::ScanRepository { repository } | Job::ProcessRepository { repository } => state
+            .database
+            .find_repository_by_full_name(repository)
+            .await?
+            .map(|repo| repo.id),
+        _ => None,
+    };
+
+    let job = jobs::enqueue(&state.database, &job, repository_id).await?;
+    Ok((StatusCode::ACCEPTED, Json(job.into())))
+}
+
+/// List jobs, most recent first.
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(JobsQuery),
+    responses(
+        (status = 200, description = "The jobs", body = Vec<JobResponse>),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+    ),
+    security(("bearer" = ["jobs:write"]))
+)]
+pub(crate) async fn list_jobs(
+    State(state): State<AppState>,
+    query: Result<Query<JobsQuery>, QueryRejection>,
+) -> Result<Json<Vec<JobResponse>>, AppError> {
+    let Query(query) = query?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let jobs = state
+        .database
+        .list_jobs(query.status.as_deref(), limit)
+        .await?;
+
+    Ok(Json(jobs.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}",
+    tag = "jobs",
+    params(("id" = Uuid, Path, description = "ID of the job")),
+    responses(
+        (status = 200, description = "The job", body = JobResponse),
+        (status = 400, description = "The ID is not a UUID", body = ErrorResponse),
+        (status = 404, description = "No job has this ID", body = ErrorResponse),
+    ),
+    security(("bearer" = ["jobs:write"]))
+)]
+pub(crate) async fn get_job(
+    State(state): State<AppState>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<JobResponse>, AppError> {
+    let Path(job_id) = job_id?;
+    let job = state.database.get_processing_job(job_id).await?;
+    Ok(Json(job.into()))
+}
+
+/// Cancel a job that has not finished yet.
+#[utoipa::path(
+    delete,
+    path = "/api/jobs/{id}",
+    tag = "jobs",
+    params(("id" = Uuid, Path, description = "ID of the job")),
+    responses(
+        (status = 200, description = "The cancelled job", body = JobResponse),
+        (status = 400, description = "The ID is not a UUID", body = ErrorResponse),
+        (status = 404, description = "No job has this ID", body = ErrorResponse),
+        (status = 409, description = "The job has already finished", body = ErrorResponse),
+    ),
+    security(("bearer" = ["jobs:write"]))
+)]
+pub(crate) async fn cancel_job(
+    State(state): State<AppState>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<JobResponse>, AppError> {
+    let Path(job_id) = job_id?;
+
+    match state.database.cancel_job(job_id).await? {
+        Some(job) => Ok(Json(job.into())),
+        None => {
+            let job = state.database.get_processing_job(job_id).await?;
+            Err(AppError::Conflict(format!(
+                "Job {} is already {}",
+                job.id, job.status
+            )))
+        }
+    }
+}
+
+/// Stream the progress of a job as server-sent events until it finishes.
+///
+/// `progress` events carry the per-repository status the CLI prints, with the event id
+/// set so a reconnecting client resumes with `Last-Event-ID`. `status` events are sent
+/// whenever the job status changes.
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}/events",
+    tag = "jobs",
+    params(
+        ("id" = Uuid, Path, description = "ID of the job"),
+        ("Last-Event-ID" = Option<i64>, Header, description = "ID of the last `progress` event received, to resume after it"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Server-sent `progress` events with a `ProgressEvent` and `status` events with a `StatusEvent`, until the job finishes",
+            body = String,
+            content_type = "text/event-stream"
+        ),
+        (status = 400, description = "The ID is not a UUID", body = ErrorResponse),
+        (status = 404, description = "No job has this ID", body = ErrorResponse),
+    ),
+    security(("bearer" = ["jobs:write"]))
+)]
+pub(crate) async fn job_events(
+    State(state): State<AppState>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let Path(job_id) = job_id?;
+    let job = state.database.get_processing_job(job_id).await?;
+
+    let last_event_id = headers
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+
+    let stream = EventCursor {
+        database: state.database.clone(),
+        job_id: job.id,
+        last_event_id,
+        last_status: None,
+        buffered: VecDeque::new(),
+        polled: false,
+        finished: false,
+    }
+    .into_stream();
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Position of an event stream in the events of a job.
+struct EventCursor {
+    database: Database,
+    job_id: Uuid,
+    last_event_id: i64,
+    last_status: Option<String>,
+    buffered: VecDeque<Event>,
+    polled: bool,
+    finished: bool,
+}
+
+impl EventCursor {
+    fn into_stream(self) -> impl Stream<Item = Result<Event, Infallible>> {
+        futures_lite::stream::unfold(self, |mut cursor| async move {
+            loop {
+                if let Some(event) = cursor.buffered.pop_front() {
+                    return Some((Ok(event), cursor));
+                }
+                if cursor.finished {
+                    return None;
+                }
+                if cursor.polled {
+                    tokio::time::sleep(EVENTS_POLL_INTERVAL).await;
+                }
+                cursor.poll().await;
+            }
+        })
+    }
+
+    async fn poll(&mut self) {
+        self.polled = true;
+
+        // The job is read first: events recorded before it finished are then read too
+        let result = async {
+            let job = self.database.get_processing_job(self.job_id).await?;
+            let events = self
+                .database
+                .list_job_events(self.job_id, self.last_event_id)
+                .await?;
+            Ok::<_, AppError>((events, job))
+        }
+        .await;
+
+        let (events, job) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.buffered
+                    .push_back(Event::default().event("error").data(e.to_string()));
+                self.finished = true;
+                return;
+            }
+        };
+
+        for event in events {
+            self.last_event_id = event.id;
+            let id = event.id.to_string();
+            self.buffered.push_back(json_event(
+                Event::default().event("progress").id(id),
+                &ProgressEvent::from(event),
+            ));
+        }
+
+        if self.last_status.as_deref() != Some(job.status.as_str()) {
+            self.buffered.push_back(json_event(
+                Event::default().event("status"),
+                &StatusEvent {
+                    status: job.status.clone(),
+                    attempts: job.attempts,
+                    error_message: job.error_message.clone(),
+                },
+            ));
+            self.last_status = Some(job.status.clone());
+        }
+
+        self.finished = FINISHED_STATUSES.contains(&job.status.as_str());
+    }
+}
+
+fn json_event(event: Event, data: &impl Serialize) -> Event {
+    let data = serde_json::to_string(data).unwrap_or_default();
+    event.data(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    async fn send(
+        database: &Database,
+        method: &str,
+        uri: &str,
+        body: Option<&str>,
+    ) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", "Bearer admin-token")
+            .header("Content-Type", "application/json")
+            .body(body.map(|b| Body::from(b.to_string())).unwrap_or_default())
+            .unwrap();
+        let response = create_app(test_state(database.clone()).await)
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[sqlx::test]
+    async fn test_create_get_and_cancel_jobs(pool: PgPool) {
+        let database = Database::from_pool(pool);
+
+        let (status, body) = send(
+            &database,
+            "POST",
+            "/api/jobs",
+            Some(r#"{"kind": "process", "repository": "test-repo"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let job: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(job["job_type"], "process_repository");
+        assert_eq!(job["parameters"]["repository"], "test-org/test-repo");
+        assert_eq!(job["status"], "pending");
+
+        let (status, _) = send(&database, "POST", "/api/jobs", Some(r#"{"kind": "scan"}"#)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let (status, body) = send(
+            &database,
+            "POST",
+            "/api/jobs",
+            Some(r#"{"kind": "scan", "organization": "other-org"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("other-org"));
+
+        let (status, _) = send(
+            &database,
+            "POST",
+            "/api/jobs",
+            Some(r#"{"kind": "scan", "repository": "other-org/test-repo"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&database, "POST", "/api/jobs", Some(r#"{"kind": "x"}"#)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(&database, "GET", "/api/jobs?status=pending", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let jobs: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(jobs.as_array().unwrap().len(), 2);
+
+        let uri = format!("/api/jobs/{}", job["id"].as_str().unwrap());
+        let (status, body) = send(&database, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("process_repository"));
+
+        let (status, body) = send(&database, "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("cancelled"));
+
+        let (status, _) = send(&database, "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = send(&database, "GET", "/api/jobs/not-a-uuid", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_job_events_stream_progress(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let job = crate::jobs::enqueue(&database, &crate::jobs::Job::ProcessOrganization { organization: None }, None)
+            .await
+            .unwrap();
+        database
+            .record_job_event(job.id, "repo-a", "processing", None)
+            .await
+            .unwrap();
+        database
+            .record_job_event(job.id, "repo-a", "error", Some("Processing failed"))
+            .await
+            .unwrap();
+        database
+            .claim_next_job(crate::jobs::JOB_LEASE)
+            .await
+            .unwrap();
+        database
+            .update_job_status(job.id, "completed", None)
+            .await
+            .unwrap();
+
+        // The job is finished, so the stream ends after replaying its events
+        let uri = format!("/api/jobs/{}/events", job.id);
+        let (status, body) = send(&database, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let progress: Vec<&str> = body
+            .lines()
+            .filter(|line| line.starts_with("event: "))
+            .collect();
+        assert_eq!(
+            progress,
+            ["event: progress", "event: progress", "event: status"]
+        );
+        assert!(body.contains(r#""repository":"repo-a","status":"error""#));
+        assert!(body.contains(r#""status":"completed""#));
+
+        let (status, _) = send(
+            &database,
+            "GET",
+            &format!("/api/jobs/{}/events", uuid::Uuid::new_v4()),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
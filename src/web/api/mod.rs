@@ -0,0 +1,133 @@
+pub mod jobs;
+pub mod repositories;
+pub mod search;
+
+use crate::web::AppError;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PER_PAGE: u32 = 50;
+const MAX_PER_PAGE: u32 = 200;
+
+/// Query parameters shared by the list endpoints.
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// 1-based page number
+    pub page: Option<u32>,
+    /// Items per page, at most 200
+    pub per_page: Option<u32>,
+    /// Only include items updated at or after this RFC 3339 timestamp
+    pub updated_since: Option<DateTime<Utc>>,
+}
+
+impl ListQuery {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page() as i64
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page() as i64 - 1) * self.per_page() as i64
+    }
+}
+
+/// A page of results from a list endpoint.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+    pub total_pages: i64,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, query: &ListQuery, total: i64) -> Self {
+        let per_page = query.per_page();
+
+        Self {
+            items,
+            page: query.page(),
+            per_page,
+            total,
+            total_pages: (total + per_page as i64 - 1) / per_page as i64,
+        }
+    }
+}
+
+/// Parse a JSON string column into a value for API responses.
+fn json_column(value: Option<&str>) -> Option<serde_json::Value> {
+    value.and_then(|value| serde_json::from_str(value).ok())
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_query_defaults_and_bounds() {
+        let query = ListQuery::default();
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), DEFAULT_PER_PAGE);
+        assert_eq!(query.offset(), 0);
+
+        let query = ListQuery {
+            page: Some(3),
+            per_page: Some(10_000),
+            updated_since: None,
+        };
+        assert_eq!(query.per_page(), MAX_PER_PAGE);
+        assert_eq!(query.offset(), 2 * MAX_PER_PAGE as i64);
+
+        let query = ListQuery {
+            page: Some(0),
+            per_page: Some(0),
+            updated_since: None,
+        };
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), 1);
+    }
+
+    #[test]
+    fn test_page_counts_total_pages() {
+        let query = ListQuery {
+            page: Some(2),
+            per_page: Some(10),
+            updated_since: None,
+        };
+
+        assert_eq!(Page::new(vec![1, 2, 3], &query, 23).total_pages, 3);
+        assert_eq!(Page::<i32>::new(vec![], &query, 0).total_pages, 0);
+        assert_eq!(Page::<i32>::new(vec![], &query, 20).total_pages, 2);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::database::{Document, Repository};
+use crate::web::api::{ListQuery, Page, json_column};
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::Json;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RepositoryResponse {
+    pub name: String,
+    pub full_name: String,
+    pub description: Option<String>,
+    pub default_branch: String,
+    pub is_private: bool,
+    pub is_archived: bool,
+    pub is_fork: bool,
+    pub documents_config: Option<serde_json::Value>,
+    pub last_processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Repository> for RepositoryResponse {
+    fn from(repo: Repository) -> Self {
+        Self {
+            documents_config: json_column(repo.documents_config.as_deref()),
+            name: repo.name,
+            full_name: repo.full_name,
+            description: repo.description,
+            default_branch: repo.default_branch,
+            is_private: repo.is_private,
+            is_archived: repo.is_archived,
+            is_fork: repo.is_fork,
+            last_processed_at: repo.last_processed_at,
+            created_at: repo.created_at,
+            updated_at: repo.updated_at,
+        }
+    }
+}
+
+/// A document without its content, as listed by `/api/repositories/{owner}/{name}/documents`.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DocumentSummary {
+    pub path: String,
+    pub title: String,
+    pub content_hash: String,
+    pub file_size: i64,
+    pub metadata: Option<serde_json::Value>,
+    pub last_modified_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Document> for DocumentSummary {
+    fn from(doc: Document) -> Self {
+        Self {
+            metadata: json_column(doc.metadata.as_deref()),
+            path: doc.file_path,
+            title: doc.title,
+            content_hash: doc.content_hash,
+            file_size: doc.file_size,
+            last_modified_at: doc.last_modified_at,
+            updated_at: doc.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DocumentResponse {
+    pub repository: String,
+    #[serde(flatten)]
+    pub summary: DocumentSummary,
+    pub content: String,
+}
+
+/// List the documented repositories of the organization.
+#[utoipa::path(
+    get,
+    path = "/api/repositories",
+    tag = "documents",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "A page of repositories", body = Page<RepositoryResponse>),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn list_repositories(
+    State(state): State<AppState>,
+    query: Result<Query<ListQuery>, QueryRejection>,
+) -> Result<Json<Page<RepositoryResponse>>, AppError> {
+    let Query(query) = query?;
+
+    let (repos, total) = state
+        .database
+        .list_repositories_page(query.updated_since, query.limit(), query.offset())
+        .await?;
+
+    Ok(Json(Page::new(
+        repos.into_iter().map(Into::into).collect(),
+        &query,
+        total,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/repositories/{owner}/{name}",
+    tag = "documents",
+    params(
+        ("owner" = String, Path, description = "Organization that owns the repository"),
+        ("name" = String, Path, description = "Name of the repository within the organization"),
+    ),
+    responses(
+        (status = 200, description = "The repository", body = RepositoryResponse),
+        (status = 404, description = "No documented repository has this name", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn get_repository(
+    State(state): State<AppState>,
+    Path((owner, name)): Path<(String, String)>,
+) -> Result<Json<RepositoryResponse>, AppError> {
+    let repo = find_repository(&state, &format!("{}/{}", owner, name)).await?;
+    Ok(Json(repo.into()))
+}
+
+/// List the documents of a repository, without their content.
+#[utoipa::path(
+    get,
+    path = "/api/repositories/{owner}/{name}/documents",
+    tag = "documents",
+    params(
+        ("owner" = String, Path, description = "Organization that owns the repository"),
+        ("name" = String, Path, description = "Name of the repository within the organization"),
+        ListQuery,
+    ),
+    responses(
+        (status = 200, description = "A page of documents", body = Page<DocumentSummary>),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 404, description = "No documented repository has this name", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn list_documents(
+    State(state): State<AppState>,
+    Path((owner, name)): Path<(String, String)>,
+    query: Result<Query<ListQuery>, QueryRejection>,
+) -> Result<Json<Page<DocumentSummary>>, AppError> {
+    let Query(query) = query?;
+    let repo = find_repository(&state, &format!("{}/{}", owner, name)).await?;
+
+    let (docs, total) = state
+        .database
+        .list_documents_page(repo.id, query.updated_since, query.limit(), query.offset())
+        .await?;
+
+    Ok(Json(Page::new(
+        docs.into_iter().map(Into::into).collect(),
+        &query,
+        total,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/repositories/{owner}/{name}/documents/{*path}",
+    tag = "documents",
+    params(
+        ("owner" = String, Path, description = "Organization that owns the repository"),
+        ("name" = String, Path, description = "Name of the repository within the organization"),
+        ("path" = String, Path, description = "Path of the document in the repository; may contain slashes"),
+    ),
+    responses(
+        (status = 200, description = "The document with its content", body = DocumentResponse),
+        (status = 404, description = "The repository or document does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn get_document(
+    State(state): State<AppState>,
+    Path((owner, name, path)): Path<(String, String, String)>,
+) -> Result<Json<DocumentResponse>, AppError> {
+    let repo = find_repository(&state, &format!("{}/{}", owner, name)).await?;
+
+    let mut doc = state
+        .database
+        .get_document_by_path(repo.id, &path)
+        .await
+        .map_err(|_| {
+            AppError::NotFound(format!(
+                "Document {} not found in repository {}",
+                path, repo.full_name
+            ))
+        })?;
+
+    let content = std::mem::take(&mut doc.content);
+
+    Ok(Json(DocumentResponse {
+        repository: repo.full_name,
+        summary: doc.into(),
+        content,
+    }))
+}
+
+/// Look up a documented repository of the configured organizations by `owner/name`, or by
+/// its short name in the first organization that has one.
+pub(crate) async fn find_repository(state: &AppState, name: &str) -> Result<Repository, AppError> {
+    state
+        .database
+        .find_documented_repository(&state.config.organization_names(), name)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Repository {} not found", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::{Database, Document, Repository};
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use chrono::Utc;
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn seed(database: &Database) -> Repository {
+        let repo = database
+            .upsert_repository(&Repository {
+                id: Uuid::new_v4(),
+                name: "test-repo".to_string(),
+                full_name: "test-org/test-repo".to_string(),
+                owner: "test-org".to_string(),
+                description: Some("A test repository".to_string()),
+                default_branch: "main".to_string(),
+                is_private: false,
+                is_archived: false,
+                is_fork: false,
+                has_documents_config: true,
+                documents_config: Some(r#"{"project": {"name": "Test"}}"#.to_string()),
+                last_scanned_at: None,
+                last_processed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                github_id: None,
+                deleted_at: None,
+            })
+            .await
+            .unwrap();
+
+        for path in ["docs/a.md", "docs/guides/b.md", "docs/guides/c.md"] {
+            database
+                .upsert_document(&Document {
+                    id: Uuid::new_v4(),
+                    repository_id: repo.id,
+                    file_path: path.to_string(),
+                    title: format!("Title of {}", path),
+                    content: format!("# {}", path),
+                    content_hash: "abc123".to_string(),
+                    metadata: Some(r#"{"author": "test"}"#.to_string()),
+                    file_size: 10,
+                    last_modified_at: Utc::now(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                    deleted_at: None,
+                    headings: String::new(),
+                    tags: vec![],
+                    last_commit_sha: None,
+                    last_author: None,
+                    contributors: 0,
+                })
+                .await
+                .unwrap();
+        }
+        repo
+    }
+
+    async fn get(database: &Database, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = create_app(test_state(database.clone()).await)
+            .oneshot(
+                Request::get(uri)
+                    .header("Authorization", "Bearer admin-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[sqlx::test]
+    async fn test_list_and_get_repositories(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let repo = seed(&database).await;
+
+        let (status, body) = get(&database, "/api/repositories").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["items"][0]["name"], "test-repo");
+        assert_eq!(
+            body["items"][0]["documents_config"]["project"]["name"],
+            "Test"
+        );
+
+        // Repositories of the same name are paged in the order of their owners
+        database
+            .upsert_repository(&Repository {
+                id: Uuid::new_v4(),
+                full_name: "another-org/test-repo".to_string(),
+                owner: "another-org".to_string(),
+                ..repo
+            })
+            .await
+            .unwrap();
+        for (page, full_name) in [(1, "another-org/test-repo"), (2, "test-org/test-repo")] {
+            let uri = format!("/api/repositories?per_page=1&page={}", page);
+            let (_, body) = get(&database, &uri).await;
+            assert_eq!(body["total"], 2);
+            assert_eq!(body["items"][0]["full_name"], full_name);
+        }
+
+        let (status, body) = get(&database, "/api/repositories/test-org/test-repo").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["full_name"], "test-org/test-repo");
+
+        let (status, body) = get(&database, "/api/repositories/test-org/missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not_found");
+    }
+
+    #[sqlx::test]
+    async fn test_list_documents_paginates_and_filters(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        seed(&database).await;
+
+        let (status, body) = get(
+            &database,
+            "/api/repositories/test-org/test-repo/documents?page=2&per_page=2",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["total_pages"], 2);
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["path"], "docs/guides/c.md");
+        assert!(body["items"][0].get("content").is_none());
+
+        let (status, body) = get(
+            &database,
+            "/api/repositories/test-org/test-repo/documents?updated_since=2999-01-01T00:00:00Z",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 0);
+
+        let (status, body) = get(
+            &database,
+            "/api/repositories/test-org/test-repo/documents?updated_since=yesterday",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "bad_request");
+    }
+
+    #[sqlx::test]
+    async fn test_get_document_by_nested_path(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        seed(&database).await;
+
+        let (status, body) = get(
+            &database,
+            "/api/repositories/test-org/test-repo/documents/docs/guides/b.md",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["repository"], "test-org/test-repo");
+        assert_eq!(body["path"], "docs/guides/b.md");
+        assert_eq!(body["content"], "# docs/guides/b.md");
+        assert_eq!(body["metadata"]["author"], "test");
+
+        let (status, body) = get(
+            &database,
+            "/api/repositories/test-org/test-repo/documents/docs/missing.md",
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not_found");
+    }
+}
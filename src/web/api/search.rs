@@ -0,0 +1,110 @@
+use crate::config::parse_list;
+use crate::database::SearchResult;
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::Json;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct SearchQuery {
+    /// Search terms; required
+    q: Option<String>,
+    /// Only search documents of this repository, as `owner/name` or by its short name
+    repository: Option<String>,
+    /// Comma-separated frontmatter tags; results must have all of them
+    tags: Option<String>,
+    /// Maximum number of results, at most 100
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+}
+
+/// Full-text search over the processed documents, best matches first.
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "documents",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching documents", body = SearchResponse),
+        (status = 400, description = "The q parameter is missing or a parameter is invalid", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn search(
+    State(state): State<AppState>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let Query(query) = query?;
+
+    let q = query.q.as_deref().map(str::trim).unwrap_or_default();
+    if q.is_empty() {
+        return Err(AppError::BadRequest(
+            "The q query parameter is required".to_string(),
+        ));
+    }
+
+    let tags = query.tags.as_deref().map(parse_list).unwrap_or_default();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let results = state
+        .database
+        .search_documents(q, query.repository.as_deref(), &tags, limit)
+        .await?;
+
+    Ok(Json(SearchResponse {
+        query: q.to_string(),
+        results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn test_search_requires_query(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/api/search?q=")
+                    .header("Authorization", "Bearer admin-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(
+                Request::get("/api/search?q=token&tags=api")
+                    .header("Authorization", "Bearer admin-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["query"], "token");
+        assert!(body["results"].as_array().unwrap().is_empty());
+    }
+}
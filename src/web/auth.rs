@@ -0,0 +1,384 @@
+use crate::web::{AppError, AppState};
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, Request, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Redirect, Response};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Prefix of every generated API key, so leaked keys are easy to recognise.
+pub const API_KEY_PREFIX: &str = "dk_";
+
+/// Number of characters of a key stored in clear to tell keys apart in listings.
+const DISPLAY_PREFIX_LEN: usize = 11;
+
+/// Cookie holding the ID of a browser's session of the documentation site.
+pub const SESSION_COOKIE: &str = "documents_session";
+
+/// Where browsers without a session are sent to sign in to the documentation site.
+pub const LOGIN_PATH: &str = "/docs/login";
+
+/// Where browsers end their session of the documentation site.
+pub const LOGOUT_PATH: &str = "/docs/logout";
+
+/// How long a browser stays signed in to the documentation site.
+const SESSION_MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// What an API key is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Read repositories, documents and search results
+    ReadDocs,
+    /// Queue, inspect and cancel jobs
+    TriggerJobs,
+    /// Everything, including the webhook delivery log
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::ReadDocs, Scope::TriggerJobs, Scope::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadDocs => "docs:read",
+            Scope::TriggerJobs => "jobs:write",
+            Scope::Admin => "admin",
+        }
+    }
+
+    /// Whether a key holding `scopes` may use a route requiring this scope.
+    pub fn granted_by(&self, scopes: &[String]) -> bool {
+        scopes
+            .iter()
+            .any(|scope| scope == self.as_str() || scope == Scope::Admin.as_str())
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == value)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown scope '{}', expected one of: docs:read, jobs:write, admin",
+                    value
+                )
+            })
+    }
+}
+
+/// A freshly generated API key. `key` is only ever available here; the database keeps
+/// `prefix` and `hash`.
+pub struct GeneratedKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+pub fn generate_api_key() -> GeneratedKey {
+    let key = format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+
+    GeneratedKey {
+        prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+        hash: hash_api_key(&key),
+        key,
+    }
+}
+
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Middleware requiring `Authorization: Bearer <key>` with a key holding the given scope.
+///
+/// `ADMIN_API_TOKEN`, when set, is accepted as a key with every scope so that a deployment
+/// can be bootstrapped before any API key exists.
+pub(crate) async fn require_scope(
+    State((state, scope)): State<(AppState, Scope)>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let provided = bearer_token(request.headers())
+        .ok_or_else(|| AppError::Unauthorized("Missing bearer token".to_string()))?;
+    authorize(&state, scope, provided).await?;
+
+    Ok(next.run(request).await)
+}
+
+/// Middleware guarding the documentation site, which browsers open without a bearer token.
+///
+/// Pages are public when `SITE_PUBLIC` is set. Otherwise a bearer token is checked as for
+/// the API, and a browser is let in by the session opened by `/docs/login`, as long as the
+/// key it signed in with still reads documents, or sent there to sign in.
+pub(crate) async fn require_site_access(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if state.config.site_public {
+        return Ok(next.run(request).await);
+    }
+
+    if let Some(provided) = bearer_token(request.headers()) {
+        authorize(&state, Scope::ReadDocs, provided).await?;
+        return Ok(next.run(request).await);
+    }
+
+    if let Some(session) = session_cookie(request.headers())
+        && authorize_session(&state, session).await.is_ok()
+    {
+        return Ok(next.run(request).await);
+    }
+
+    let login = format!(
+        "{}?{}",
+        LOGIN_PATH,
+        serde_urlencoded::to_string([("next", request.uri().path())]).unwrap_or_default()
+    );
+    Ok(Redirect::to(&login).into_response())
+}
+
+/// Check that `provided` is the admin token or an API key holding `scope`.
+pub(crate) async fn authorize(
+    state: &AppState,
+    scope: Scope,
+    provided: &str,
+) -> Result<(), AppError> {
+    authorize_hash(state, scope, &hash_api_key(provided)).await
+}
+
+/// Check that the session with ID `session` is open and that its key still reads documents.
+async fn authorize_session(state: &AppState, session: &str) -> Result<(), AppError> {
+    let session = state
+        .database
+        .find_site_session(&hash_api_key(session))
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid session".to_string()))?;
+
+    authorize_hash(state, Scope::ReadDocs, &session.key_hash).await
+}
+
+/// [`authorize`] for the key whose hash is `key_hash`.
+async fn authorize_hash(state: &AppState, scope: Scope, key_hash: &str) -> Result<(), AppError> {
+    if let Some(admin_token) = state.config.admin_api_token.as_deref()
+        && tokens_match(&hash_api_key(admin_token), key_hash)
+    {
+        return Ok(());
+    }
+
+    let key = state
+        .database
+        .use_api_key(key_hash)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid bearer token".to_string()))?;
+
+    if !scope.granted_by(&key.scopes) {
+        return Err(AppError::Forbidden(format!(
+            "API key '{}' does not have the {} scope",
+            key.name, scope
+        )));
+    }
+
+    Ok(())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// The session ID held by the site's session cookie, if the request carries one.
+fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// Open a site session for `key`, which has been authorized, and return its ID. Only
+/// hashes of the ID and of the key are stored.
+pub(crate) async fn start_session(state: &AppState, key: &str) -> Result<String, AppError> {
+    let session = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(SESSION_MAX_AGE_SECS);
+    state
+        .database
+        .create_site_session(&hash_api_key(&session), &hash_api_key(key), expires_at)
+        .await?;
+
+    Ok(session)
+}
+
+/// End the session the request's cookie holds, if any.
+pub(crate) async fn end_session(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    if let Some(session) = session_cookie(headers) {
+        state
+            .database
+            .delete_site_session(&hash_api_key(session))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Whether the browser reached the server over HTTPS, either served by the server itself
+/// or by a TLS-terminating proxy that says so in `X-Forwarded-Proto`.
+pub(crate) fn is_https(state: &AppState, headers: &HeaderMap) -> bool {
+    state.config.tls.is_some()
+        || headers
+            .get("x-forwarded-proto")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"))
+}
+
+/// A `Set-Cookie` value holding `session` as the site's session, scoped to the site's
+/// pages. The cookie is marked `Secure` when the browser connected over HTTPS.
+pub(crate) fn session_cookie_header(session: &str, secure: bool) -> String {
+    cookie_header(session, SESSION_MAX_AGE_SECS, secure)
+}
+
+/// A `Set-Cookie` value removing the site's session cookie.
+pub(crate) fn cleared_session_cookie_header(secure: bool) -> String {
+    cookie_header("", 0, secure)
+}
+
+fn cookie_header(value: &str, max_age: i64, secure: bool) -> String {
+    format!(
+        "{}={}; Path=/docs; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        SESSION_COOKIE,
+        value,
+        max_age,
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// Compare two tokens without leaking where they differ through timing.
+fn tokens_match(expected: &str, provided: &str) -> bool {
+    let expected = Sha256::digest(expected.as_bytes());
+    let provided = Sha256::digest(provided.as_bytes());
+
+    expected
+        .iter()
+        .zip(provided.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::http::StatusCode;
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("admin-token", "admin-token"));
+        assert!(!tokens_match("admin-token", "admin-toke"));
+        assert!(!tokens_match("admin-token", ""));
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!("jobs:write".parse::<Scope>(), Ok(Scope::TriggerJobs));
+        assert!("docs:write".parse::<Scope>().is_err());
+
+        let read = vec!["docs:read".to_string()];
+        let admin = vec!["admin".to_string()];
+        assert!(Scope::ReadDocs.granted_by(&read));
+        assert!(!Scope::TriggerJobs.granted_by(&read));
+        assert!(Scope::TriggerJobs.granted_by(&admin));
+        assert!(!Scope::ReadDocs.granted_by(&[]));
+    }
+
+    #[test]
+    fn test_generate_api_key() {
+        let generated = generate_api_key();
+
+        assert!(generated.key.starts_with(API_KEY_PREFIX));
+        assert!(generated.key.starts_with(&generated.prefix));
+        assert_eq!(generated.hash, hash_api_key(&generated.key));
+        assert_ne!(generated.key, generate_api_key().key);
+    }
+
+    async fn status(app: axum::Router, method: &str, uri: &str, token: Option<&str>) -> StatusCode {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        app.oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[sqlx::test]
+    async fn test_require_scope(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let app = create_app(test_state(database.clone()).await);
+        let generated = generate_api_key();
+        let key = database
+            .create_api_key(
+                "reader",
+                &generated.prefix,
+                &generated.hash,
+                &["docs:read".to_string()],
+            )
+            .await
+            .unwrap();
+        let token = Some(generated.key.as_str());
+
+        assert_eq!(
+            status(app.clone(), "GET", "/health", None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), "GET", "/api/repositories", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(app.clone(), "GET", "/api/repositories", token).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(app.clone(), "GET", "/api/jobs", token).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(app.clone(), "GET", "/api/jobs", Some("admin-token")).await,
+            StatusCode::OK
+        );
+
+        database.revoke_api_key(key.id).await.unwrap();
+        assert_eq!(
+            status(app, "GET", "/api/repositories", token).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
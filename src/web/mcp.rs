@@ -0,0 +1,108 @@
+//! The streamable HTTP transport of the MCP server, mounted at `/mcp`.
+//!
+//! Every request is answered with a single JSON response, so the server never opens an SSE
+//! stream and `GET /mcp` is not supported.
+
+use crate::mcp::McpServer;
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::Json;
+use axum::extract::State;
+use axum::extract::rejection::JsonRejection;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+
+/// `POST /mcp`: a JSON-RPC message or batch from the client.
+#[utoipa::path(
+    post,
+    path = "/mcp",
+    tag = "mcp",
+    request_body(content = serde_json::Value, description = "JSON-RPC request, notification or batch"),
+    responses(
+        (status = 200, description = "JSON-RPC response or batch of responses", body = serde_json::Value),
+        (status = 202, description = "Only notifications or responses were sent"),
+        (status = 400, description = "The body is not JSON", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn post(
+    State(state): State<AppState>,
+    body: Result<Json<Value>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(message) = body?;
+    let server = McpServer::new(state.database.clone(), state.config.organization_names());
+
+    Ok(match server.handle_message(message).await {
+        Some(response) => Json(response).into_response(),
+        // Notifications and responses are acknowledged without a body
+        None => StatusCode::ACCEPTED.into_response(),
+    })
+}
+
+/// `GET /mcp`: the server does not push messages, so there is no stream to open.
+#[utoipa::path(
+    get,
+    path = "/mcp",
+    tag = "mcp",
+    responses((status = 405, description = "The server does not open SSE streams")),
+    security(("bearer" = ["docs:read"]))
+)]
+pub(crate) async fn get() -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "POST")]).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode, header};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    fn mcp_request(body: &'static str) -> Request<Body> {
+        Request::post("/mcp")
+            .header("Authorization", "Bearer admin-token")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ACCEPT, "application/json, text/event-stream")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_mcp_over_http(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+
+        let response = app
+            .clone()
+            .oneshot(mcp_request(
+                r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["result"]["protocolVersion"], "2025-06-18");
+
+        let response = app
+            .clone()
+            .oneshot(mcp_request(
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let response = app
+            .oneshot(
+                Request::get("/mcp")
+                    .header("Authorization", "Bearer admin-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::metrics::METRICS;
+use crate::web::{AppError, AppState};
+use axum::body::Body;
+use axum::extract::{MatchedPath, State};
+use axum::http::{Request, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use prometheus::TEXT_FORMAT;
+use std::time::Instant;
+
+/// `/metrics`: every metric of this process in the Prometheus text format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "monitoring",
+    responses((
+        status = 200,
+        description = "Metrics in the Prometheus text exposition format",
+        body = String,
+        content_type = "text/plain; version=0.0.4"
+    ))
+)]
+pub(crate) async fn metrics(State(state): State<AppState>) -> Result<Response, AppError> {
+    let pool = state.database.pool();
+    let idle = pool.num_idle() as i64;
+    METRICS
+        .database_connections
+        .with_label_values(&["idle"])
+        .set(idle);
+    METRICS
+        .database_connections
+        .with_label_values(&["in_use"])
+        .set(pool.size() as i64 - idle);
+
+    // Statuses without jobs left must drop to zero rather than keep their last count
+    let counts = state.database.count_jobs_by_status().await?;
+    METRICS.jobs.reset();
+    for (status, count) in counts {
+        METRICS.jobs.with_label_values(&[&status]).set(count);
+    }
+
+    Ok(([(header::CONTENT_TYPE, TEXT_FORMAT)], METRICS.encode()).into_response())
+}
+
+/// Middleware counting requests and their latency by method, matched route and status.
+///
+/// The route template (e.g. `/api/jobs/{id}`) is used rather than the path so that ids do
+/// not each get their own time series.
+pub(crate) async fn track_http_metrics(request: Request<Body>, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    METRICS
+        .http_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn test_metrics(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+
+        app.clone()
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(
+            r#"documents_http_requests_total{method="GET",route="/health",status="200"}"#
+        ));
+        assert!(body.contains(r#"documents_database_connections{state="idle"}"#));
+    }
+}
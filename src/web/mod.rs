@@ -1,31 +1,49 @@
+pub mod api;
+pub mod auth;
+pub mod mcp;
+pub mod metrics;
+pub mod openapi;
+pub mod site;
+pub mod webhook;
+
+use crate::ApplicationConfig;
+use crate::database::Database;
+use crate::github::GitHubClients;
+use crate::health;
+use auth::Scope;
 use axum::body::Body;
+use axum::extract::State;
 use axum::http;
 use axum::response::IntoResponse;
 use axum::{
     Router,
-    extract::Query,
-    http::{HeaderMap, StatusCode},
+    http::StatusCode,
     middleware,
-    response::{Html, Json, Response},
-    routing::{get, post},
+    response::{Json, Response},
 };
-use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use axum_server::tls_rustls::RustlsConfig;
+use serde::Serialize;
+use std::sync::Arc;
 use std::time::Duration;
 use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
+use tower::util::option_layer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_axum::routes;
 
-#[derive(Serialize, Debug)]
-struct HealthCheckResponse {
+#[derive(Serialize, Debug, ToSchema)]
+pub(crate) struct HealthCheckResponse {
     status: String,
     timestamp: String,
     version: String,
 }
 
-#[derive(Serialize, Debug)]
-struct ErrorResponse {
+#[derive(Serialize, Debug, ToSchema)]
+pub(crate) struct ErrorResponse {
+    /// Machine-readable error type, such as `not_found`
     error: String,
     message: String,
     timestamp: String,
@@ -33,21 +51,42 @@ struct ErrorResponse {
     details: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
-struct WebhookQuery {
-    #[serde(default)]
-    test: bool,
+/// Shared state handed to every request handler.
+#[derive(Clone, Debug)]
+pub struct AppState {
+    pub config: Arc<ApplicationConfig>,
+    pub database: Database,
+    /// A client per configured organization
+    pub github: GitHubClients,
+    /// The last GitHub check of the readiness probe
+    pub github_health: health::GitHubHealthCache,
 }
 
-pub fn create_app() -> Router {
-    Router::new()
-        // Health check endpoint
-        .route("/health", get(health_check))
-        .route("/webhooks/github", post(github_webhook))
+impl AppState {
+    pub fn new(config: ApplicationConfig, database: Database, github: GitHubClients) -> Self {
+        Self {
+            config: Arc::new(config),
+            database,
+            github,
+            github_health: Default::default(),
+        }
+    }
+}
+
+pub fn create_app(state: AppState) -> Router {
+    let request_timeout = state.config.request_timeout;
+    let cors = cors_layer(&state.config.cors_allowed_origins);
+
+    Router::from(routes(Some(&state)))
         .fallback(handler_404)
+        .with_state(state)
         .layer(
             ServiceBuilder::new()
-                .layer(middleware::from_fn(error_handling_middleware))
+                .layer(middleware::from_fn(metrics::track_http_metrics))
+                .layer(middleware::from_fn_with_state(
+                    request_timeout,
+                    error_handling_middleware,
+                ))
                 .layer(
                     TraceLayer::new_for_http()
                         .make_span_with(|request: &http::Request<_>| {
@@ -73,34 +112,105 @@ pub fn create_app() -> Router {
                             },
                         ),
                 )
-                .layer(
-                    CorsLayer::new()
-                        .allow_origin(tower_http::cors::Any) // In production, specify allowed origins
-                        .allow_methods([
-                            http::Method::GET,
-                            http::Method::POST,
-                            http::Method::OPTIONS,
-                        ])
-                        .allow_headers([
-                            http::header::CONTENT_TYPE,
-                            http::header::ACCEPT,
-                            http::header::AUTHORIZATION,
-                            // GitHub webhook headers
-                            http::header::HeaderName::from_static("x-github-event"),
-                            http::header::HeaderName::from_static("x-github-delivery"),
-                            http::header::HeaderName::from_static("x-hub-signature-256"),
-                        ])
-                        .expose_headers([
-                            http::header::CONTENT_TYPE,
-                            http::header::CACHE_CONTROL,
-                            http::header::ETAG,
-                        ])
-                        .max_age(Duration::from_secs(3600)), // 1 hour
-                ),
+                .layer(cors),
+        )
+}
+
+/// Every route of the server, each documented by the `#[utoipa::path]` of its handler.
+/// Routes that need a scope or a site session are guarded when `state` is given; without
+/// it the routes only describe the OpenAPI document.
+pub(crate) fn routes(state: Option<&AppState>) -> OpenApiRouter<AppState> {
+    let require = |scope: Scope| {
+        option_layer(state.map(|state| {
+            middleware::from_fn_with_state((state.clone(), scope), auth::require_scope)
+        }))
+    };
+
+    // Read-only REST API and the MCP server
+    let docs = OpenApiRouter::new()
+        .routes(routes!(api::repositories::list_repositories))
+        .routes(routes!(api::repositories::get_repository))
+        .routes(routes!(api::repositories::list_documents))
+        .routes(routes!(api::repositories::get_document))
+        .routes(routes!(api::search::search))
+        // Streamable HTTP transport of the MCP server
+        .routes(routes!(mcp::get, mcp::post))
+        .route_layer(require(Scope::ReadDocs));
+
+    // Rendered documentation site, which browsers sign in to with a session cookie
+    let site = OpenApiRouter::new()
+        .routes(routes!(site::repository_index))
+        .routes(routes!(site::document_page))
+        .route_layer(option_layer(state.map(|state| {
+            middleware::from_fn_with_state(state.clone(), auth::require_site_access)
+        })));
+
+    let jobs = OpenApiRouter::new()
+        .routes(routes!(api::jobs::list_jobs, api::jobs::create_job))
+        .routes(routes!(api::jobs::get_job, api::jobs::cancel_job))
+        .routes(routes!(api::jobs::job_events))
+        .route_layer(require(Scope::TriggerJobs));
+
+    let admin = OpenApiRouter::new()
+        .routes(routes!(webhook::deliveries::list_deliveries))
+        .routes(routes!(webhook::deliveries::replay_delivery))
+        .route_layer(require(Scope::Admin));
+
+    OpenApiRouter::new()
+        // Health check endpoints
+        .routes(routes!(health_check))
+        .routes(routes!(liveness_check))
+        .routes(routes!(readiness_check))
+        .routes(routes!(metrics::metrics))
+        .routes(routes!(openapi::openapi_json))
+        .routes(routes!(webhook::github_webhook))
+        .routes(routes!(site::login_page, site::login))
+        .routes(routes!(site::logout))
+        .merge(docs)
+        .merge(site)
+        .merge(jobs)
+        .merge(admin)
+}
+
+/// CORS for the configured origins; `*` allows any origin.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|origin| http::HeaderValue::from_str(origin).ok()),
         )
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([
+            http::Method::GET,
+            http::Method::POST,
+            http::Method::DELETE,
+            http::Method::OPTIONS,
+        ])
+        .allow_headers([
+            http::header::CONTENT_TYPE,
+            http::header::ACCEPT,
+            http::header::AUTHORIZATION,
+            // GitHub webhook headers
+            http::header::HeaderName::from_static("x-github-event"),
+            http::header::HeaderName::from_static("x-github-delivery"),
+            http::header::HeaderName::from_static("x-hub-signature-256"),
+        ])
+        .expose_headers([
+            http::header::CONTENT_TYPE,
+            http::header::CACHE_CONTROL,
+            http::header::ETAG,
+        ])
+        .max_age(Duration::from_secs(3600)) // 1 hour
 }
 
 async fn error_handling_middleware(
+    State(request_timeout): State<Duration>,
     request: http::Request<Body>,
     next: middleware::Next,
 ) -> Response {
@@ -108,7 +218,7 @@ async fn error_handling_middleware(
     let method = request.method().clone();
 
     // Add timeout to prevent hanging requests
-    let response = match tokio::time::timeout(Duration::from_secs(30), next.run(request)).await {
+    let response = match tokio::time::timeout(request_timeout, next.run(request)).await {
         Ok(res) => res,
         Err(_) => {
             error!("Request to {} {} timed out", method, uri);
@@ -139,7 +249,7 @@ async fn error_handling_middleware(
     );
 
     // Cache control for API responses
-    if uri.path().starts_with("/api/") || uri.path() == "/health" {
+    if uri.path().starts_with("/api/") || uri.path().starts_with("/health") {
         headers.insert(
             "Cache-Control",
             http::header::HeaderValue::from_static("no-cache, no-store, must-revalidate"),
@@ -160,6 +270,13 @@ async fn error_handling_middleware(
     response
 }
 
+/// Report that the server is up, without checking its dependencies.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = HealthCheckResponse))
+)]
 async fn health_check() -> impl IntoResponse {
     debug!("Processing health check request");
 
@@ -173,33 +290,54 @@ async fn health_check() -> impl IntoResponse {
     Json(response)
 }
 
-async fn github_webhook(
-    Query(params): Query<WebhookQuery>,
-    headers: HeaderMap,
-    body: String,
-) -> impl IntoResponse {
-    debug!("Processing github webhook request");
-    debug!("Headers: {:?}", headers);
-    debug!("Query parameters: {:?}", params);
-    debug!("Body length: {}", body.len());
-
-    if params.test {
-        info!("Received test webhook with body: {}", body);
-        return Html("<h1>Test webhook received</h1>".to_string());
-    }
+#[derive(Serialize, Debug, ToSchema)]
+pub(crate) struct ReadinessResponse {
+    status: String,
+    timestamp: String,
+    components: Vec<health::ComponentHealth>,
+}
 
-    if let Some(event_type) = headers.get("X-GitHub-Event") {
-        debug!("GitHub event type: {:?}", event_type);
-    }
-    if let Some(event_type) = headers.get("X-GitHub-Delivery") {
-        debug!("GitHub delivery ID: {:?}", event_type);
-    }
+/// Liveness probe: the same response as `/health`.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = HealthCheckResponse))
+)]
+async fn liveness_check() -> impl IntoResponse {
+    health_check().await
+}
 
-    // Here you would handle the actual GitHub webhook payload
-    info!("Received GitHub webhook with body: {}", body);
+/// Readiness probe: 200 when every dependency is up, otherwise 503. Both report the result of
+/// each check; the GitHub check is reused for [`health::GITHUB_CHECK_TTL`].
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency is up", body = ReadinessResponse),
+        (status = 503, description = "At least one dependency is down", body = ReadinessResponse),
+    )
+)]
+async fn readiness_check(State(state): State<AppState>) -> Response {
+    let github = state.github_health.check(state.github.as_slice());
+    let report = health::check_all(&state.database, github, &state.config.output_dir).await;
+
+    let (status, label) = if report.is_healthy() {
+        (StatusCode::OK, "ready")
+    } else {
+        for component in report.components.iter().filter(|c| !c.healthy) {
+            warn!("Readiness check failed for {}: {}", component.name, component.details);
+        }
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
 
-    // For now, just return a success response
-    Html("<h1>GitHub webhook received</h1><p>Processing not yet implemented</p>".to_string())
+    let response = ReadinessResponse {
+        status: label.to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        components: report.components,
+    };
+    (status, Json(response)).into_response()
 }
 
 async fn handler_404() -> AppError {
@@ -210,10 +348,16 @@ async fn handler_404() -> AppError {
 pub enum AppError {
     #[error("Bad Request: {0}")]
     BadRequest(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
     #[error("Internal Server Error: {0}")]
     InternalServerError(String),
     #[error("Not Found: {0}")]
     NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
     #[error("Service Unavailable: {0}")]
     ServiceUnavailable(String),
     #[error("IO Error: {0}")]
@@ -222,18 +366,25 @@ pub enum AppError {
     SerializationError(#[from] serde_json::Error),
     #[error("GitHub API Error: {0}")]
     GitHubApiError(#[from] crate::github::GitHubError),
+    #[error("Database Error: {0}")]
+    DatabaseError(#[from] crate::database::DatabaseError),
+    #[error("Processing Error: {0}")]
+    ProcessingError(#[from] crate::processing::ProcessingError),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_type, message) = match &self {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "unauthorized", msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg),
             AppError::InternalServerError(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "internal_server_error",
                 msg,
             ),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg),
             AppError::ServiceUnavailable(msg) => {
                 (StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", msg)
             }
@@ -251,7 +402,23 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "github_api_error",
                 &msg.to_string(),
-            )
+            ),
+            AppError::DatabaseError(
+                msg @ (crate::database::DatabaseError::RepositoryNotFound(_)
+                | crate::database::DatabaseError::DocumentNotFound(_)
+                | crate::database::DatabaseError::WebhookDeliveryNotFound(_)
+                | crate::database::DatabaseError::JobNotFound(_)),
+            ) => (StatusCode::NOT_FOUND, "not_found", &msg.to_string()),
+            AppError::DatabaseError(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+                &msg.to_string(),
+            ),
+            AppError::ProcessingError(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "processing_error",
+                &msg.to_string(),
+            ),
         };
 
         let error_response = ErrorResponse {
@@ -267,24 +434,76 @@ impl IntoResponse for AppError {
     }
 }
 
-pub async fn start_server(port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let app = create_app();
+pub async fn start_server(state: AppState) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if state.config.github_webhook_secrets.is_empty() {
+        warn!("GITHUB_WEBHOOK_SECRET is not set; all GitHub webhook deliveries will be rejected");
+    }
+    if state.config.cors_allowed_origins.is_empty() {
+        info!("CORS_ALLOWED_ORIGINS is not set; browsers may only call the API from its own origin");
+    }
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
-        error!("Failed to bind to address {}: {}", addr, e);
-        e
-    })?;
-    info!("Server starting on http://0.0.0.0:{}", port);
-    info!("Press Ctrl+C to stop the server");
+    let config = state.config.clone();
+    let app = create_app(state);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    let host = config.server_host.as_str();
+    let addr = tokio::net::lookup_host((host, config.server_port))
         .await
-        .map_err(|e| {
-            error!("Server error: {}", e);
-            e
-        })?;
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("Cannot resolve server address {}", host))?;
+
+    match &config.tls {
+        Some(tls) => {
+            let rustls = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .map_err(|e| {
+                    error!(
+                        "Failed to load TLS certificate {} and key {}: {}",
+                        tls.cert_path.display(),
+                        tls.key_path.display(),
+                        e
+                    );
+                    e
+                })?;
+
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
+
+            info!("Server starting on https://{}", addr);
+            info!("Press Ctrl+C to stop the server");
+
+            axum_server::bind_rustls(addr, rustls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .map_err(|e| {
+                    error!("Server error: {}", e);
+                    e
+                })?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+                error!("Failed to bind to address {}: {}", addr, e);
+                e
+            })?;
+            info!("Server starting on http://{}", addr);
+            info!("Press Ctrl+C to stop the server");
+
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+                .map_err(|e| {
+                    error!("Server error: {}", e);
+                    e
+                })?;
+        }
+    }
 
     Ok(())
 }
@@ -316,3 +535,198 @@ async fn shutdown_signal() {
         },
     }
 }
+
+#[cfg(test)]
+pub(crate) async fn test_state(database: Database) -> AppState {
+    let config = ApplicationConfig {
+        github_organizations: vec![crate::OrganizationConfig {
+            name: "test-org".to_string(),
+            auth: crate::GitHubAuth::Token("token".to_string()),
+        }],
+        database_url: "postgres://localhost/test".to_string(),
+        server_host: "127.0.0.1".to_string(),
+        server_port: 3000,
+        cors_allowed_origins: vec!["https://docs.example.com".to_string()],
+        request_timeout: crate::config::DEFAULT_REQUEST_TIMEOUT,
+        tls: None,
+        github_webhook_secrets: vec!["secret".to_string()],
+        admin_api_token: Some("admin-token".to_string()),
+        site_public: false,
+        job_max_attempts: Default::default(),
+        scan_schedule: None,
+        process_schedule: None,
+        output_dir: std::env::temp_dir(),
+        repository_filter: Default::default(),
+        github_http_cache: None,
+    };
+    let github = GitHubClients::new(&config).await.unwrap();
+    AppState::new(config, database, github)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::GitHubClient;
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+
+    /// An app whose GitHub client talks to `server`.
+    async fn app_with_github(pool: PgPool, server: &mockito::Server) -> Router {
+        let mut state = test_state(Database::from_pool(pool)).await;
+        state.github = GitHubClients::from_clients(vec![GitHubClient::with_base_uri(
+            "test-org",
+            &server.url(),
+        )]);
+        create_app(state)
+    }
+
+    async fn readiness(app: &Router) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::get("/health/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn component_health(body: &serde_json::Value) -> Vec<(&str, bool)> {
+        body["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["name"].as_str().unwrap(), c["healthy"].as_bool().unwrap()))
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn test_health_probes(pool: PgPool) {
+        let mut server = mockito::Server::new_async().await;
+        let url = server.url();
+        let user = serde_json::json!({
+            "login": "docs-bot",
+            "id": 1,
+            "node_id": "U_1",
+            "avatar_url": format!("{url}/avatar"),
+            "gravatar_id": "",
+            "url": format!("{url}/users/docs-bot"),
+            "html_url": format!("{url}/docs-bot"),
+            "followers_url": format!("{url}/followers"),
+            "following_url": format!("{url}/following"),
+            "gists_url": format!("{url}/gists"),
+            "starred_url": format!("{url}/starred"),
+            "subscriptions_url": format!("{url}/subscriptions"),
+            "organizations_url": format!("{url}/orgs"),
+            "repos_url": format!("{url}/repos"),
+            "events_url": format!("{url}/events"),
+            "received_events_url": format!("{url}/received_events"),
+            "type": "User",
+            "site_admin": false
+        });
+        // Repeated probes within the TTL reuse the first check
+        let authenticated = server
+            .mock("GET", "/user")
+            .with_header("content-type", "application/json")
+            .with_body(user.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let app = app_with_github(pool.clone(), &server).await;
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/health/live").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for _ in 0..2 {
+            let (status, body) = readiness(&app).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["status"], "ready");
+            assert_eq!(
+                component_health(&body),
+                [
+                    ("database", true),
+                    ("migrations", true),
+                    ("github", true),
+                    ("storage", true)
+                ]
+            );
+            let github = body["components"][2]["details"].as_str().unwrap();
+            assert!(github.starts_with("test-org: Authenticated as docs-bot"));
+        }
+        authenticated.assert_async().await;
+
+        // GitHub rejects the credentials, so it is reported as down
+        authenticated.remove_async().await;
+        server
+            .mock("GET", "/user")
+            .with_status(401)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message": "Bad credentials"}"#)
+            .create_async()
+            .await;
+        let app = app_with_github(pool, &server).await;
+
+        let (status, body) = readiness(&app).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unavailable");
+        assert_eq!(
+            component_health(&body),
+            [
+                ("database", true),
+                ("migrations", true),
+                ("github", false),
+                ("storage", true)
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_cors_allows_configured_origins(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+        let preflight = |origin: &str| {
+            Request::options("/api/repositories")
+                .header(http::header::ORIGIN, origin)
+                .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(preflight("https://docs.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[http::header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://docs.example.com"
+        );
+
+        let response = app
+            .oneshot(preflight("https://elsewhere.example.com"))
+            .await
+            .unwrap();
+        assert!(
+            !response
+                .headers()
+                .contains_key(http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_start_server_rejects_missing_tls_files(pool: PgPool) {
+        let mut state = test_state(Database::from_pool(pool)).await;
+        let missing = std::env::temp_dir().join("documents-missing-tls");
+        Arc::make_mut(&mut state.config).tls = Some(crate::TlsConfig {
+            cert_path: missing.join("cert.pem"),
+            key_path: missing.join("key.pem"),
+        });
+
+        assert!(start_server(state).await.is_err());
+    }
+}
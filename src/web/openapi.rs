@@ -0,0 +1,218 @@
+//! The OpenAPI 3 document describing every route of `create_app`, served at
+//! `/api/openapi.json` and written to disk by the `openapi` command.
+
+use crate::web::api::jobs;
+use crate::web::auth::SESSION_COOKIE;
+use crate::web::site;
+use axum::Json;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+/// Name of the bearer security scheme that operations requiring a scope refer to.
+pub const SECURITY_SCHEME: &str = "bearer";
+
+/// Name of the cookie security scheme the documentation site also accepts.
+pub const SESSION_SECURITY_SCHEME: &str = "session";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Documents API",
+        description = "Documentation collected from the repositories of a GitHub organization."
+    ),
+    components(schemas(
+        super::ErrorResponse,
+        super::HealthCheckResponse,
+        site::LoginForm,
+        jobs::ProgressEvent,
+        jobs::StatusEvent,
+    )),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "monitoring", description = "Metrics and this document"),
+        (name = "documents", description = "Repositories, documents and search"),
+        (name = "site", description = "Documents rendered as HTML pages"),
+        (name = "mcp", description = "Model Context Protocol server"),
+        (name = "jobs", description = "Scan and processing jobs"),
+        (name = "webhooks", description = "GitHub webhook deliveries"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the bearer scheme and documents the 401 and 403 responses of every operation
+/// that requires a scope, so each handler only lists its own responses.
+struct Authentication;
+
+impl Modify for Authentication {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            SECURITY_SCHEME,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "An API key, or the admin token, as a bearer token. Operations list the \
+                         scope the key needs; the admin scope grants every other scope.",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            SESSION_SECURITY_SCHEME,
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "A session opened by signing in at /docs/login with an API key holding docs:read.",
+            ))),
+        );
+
+        let error = |description: &str| {
+            ResponseBuilder::new()
+                .description(description)
+                .content(
+                    "application/json",
+                    ContentBuilder::new()
+                        .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                        .build(),
+                )
+                .build()
+        };
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                if operation.security.is_none() {
+                    continue;
+                }
+                let responses = &mut operation.responses.responses;
+                responses.insert(
+                    "401".to_string(),
+                    error("The bearer token is missing or not a valid key").into(),
+                );
+                responses.insert(
+                    "403".to_string(),
+                    error("The key does not have the required scope").into(),
+                );
+            }
+        }
+    }
+}
+
+/// The OpenAPI document of the web server: the operations of every route of
+/// [`super::routes`], with axum's `{*path}` wildcards written as OpenAPI parameters.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    openapi.merge(super::routes(None).into_openapi());
+    openapi.paths.paths = std::mem::take(&mut openapi.paths.paths)
+        .into_iter()
+        .map(|(path, item)| (path.replace("{*", "{"), item))
+        .collect();
+    Authentication.modify(&mut openapi);
+    openapi
+}
+
+/// `/api/openapi.json`: this document, for generating clients.
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "monitoring",
+    responses((status = 200, description = "The OpenAPI document", body = serde_json::Value))
+)]
+pub(crate) async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use std::collections::BTreeSet;
+    use tower::ServiceExt;
+
+    const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let spec = serde_json::to_value(openapi()).unwrap();
+        let mut routes = BTreeSet::new();
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for method in METHODS {
+                if item.get(method).is_some() {
+                    routes.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn test_routes_are_documented() {
+        let documented = documented_routes();
+        for (method, path) in [
+            ("get", "/health/ready"),
+            ("get", "/api/jobs/{id}"),
+            ("delete", "/api/jobs/{id}"),
+            ("get", "/api/repositories/{owner}/{name}/documents/{path}"),
+            ("get", "/docs/{owner}/{repo}/{path}"),
+            ("post", "/docs/login"),
+            ("post", "/api/webhooks/deliveries/{delivery_id}/replay"),
+        ] {
+            assert!(
+                documented.contains(&(method.to_string(), path.to_string())),
+                "{} {} is missing from the OpenAPI document",
+                method,
+                path
+            );
+        }
+        assert!(documented.iter().all(|(_, path)| !path.contains('*')));
+    }
+
+    #[test]
+    fn test_document_describes_schemas_and_scopes() {
+        let spec = serde_json::to_value(openapi()).unwrap();
+
+        let schemas = &spec["components"]["schemas"];
+        assert!(schemas["ErrorResponse"]["properties"]["error"].is_object());
+        assert!(schemas["HealthCheckResponse"]["properties"]["version"].is_object());
+        assert_eq!(
+            spec["components"]["securitySchemes"][SECURITY_SCHEME]["scheme"],
+            "bearer"
+        );
+
+        let create_job = &spec["paths"]["/api/jobs"]["post"];
+        assert_eq!(create_job["security"][0][SECURITY_SCHEME][0], "jobs:write");
+        assert!(create_job["responses"]["403"].is_object());
+        let health = &spec["paths"]["/health"]["get"];
+        assert!(health.get("security").is_none());
+        assert!(health["responses"].get("401").is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_openapi_json_is_served(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+
+        let response = app
+            .oneshot(
+                Request::get("/api/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["openapi"].as_str().unwrap().starts_with("3."));
+        assert_eq!(body["info"]["title"], "Documents API");
+    }
+}
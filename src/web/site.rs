@@ -0,0 +1,967 @@
+//! The documentation site: stored documents rendered as HTML pages under
+//! `/docs/{owner}/{repo}`.
+//!
+//! Browsers sign in at `/docs/login` with an API key, which opens a session held by a
+//! cookie, unless `SITE_PUBLIC` makes the pages public.
+
+use crate::database::{Document, Repository};
+use crate::processing::persistence::content_hash;
+use crate::web::api::repositories::find_repository;
+use crate::web::auth::{self, Scope};
+use crate::web::{AppError, AppState, ErrorResponse};
+use crate::{DocumentConfig, ProjectConfig};
+use axum::Form;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use utoipa::{IntoParams, ToSchema};
+
+/// Characters left as they are in a path segment of a page URL.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Pages are cached by browsers but revalidated with their ETag on every visit.
+const CACHE_CONTROL: HeaderValue = HeaderValue::from_static("no-cache");
+
+/// A heading of a rendered page, linked from its table of contents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: usize,
+    pub title: String,
+    pub id: String,
+}
+
+/// An entry of the navigation, mirroring a document of `documents.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavItem {
+    pub title: String,
+    pub path: Option<String>,
+    pub children: Vec<NavItem>,
+}
+
+/// `/docs/{owner}/{repo}`: the navigation of a repository's documents.
+#[utoipa::path(
+    get,
+    path = "/docs/{owner}/{repo}",
+    tag = "site",
+    params(
+        ("owner" = String, Path, description = "Organization that owns the repository"),
+        ("repo" = String, Path, description = "Name of the repository within the organization"),
+    ),
+    responses(
+        (status = 200, description = "Index page of the repository", body = String, content_type = "text/html"),
+        (status = 303, description = "No session; redirects to the sign-in form"),
+        (status = 404, description = "No documented repository has this name", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]), ("session" = []))
+)]
+pub(crate) async fn repository_index(
+    State(state): State<AppState>,
+    Path((owner, name)): Path<(String, String)>,
+) -> Result<Html<String>, AppError> {
+    let repo = find_repository(&state, &format!("{}/{}", owner, name)).await?;
+    let config = project_config(&repo)?;
+    let nav = navigation(&config);
+
+    let mut body = String::new();
+    write!(body, "<h1>{}</h1>", text(&config.project.name)).unwrap();
+    write!(body, "<p>{}</p>", text(&config.project.description)).unwrap();
+    render_nav_list(&mut body, &repo.full_name, &nav, None);
+
+    Ok(Html(layout(
+        &config.project.name,
+        &repo.full_name,
+        &nav,
+        None,
+        &breadcrumbs_html(&repo.full_name, &config, &[]),
+        &body,
+        &[],
+    )))
+}
+
+/// `/docs/{owner}/{repo}/{*path}`: a document rendered as HTML.
+///
+/// The ETag combines the document's `content_hash` with the repository configuration, since
+/// the navigation changes with it; `If-None-Match` requests for an unchanged page get a 304.
+#[utoipa::path(
+    get,
+    path = "/docs/{owner}/{repo}/{*path}",
+    tag = "site",
+    params(
+        ("owner" = String, Path, description = "Organization that owns the repository"),
+        ("repo" = String, Path, description = "Name of the repository within the organization"),
+        ("path" = String, Path, description = "Path of the document in the repository; may contain slashes"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy of the page"),
+    ),
+    responses(
+        (status = 200, description = "The rendered document", body = String, content_type = "text/html"),
+        (status = 303, description = "No session; redirects to the sign-in form"),
+        (status = 304, description = "The cached copy is still current"),
+        (status = 404, description = "The repository or document does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = ["docs:read"]), ("session" = []))
+)]
+pub(crate) async fn document_page(
+    State(state): State<AppState>,
+    Path((owner, name, path)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let repo = find_repository(&state, &format!("{}/{}", owner, name)).await?;
+    let config = project_config(&repo)?;
+
+    let doc = state
+        .database
+        .get_document_by_path(repo.id, &path)
+        .await
+        .map_err(|_| {
+            AppError::NotFound(format!(
+                "Document {} not found in repository {}",
+                path, repo.full_name
+            ))
+        })?;
+
+    let etag = page_etag(&doc, &repo);
+    if if_none_match(&headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, CACHE_CONTROL)],
+        )
+            .into_response());
+    }
+
+    let nav = navigation(&config);
+    let trail = nav_trail(&nav, &doc.file_path).unwrap_or_default();
+    let (content, toc) = render_markdown(&doc.content);
+
+    let page = layout(
+        &doc.title,
+        &repo.full_name,
+        &nav,
+        Some(&doc.file_path),
+        &breadcrumbs_html(&repo.full_name, &config, &trail),
+        &format!("<article>{}</article>", content),
+        &toc,
+    );
+
+    Ok((
+        [(header::ETAG, etag), (header::CACHE_CONTROL, CACHE_CONTROL)],
+        Html(page),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct LoginQuery {
+    /// Page to return to once signed in
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct LoginForm {
+    /// An API key holding the docs:read scope
+    key: String,
+    /// Page to return to once signed in
+    next: Option<String>,
+}
+
+/// `/docs/login`: the form browsers sign in to the site with.
+#[utoipa::path(
+    get,
+    path = "/docs/login",
+    tag = "site",
+    params(LoginQuery),
+    responses((status = 200, description = "The sign-in form", body = String, content_type = "text/html"))
+)]
+pub(crate) async fn login_page(Query(query): Query<LoginQuery>) -> Html<String> {
+    Html(login_html(query.next.as_deref(), None))
+}
+
+/// `POST /docs/login`: check an API key and open a session for it.
+#[utoipa::path(
+    post,
+    path = "/docs/login",
+    tag = "site",
+    request_body(content = LoginForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Signed in; redirects to the page that was asked for"),
+        (status = 401, description = "The key cannot read documents", body = String, content_type = "text/html"),
+    )
+)]
+pub(crate) async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> Result<Response, AppError> {
+    let key = form.key.trim();
+    match auth::authorize(&state, Scope::ReadDocs, key).await {
+        Ok(()) => {}
+        Err(AppError::Unauthorized(_) | AppError::Forbidden(_)) => {
+            let page = login_html(
+                form.next.as_deref(),
+                Some("This key is not valid or cannot read documents."),
+            );
+            return Ok((StatusCode::UNAUTHORIZED, Html(page)).into_response());
+        }
+        Err(e) => return Err(e),
+    }
+
+    let session = auth::start_session(&state, key).await?;
+    let cookie = auth::session_cookie_header(&session, auth::is_https(&state, &headers));
+    // Only pages of the site are returned to, so the form cannot send browsers elsewhere
+    let next = form
+        .next
+        .as_deref()
+        .filter(|next| next.starts_with("/docs/") && *next != auth::LOGIN_PATH);
+
+    Ok(match next {
+        Some(next) => ([(header::SET_COOKIE, cookie)], Redirect::to(next)).into_response(),
+        None => (
+            [(header::SET_COOKIE, cookie)],
+            Html(plain_page("Signed in", &signed_in_html())),
+        )
+            .into_response(),
+    })
+}
+
+/// `POST /docs/logout`: end the browser's session and clear its cookie.
+#[utoipa::path(
+    post,
+    path = "/docs/logout",
+    tag = "site",
+    responses((status = 303, description = "Signed out; redirects to the sign-in form"))
+)]
+pub(crate) async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    auth::end_session(&state, &headers).await?;
+    let cookie = auth::cleared_session_cookie_header(auth::is_https(&state, &headers));
+
+    Ok((
+        [(header::SET_COOKIE, cookie)],
+        Redirect::to(auth::LOGIN_PATH),
+    )
+        .into_response())
+}
+
+fn signed_in_html() -> String {
+    format!(
+        "<p>You are signed in.</p>\
+         <form method=\"post\" action=\"{}\"><button type=\"submit\">Sign out</button></form>",
+        auth::LOGOUT_PATH
+    )
+}
+
+fn login_html(next: Option<&str>, error: Option<&str>) -> String {
+    let mut body = String::from("<h1>Sign in</h1>");
+    if let Some(error) = error {
+        write!(body, "<p role=\"alert\">{}</p>", text(error)).unwrap();
+    }
+    write!(
+        body,
+        "<form method=\"post\" action=\"{}\">\
+         <label>API key <input type=\"password\" name=\"key\" required autofocus></label>",
+        auth::LOGIN_PATH
+    )
+    .unwrap();
+    if let Some(next) = next {
+        write!(
+            body,
+            "<input type=\"hidden\" name=\"next\" value=\"{}\">",
+            attr(next)
+        )
+        .unwrap();
+    }
+    body.push_str(" <button type=\"submit\">Sign in</button></form>");
+    plain_page("Sign in", &body)
+}
+
+fn plain_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+</head>
+<body>
+    <main>{body}</main>
+</body>
+</html>"#,
+        title = text(title),
+    )
+}
+
+fn project_config(repo: &Repository) -> Result<ProjectConfig, AppError> {
+    repo.documents_config
+        .as_deref()
+        .and_then(|config| serde_json::from_str(config).ok())
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Repository {} has no documents.toml",
+                repo.full_name
+            ))
+        })
+}
+
+fn page_etag(doc: &Document, repo: &Repository) -> HeaderValue {
+    let config_hash = content_hash(repo.documents_config.as_deref().unwrap_or_default());
+    HeaderValue::from_str(&format!("\"{}-{}\"", doc.content_hash, &config_hash[..16]))
+        .expect("hex digests are valid header values")
+}
+
+/// Whether an `If-None-Match` header matches `etag`, ignoring weak validator prefixes.
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(value) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let etag = etag.to_str().unwrap_or_default();
+
+    value
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+fn normalize_path(path: &str) -> &str {
+    path.trim_start_matches("./").trim_start_matches('/')
+}
+
+/// The navigation of a project, in the order of its document keys.
+pub fn navigation(config: &ProjectConfig) -> Vec<NavItem> {
+    let mut keys: Vec<&String> = config.documents.keys().collect();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| nav_item(&config.documents[key]))
+        .collect()
+}
+
+fn nav_item(document: &DocumentConfig) -> NavItem {
+    NavItem {
+        title: document.title.clone(),
+        path: document
+            .path
+            .as_ref()
+            .map(|path| normalize_path(&path.display().to_string()).to_string()),
+        children: document
+            .sub_documents
+            .iter()
+            .flatten()
+            .map(nav_item)
+            .collect(),
+    }
+}
+
+/// The navigation items leading to the document at `path`, outermost first.
+pub fn nav_trail<'a>(nav: &'a [NavItem], path: &str) -> Option<Vec<&'a NavItem>> {
+    let path = normalize_path(path);
+    for item in nav {
+        if item.path.as_deref() == Some(path) {
+            return Some(vec![item]);
+        }
+        if let Some(mut trail) = nav_trail(&item.children, path) {
+            trail.insert(0, item);
+            return Some(trail);
+        }
+    }
+    None
+}
+
+/// Render markdown to HTML, giving every heading an id and collecting the table of contents.
+///
+/// Raw HTML in documents is escaped rather than passed through, and links and images keep
+/// only URLs that cannot run script, since documents come from any repository of the
+/// organization.
+pub fn render_markdown(markdown: &str) -> (String, Vec<TocEntry>) {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options)
+        .map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            other => other,
+        })
+        .collect();
+
+    let mut toc = Vec::new();
+    let mut used_ids = HashMap::new();
+    let mut index = 0;
+    while index < events.len() {
+        if let Event::Start(Tag::Heading { level, .. }) = &events[index] {
+            let level = *level as usize;
+            let mut title = String::new();
+            let mut end = index + 1;
+            while end < events.len() && !matches!(events[end], Event::End(TagEnd::Heading(_))) {
+                if let Event::Text(value) | Event::Code(value) = &events[end] {
+                    title.push_str(value);
+                }
+                end += 1;
+            }
+
+            let id = unique_slug(&title, &mut used_ids);
+            if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[index] {
+                *heading_id = Some(id.clone().into());
+            }
+            toc.push(TocEntry { level, title, id });
+            index = end;
+        }
+        index += 1;
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    (html, toc)
+}
+
+/// `url` if it is relative, a fragment, or an http, https or mailto URL; otherwise nothing.
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // Browsers ignore whitespace and control characters within a scheme
+    let normalized: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    let scheme = normalized
+        .find([':', '/', '?', '#'])
+        .filter(|&end| normalized[end..].starts_with(':'))
+        .map(|end| normalized[..end].to_ascii_lowercase());
+
+    match scheme.as_deref() {
+        None | Some("http" | "https" | "mailto") => url,
+        Some(_) => CowStr::Borrowed(""),
+    }
+}
+
+fn unique_slug(title: &str, used_ids: &mut HashMap<String, usize>) -> String {
+    let mut slug = String::new();
+    for c in title.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_matches('-') {
+        "" => "section".to_string(),
+        slug => slug.to_string(),
+    };
+
+    let count = used_ids.entry(slug.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        slug
+    } else {
+        format!("{}-{}", slug, *count - 1)
+    }
+}
+
+/// The URL of the index page of a repository, from its `owner/name`.
+fn repository_url(full_name: &str) -> String {
+    let mut url = String::from("/docs");
+    push_segments(&mut url, full_name);
+    url
+}
+
+/// The URL of the page of a document, with each segment of its path percent-encoded.
+fn page_url(full_name: &str, path: &str) -> String {
+    let mut url = repository_url(full_name);
+    push_segments(&mut url, path);
+    url
+}
+
+fn push_segments(url: &mut String, path: &str) {
+    for segment in path.split('/') {
+        write!(url, "/{}", utf8_percent_encode(segment, PATH_SEGMENT)).unwrap();
+    }
+}
+
+fn render_nav_list(html: &mut String, repo: &str, items: &[NavItem], current: Option<&str>) {
+    if items.is_empty() {
+        return;
+    }
+
+    html.push_str("<ul>");
+    for item in items {
+        html.push_str("<li>");
+        match &item.path {
+            Some(path) if Some(path.as_str()) == current => {
+                write!(html, "<strong>{}</strong>", text(&item.title)).unwrap();
+            }
+            Some(path) => {
+                write!(
+                    html,
+                    "<a href=\"{}\">{}</a>",
+                    attr(&page_url(repo, path)),
+                    text(&item.title)
+                )
+                .unwrap();
+            }
+            None => write!(html, "<span>{}</span>", text(&item.title)).unwrap(),
+        }
+        render_nav_list(html, repo, &item.children, current);
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+}
+
+fn breadcrumbs_html(repo: &str, config: &ProjectConfig, trail: &[&NavItem]) -> String {
+    let mut html = String::from("<nav class=\"breadcrumbs\" aria-label=\"Breadcrumbs\">");
+    write!(
+        html,
+        "<a href=\"{}\">{}</a>",
+        attr(&repository_url(repo)),
+        text(&config.project.name)
+    )
+    .unwrap();
+
+    for (index, item) in trail.iter().enumerate() {
+        html.push_str(" / ");
+        match &item.path {
+            Some(path) if index + 1 < trail.len() => write!(
+                html,
+                "<a href=\"{}\">{}</a>",
+                attr(&page_url(repo, path)),
+                text(&item.title)
+            )
+            .unwrap(),
+            _ => write!(html, "<span>{}</span>", text(&item.title)).unwrap(),
+        }
+    }
+
+    html.push_str("</nav>");
+    html
+}
+
+fn toc_html(toc: &[TocEntry]) -> String {
+    let entries: Vec<&TocEntry> = toc.iter().filter(|entry| entry.level > 1).collect();
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from("<nav class=\"toc\" aria-label=\"Contents\"><h2>Contents</h2><ul>");
+    for entry in entries {
+        write!(
+            html,
+            "<li class=\"toc-level-{}\"><a href=\"#{}\">{}</a></li>",
+            entry.level,
+            attr(&entry.id),
+            text(&entry.title)
+        )
+        .unwrap();
+    }
+    html.push_str("</ul></nav>");
+    html
+}
+
+fn layout(
+    title: &str,
+    repo: &str,
+    nav: &[NavItem],
+    current: Option<&str>,
+    breadcrumbs: &str,
+    body: &str,
+    toc: &[TocEntry],
+) -> String {
+    let mut nav_html = String::new();
+    render_nav_list(&mut nav_html, repo, nav, current);
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title}</title>
+    <style>
+        body {{ display: grid; grid-template-columns: 16rem 1fr 14rem; gap: 2rem; margin: 0 auto; max-width: 80rem; padding: 1rem; font-family: system-ui, sans-serif; line-height: 1.5; }}
+        .site-nav ul, .toc ul {{ list-style: none; padding-left: 1rem; }}
+        .toc-level-3 {{ margin-left: 1rem; }}
+        .toc-level-4, .toc-level-5, .toc-level-6 {{ margin-left: 2rem; }}
+        pre {{ overflow-x: auto; padding: 1rem; background: #f6f8fa; }}
+    </style>
+</head>
+<body>
+    <nav class="site-nav" aria-label="Documents">{nav_html}</nav>
+    <main>{breadcrumbs}{body}</main>
+    <aside>{toc}</aside>
+</body>
+</html>"#,
+        title = text(title),
+        toc = toc_html(toc),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::web::{create_app, test_state};
+    use crate::{DocumentConfig, ProjectDetails};
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use chrono::Utc;
+    use sqlx::PgPool;
+    use std::path::PathBuf;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn document(
+        title: &str,
+        path: Option<&str>,
+        sub_documents: Vec<DocumentConfig>,
+    ) -> DocumentConfig {
+        DocumentConfig {
+            title: title.to_string(),
+            path: path.map(PathBuf::from),
+            sub_documents: (!sub_documents.is_empty()).then_some(sub_documents),
+        }
+    }
+
+    fn project() -> ProjectConfig {
+        ProjectConfig {
+            project: ProjectDetails {
+                name: "Test Project".to_string(),
+                description: "A test project".to_string(),
+            },
+            documents: HashMap::from([
+                (
+                    "guides".to_string(),
+                    document(
+                        "Guides",
+                        None,
+                        vec![document("Setup", Some("./docs/setup.md"), vec![])],
+                    ),
+                ),
+                (
+                    "overview".to_string(),
+                    document("Overview", Some("README.md"), vec![]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let (html, toc) =
+            render_markdown("# Title\n\n## Set up\n\ntext <script>x</script>\n\n## Set up\n");
+
+        assert!(html.contains(r#"<h2 id="set-up">Set up</h2>"#));
+        assert!(html.contains(r#"<h2 id="set-up-1">"#));
+        assert!(html.contains("&lt;script&gt;"));
+        assert_eq!(
+            toc.iter()
+                .map(|entry| entry.id.as_str())
+                .collect::<Vec<_>>(),
+            ["title", "set-up", "set-up-1"]
+        );
+        assert_eq!(toc[1].level, 2);
+    }
+
+    #[test]
+    fn test_render_markdown_drops_unsafe_urls() {
+        let (html, _) = render_markdown(
+            "[run](javascript:alert(1)) [case](<Java\tScript:alert(1)>) \
+             ![pixel](data:image/svg+xml;base64,PHN2Zz4=)\n\n\
+             [site](https://example.com) [mail](mailto:docs@example.com) \
+             [guide](../guide.md#setup) [top](#top) ![logo](images/logo.png)",
+        );
+
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("Script:"));
+        assert!(!html.contains("data:"));
+        assert!(html.contains(r#"<a href="">run</a>"#));
+        assert!(html.contains(r#"<a href="">case</a>"#));
+        assert!(html.contains(r#"<img src="" alt="pixel" />"#));
+        assert!(html.contains(r#"<a href="https://example.com">site</a>"#));
+        assert!(html.contains(r#"<a href="mailto:docs@example.com">mail</a>"#));
+        assert!(html.contains(r#"<a href="../guide.md#setup">guide</a>"#));
+        assert!(html.contains(r##"<a href="#top">top</a>"##));
+        assert!(html.contains(r#"<img src="images/logo.png" alt="logo" />"#));
+    }
+
+    #[test]
+    fn test_nav_trail() {
+        let nav = navigation(&project());
+
+        assert_eq!(nav[0].title, "Guides");
+        let trail = nav_trail(&nav, "docs/setup.md").unwrap();
+        let titles: Vec<&str> = trail.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["Guides", "Setup"]);
+        assert!(nav_trail(&nav, "docs/missing.md").is_none());
+    }
+
+    #[test]
+    fn test_page_url_encodes_paths() {
+        assert_eq!(
+            page_url("test-org/test-repo", "docs/set up/#1?.md"),
+            "/docs/test-org/test-repo/docs/set%20up/%231%3F.md"
+        );
+        assert_eq!(
+            page_url("test-org/test-repo", "README.md"),
+            "/docs/test-org/test-repo/README.md"
+        );
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = HeaderValue::from_static("\"abc\"");
+        let mut headers = HeaderMap::new();
+        assert!(!if_none_match(&headers, &etag));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"x\", W/\"abc\""),
+        );
+        assert!(if_none_match(&headers, &etag));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"x\""));
+        assert!(!if_none_match(&headers, &etag));
+    }
+
+    #[sqlx::test]
+    async fn test_document_page(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let repo = database
+            .upsert_repository(&Repository {
+                id: Uuid::new_v4(),
+                name: "test-repo".to_string(),
+                full_name: "test-org/test-repo".to_string(),
+                owner: "test-org".to_string(),
+                description: None,
+                default_branch: "main".to_string(),
+                is_private: false,
+                is_archived: false,
+                is_fork: false,
+                has_documents_config: true,
+                documents_config: Some(serde_json::to_string(&project()).unwrap()),
+                last_scanned_at: None,
+                last_processed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                github_id: None,
+                deleted_at: None,
+            })
+            .await
+            .unwrap();
+        let content = "# Setup\n\n## Install\n\nRun it.";
+        database
+            .upsert_document(&Document {
+                id: Uuid::new_v4(),
+                repository_id: repo.id,
+                file_path: "docs/setup.md".to_string(),
+                title: "Setup".to_string(),
+                content: content.to_string(),
+                content_hash: content_hash(content),
+                metadata: None,
+                file_size: content.len() as i64,
+                last_modified_at: Utc::now(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                headings: String::new(),
+                tags: vec![],
+                last_commit_sha: None,
+                last_author: None,
+                contributors: 0,
+            })
+            .await
+            .unwrap();
+        let app = create_app(test_state(database).await);
+        let request = |etag: Option<&str>| {
+            let mut builder = Request::get("/docs/test-org/test-repo/docs/setup.md")
+                .header("Authorization", "Bearer admin-token");
+            if let Some(etag) = etag {
+                builder = builder.header(header::IF_NONE_MATCH, etag);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(etag.starts_with(&format!("\"{}-", content_hash(content))));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r##"<a href="#install">Install</a>"##));
+        assert!(body.contains("<span>Guides</span> / <span>Setup</span>"));
+        assert!(body.contains(r#"<a href="/docs/test-org/test-repo/README.md">Overview</a>"#));
+        assert!(body.contains("<strong>Setup</strong>"));
+
+        let response = app.clone().oneshot(request(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+
+        let response = app.oneshot(request(Some("\"stale\""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_browsers_sign_in_with_a_session_cookie(pool: PgPool) {
+        let mut state = test_state(Database::from_pool(pool)).await;
+        let app = create_app(state.clone());
+        let page = |cookie: Option<&str>| {
+            let mut builder = Request::get("/docs/test-org/test-repo/README.md");
+            if let Some(cookie) = cookie {
+                builder = builder.header(header::COOKIE, cookie);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+        let login = |form: &str| {
+            Request::post("/docs/login")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(form.to_string()))
+                .unwrap()
+        };
+
+        // Browsers without a session are sent to the sign-in form, and back afterwards
+        let response = app.clone().oneshot(page(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "/docs/login?next=%2Fdocs%2Ftest-org%2Ftest-repo%2FREADME.md"
+        );
+        let response = app
+            .clone()
+            .oneshot(page(Some("documents_session=revoked")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let response = app
+            .clone()
+            .oneshot(login(
+                "key=wrong&next=%2Fdocs%2Ftest-org%2Ftest-repo%2FREADME.md",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!response.headers().contains_key(header::SET_COOKIE));
+
+        let response = app
+            .clone()
+            .oneshot(login(
+                "key=admin-token&next=%2Fdocs%2Ftest-org%2Ftest-repo%2FREADME.md",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "/docs/test-org/test-repo/README.md"
+        );
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.contains("HttpOnly"));
+        assert!(!cookie.contains("Secure"));
+        let session = cookie.split(';').next().unwrap().to_string();
+        assert!(session.starts_with("documents_session="));
+        assert!(!session.contains("admin-token"));
+
+        // Signed in, the page is looked up; the repository is not stored in this test
+        let response = app.clone().oneshot(page(Some(&session))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Signing out ends the session and clears the cookie
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/docs/logout")
+                    .header(header::COOKIE, &session)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.starts_with("documents_session=; Path=/docs; Max-Age=0"));
+        let response = app.clone().oneshot(page(Some(&session))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Revoking the key ends the sessions it opened
+        let generated = auth::generate_api_key();
+        let key = state
+            .database
+            .create_api_key(
+                "reader",
+                &generated.prefix,
+                &generated.hash,
+                &["docs:read".to_string()],
+            )
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(login(&format!("key={}", generated.key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        let session = cookie.split(';').next().unwrap().to_string();
+        let response = app.clone().oneshot(page(Some(&session))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        state.database.revoke_api_key(key.id).await.unwrap();
+        let response = app.clone().oneshot(page(Some(&session))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Behind a TLS-terminating proxy the cookie is only sent over HTTPS
+        let mut request = login("key=admin-token");
+        request
+            .headers_mut()
+            .insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        let response = app.clone().oneshot(request).await.unwrap();
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.ends_with("; Secure"));
+
+        // Redirects only ever lead back to the site
+        let response = app
+            .oneshot(login("key=admin-token&next=https%3A%2F%2Fevil.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut config = (*state.config).clone();
+        config.site_public = true;
+        state.config = std::sync::Arc::new(config);
+        let response = create_app(state).oneshot(page(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
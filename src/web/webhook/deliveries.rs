@@ -0,0 +1,260 @@
+use crate::database::{ProcessingJob, WebhookDelivery};
+use crate::jobs::{self, Job};
+use crate::web::webhook::{WebhookOutcome, process_delivery};
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct DeliveriesQuery {
+    /// Only list deliveries of this event type
+    event: Option<String>,
+    /// Maximum number of deliveries, at most 500
+    limit: Option<i64>,
+}
+
+/// A recorded delivery as returned by the API, without its payload.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DeliverySummary {
+    pub delivery_id: String,
+    pub event_type: String,
+    pub outcome: String,
+    pub outcome_message: Option<String>,
+    pub replay_count: i32,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for DeliverySummary {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            delivery_id: delivery.delivery_id,
+            event_type: delivery.event_type,
+            outcome: delivery.outcome,
+            outcome_message: delivery.outcome_message,
+            replay_count: delivery.replay_count,
+            received_at: delivery.received_at,
+            processed_at: delivery.processed_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ReplayResponse {
+    pub delivery_id: String,
+    /// Job that runs the replay
+    pub job_id: Uuid,
+    pub status: String,
+}
+
+/// Run a recorded delivery through the webhook handler again.
+pub async fn replay(state: &AppState, delivery_id: &str) -> Result<WebhookOutcome, AppError> {
+    let delivery = state.database.get_webhook_delivery(delivery_id).await?;
+    state
+        .database
+        .increment_webhook_delivery_replays(delivery_id)
+        .await?;
+
+    info!(
+        "Replaying GitHub {} delivery {}",
+        delivery.event_type, delivery_id
+    );
+    process_delivery(state, &delivery).await
+}
+
+/// Queue a recorded delivery to be run through the webhook handler again by a worker.
+pub async fn queue_replay(state: &AppState, delivery_id: &str) -> Result<ProcessingJob, AppError> {
+    let delivery = state.database.get_webhook_delivery(delivery_id).await?;
+    state
+        .database
+        .increment_webhook_delivery_replays(delivery_id)
+        .await?;
+
+    info!(
+        "Queueing replay of GitHub {} delivery {}",
+        delivery.event_type, delivery_id
+    );
+    let job = jobs::enqueue(
+        &state.database,
+        &Job::WebhookDelivery {
+            delivery_id: delivery.delivery_id,
+        },
+        None,
+    )
+    .await?;
+
+    Ok(job)
+}
+
+/// List recorded GitHub webhook deliveries, most recent first.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/deliveries",
+    tag = "webhooks",
+    params(DeliveriesQuery),
+    responses(
+        (status = 200, description = "The deliveries", body = Vec<DeliverySummary>),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+    ),
+    security(("bearer" = ["admin"]))
+)]
+pub(crate) async fn list_deliveries(
+    State(state): State<AppState>,
+    Query(params): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<DeliverySummary>>, AppError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let deliveries = state
+        .database
+        .list_webhook_deliveries(params.event.as_deref(), limit)
+        .await?;
+
+    Ok(Json(deliveries.into_iter().map(Into::into).collect()))
+}
+
+/// Queue a recorded delivery to be handled again.
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/deliveries/{delivery_id}/replay",
+    tag = "webhooks",
+    params(("delivery_id" = String, Path, description = "GitHub ID of the delivery")),
+    responses(
+        (status = 202, description = "The replay was queued", body = ReplayResponse),
+        (status = 404, description = "No delivery has this ID", body = ErrorResponse),
+    ),
+    security(("bearer" = ["admin"]))
+)]
+pub(crate) async fn replay_delivery(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<String>,
+) -> Result<(StatusCode, Json<ReplayResponse>), AppError> {
+    let job = queue_replay(&state, &delivery_id).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ReplayResponse {
+            delivery_id,
+            job_id: job.id,
+            status: job.status,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::jobs::Worker;
+    use crate::web::{create_app, test_state};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn admin_request(method: &str, uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_list_deliveries_requires_token(pool: PgPool) {
+        let app = create_app(test_state(Database::from_pool(pool)).await);
+
+        let response = app
+            .clone()
+            .oneshot(admin_request("GET", "/api/webhooks/deliveries", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .oneshot(admin_request(
+                "GET",
+                "/api/webhooks/deliveries",
+                Some("wrong-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_list_and_replay_deliveries(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        database
+            .record_webhook_delivery("delivery-1", "ping", "{}")
+            .await
+            .unwrap();
+        database
+            .record_webhook_delivery("delivery-2", "star", "{}")
+            .await
+            .unwrap();
+        let app = create_app(test_state(database.clone()).await);
+
+        let response = app
+            .clone()
+            .oneshot(admin_request(
+                "GET",
+                "/api/webhooks/deliveries?event=ping",
+                Some("admin-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let deliveries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(deliveries.as_array().unwrap().len(), 1);
+        assert_eq!(deliveries[0]["delivery_id"], "delivery-1");
+
+        let response = app
+            .clone()
+            .oneshot(admin_request(
+                "POST",
+                "/api/webhooks/deliveries/delivery-1/replay",
+                Some("admin-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let replay: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(replay["status"], "pending");
+
+        let delivery = database.get_webhook_delivery("delivery-1").await.unwrap();
+        assert_eq!(delivery.replay_count, 1);
+        assert_eq!(delivery.outcome, "received");
+
+        let worker = Worker::new(
+            test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+        let run = worker.run_next().await.unwrap().unwrap();
+        assert_eq!(run.job.id.to_string(), replay["job_id"]);
+        assert_eq!(run.result, Ok("Processed: pong".to_string()));
+
+        let delivery = database.get_webhook_delivery("delivery-1").await.unwrap();
+        assert_eq!(delivery.outcome, "processed");
+
+        let response = app
+            .oneshot(admin_request(
+                "POST",
+                "/api/webhooks/deliveries/missing/replay",
+                Some("admin-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::web::webhook::WebhookOutcome;
+use crate::web::webhook::repository::Account;
+use crate::web::{AppError, AppState};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+/// The installation object included in GitHub App webhook payloads.
+#[derive(Debug, Deserialize)]
+pub struct InstallationPayload {
+    pub id: u64,
+    pub account: Account,
+}
+
+/// A repository added to or removed from an installation.
+#[derive(Debug, Deserialize)]
+pub struct InstallationRepository {
+    pub full_name: String,
+}
+
+/// A GitHub `installation` or `installation_repositories` event.
+#[derive(Debug, Deserialize)]
+pub struct InstallationEvent {
+    pub action: String,
+    pub installation: InstallationPayload,
+    #[serde(default)]
+    pub repositories_added: Vec<InstallationRepository>,
+    #[serde(default)]
+    pub repositories_removed: Vec<InstallationRepository>,
+}
+
+/// Handle an `installation` or `installation_repositories` delivery.
+///
+/// Installation access tokens are minted on demand, so nothing needs to be stored. The
+/// events are recorded so that an app being removed or suspended on the organization, or
+/// losing access to repositories, shows up in the delivery log.
+pub async fn handle_installation(
+    state: &AppState,
+    event_type: &str,
+    payload: &[u8],
+) -> Result<WebhookOutcome, AppError> {
+    let event: InstallationEvent = serde_json::from_slice(payload)
+        .map_err(|e| AppError::BadRequest(format!("Invalid installation payload: {}", e)))?;
+    let account = &event.installation.account.login;
+
+    if !state.config.has_organization(account) {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "Installation {} belongs to {}",
+            event.installation.id, account
+        )));
+    }
+
+    let message = match (event_type, event.action.as_str()) {
+        ("installation", "deleted" | "suspend") => {
+            warn!(
+                "GitHub App installation {} on {} was {}; API requests will fail",
+                event.installation.id, account, event.action
+            );
+            format!("Installation {} {}", event.installation.id, event.action)
+        }
+        ("installation", action) => {
+            format!("Installation {} {}", event.installation.id, action)
+        }
+        ("installation_repositories", action) => format!(
+            "Installation {} {}: {} repositories added, {} removed",
+            event.installation.id,
+            action,
+            event.repositories_added.len(),
+            event.repositories_removed.len()
+        ),
+        (other, _) => {
+            return Ok(WebhookOutcome::Ignored(format!(
+                "Unsupported event type: {}",
+                other
+            )));
+        }
+    };
+
+    info!("{} on {}", message, account);
+    Ok(WebhookOutcome::Processed(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    async fn test_state() -> AppState {
+        crate::web::test_state(Database::connect_lazy("postgres://localhost/test").unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_installation_events() {
+        let state = test_state().await;
+
+        let outcome = handle_installation(
+            &state,
+            "installation",
+            br#"{"action": "created", "installation": {"id": 7, "account": {"login": "Test-Org"}}}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            outcome,
+            WebhookOutcome::Processed("Installation 7 created".to_string())
+        );
+
+        let outcome = handle_installation(
+            &state,
+            "installation_repositories",
+            br#"{
+                "action": "removed",
+                "installation": {"id": 7, "account": {"login": "test-org"}},
+                "repositories_added": [],
+                "repositories_removed": [{"full_name": "test-org/old"}]
+            }"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            outcome,
+            WebhookOutcome::Processed(
+                "Installation 7 removed: 0 repositories added, 1 removed".to_string()
+            )
+        );
+
+        let outcome = handle_installation(
+            &state,
+            "installation",
+            br#"{"action": "created", "installation": {"id": 8, "account": {"login": "other-org"}}}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.status(), "ignored");
+    }
+}
@@ -0,0 +1,394 @@
+pub mod deliveries;
+pub mod installation;
+pub mod push;
+pub mod repository;
+pub mod signature;
+
+pub use signature::{SIGNATURE_HEADER, SignatureError, sign_payload, verify_signature};
+
+use crate::database::WebhookDelivery;
+use crate::jobs::Job;
+use crate::metrics::METRICS;
+use crate::web::{AppError, AppState, ErrorResponse};
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Html;
+use serde::Deserialize;
+use std::fmt;
+use tracing::{debug, info, warn};
+use utoipa::IntoParams;
+
+/// Header GitHub uses to name the event type of a delivery.
+pub const EVENT_HEADER: &str = "X-GitHub-Event";
+
+/// Header GitHub uses to send the unique ID of a delivery.
+pub const DELIVERY_HEADER: &str = "X-GitHub-Delivery";
+
+/// What happened to a webhook delivery after its signature was verified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookOutcome {
+    Processed(String),
+    Ignored(String),
+}
+
+impl WebhookOutcome {
+    /// Value stored in `webhook_deliveries.outcome`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            WebhookOutcome::Processed(_) => "processed",
+            WebhookOutcome::Ignored(_) => "ignored",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            WebhookOutcome::Processed(message) | WebhookOutcome::Ignored(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for WebhookOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookOutcome::Processed(message) => write!(f, "Processed: {}", message),
+            WebhookOutcome::Ignored(message) => write!(f, "Ignored: {}", message),
+        }
+    }
+}
+
+/// Dispatch a verified delivery to the handler for its event type.
+pub async fn handle_event(
+    state: &AppState,
+    event_type: &str,
+    payload: &[u8],
+) -> Result<WebhookOutcome, AppError> {
+    match event_type {
+        "ping" => Ok(WebhookOutcome::Processed("pong".to_string())),
+        "push" => push::handle_push(state, payload).await,
+        "repository" => repository::handle_repository(state, payload).await,
+        "installation" | "installation_repositories" => {
+            installation::handle_installation(state, event_type, payload).await
+        }
+        other => Ok(WebhookOutcome::Ignored(format!(
+            "Unsupported event type: {}",
+            other
+        ))),
+    }
+}
+
+/// Handle a recorded delivery and store its outcome.
+///
+/// Both live deliveries and replays go through here, so a replay behaves exactly like the
+/// original delivery did.
+pub async fn process_delivery(
+    state: &AppState,
+    delivery: &WebhookDelivery,
+) -> Result<WebhookOutcome, AppError> {
+    let result = handle_event(state, &delivery.event_type, delivery.payload.as_bytes()).await;
+
+    let (outcome, message) = match &result {
+        Ok(outcome) => (outcome.status(), outcome.message().to_string()),
+        Err(e) => ("failed", e.to_string()),
+    };
+    state
+        .database
+        .update_webhook_delivery_outcome(&delivery.delivery_id, outcome, Some(&message))
+        .await?;
+
+    result
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct WebhookQuery {
+    /// Log the payload and acknowledge it without handling the event
+    #[serde(default)]
+    test: bool,
+}
+
+/// Receive a webhook delivery from GitHub and queue it for a worker.
+#[utoipa::path(
+    post,
+    path = "/webhooks/github",
+    tag = "webhooks",
+    params(
+        WebhookQuery,
+        ("X-GitHub-Event" = String, Header, description = "Event type of the delivery"),
+        ("X-GitHub-Delivery" = String, Header, description = "Unique ID of the delivery"),
+        ("X-Hub-Signature-256" = String, Header, description = "HMAC-SHA256 signature of the payload with the webhook secret"),
+    ),
+    request_body(content = serde_json::Value, description = "Event payload as sent by GitHub"),
+    responses(
+        (status = 200, description = "A test or duplicate delivery was acknowledged", body = String, content_type = "text/html"),
+        (status = 202, description = "The delivery was queued", body = String, content_type = "text/html"),
+        (status = 400, description = "A GitHub header is missing or the payload is not UTF-8", body = ErrorResponse),
+        (status = 401, description = "The signature is missing or does not match", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn github_webhook(
+    State(state): State<AppState>,
+    Query(params): Query<WebhookQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Html<String>), AppError> {
+    debug!("Processing github webhook request");
+    debug!("Headers: {:?}", headers);
+    debug!("Query parameters: {:?}", params);
+    debug!("Body length: {}", body.len());
+
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if let Err(e) = verify_signature(&state.config.github_webhook_secrets, &body, signature) {
+        warn!("Rejected GitHub webhook: {}", e);
+        return Err(e.into());
+    }
+
+    if params.test {
+        info!(
+            "Received test webhook with body: {}",
+            String::from_utf8_lossy(&body)
+        );
+        return Ok((
+            StatusCode::OK,
+            Html("<h1>Test webhook received</h1>".to_string()),
+        ));
+    }
+
+    let event_type = header_value(&headers, EVENT_HEADER)?;
+    let delivery_id = header_value(&headers, DELIVERY_HEADER)?;
+    let payload = String::from_utf8(body.to_vec())
+        .map_err(|_| AppError::BadRequest("Webhook payload is not valid UTF-8".to_string()))?;
+
+    info!("Received GitHub {} webhook {}", event_type, delivery_id);
+    METRICS
+        .webhook_deliveries
+        .with_label_values(&[event_type])
+        .inc();
+    // Processing can take minutes, far longer than GitHub waits for a response, so the
+    // delivery is handed to a worker.
+    let job = Job::WebhookDelivery {
+        delivery_id: delivery_id.to_string(),
+    };
+    let Some((_, job)) = state
+        .database
+        .queue_webhook_delivery(delivery_id, event_type, &payload, &job.pending_row(None))
+        .await?
+    else {
+        info!("Ignoring duplicate GitHub delivery {}", delivery_id);
+        return Ok((
+            StatusCode::OK,
+            Html("<h1>GitHub webhook received</h1><p>Duplicate delivery ignored</p>".to_string()),
+        ));
+    };
+    info!("Queued {} job {}", job.job_type, job.id);
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Html(format!(
+            "<h1>GitHub webhook received</h1><p>Queued as job {}</p>",
+            job.id
+        )),
+    ))
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, AppError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::BadRequest(format!("Missing {} header", name)))
+}
+
+impl From<SignatureError> for AppError {
+    fn from(error: SignatureError) -> Self {
+        AppError::Unauthorized(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::jobs::Worker;
+    use crate::web::create_app;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::PgPool;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn test_state() -> AppState {
+        crate::web::test_state(Database::connect_lazy("postgres://localhost/test").unwrap()).await
+    }
+
+    fn webhook_request(signature: Option<&str>, body: &'static str) -> Request<Body> {
+        signed_request(
+            "/webhooks/github?test=true",
+            "ping",
+            "delivery-1",
+            signature,
+            body,
+        )
+    }
+
+    fn signed_request(
+        uri: &str,
+        event_type: &str,
+        delivery_id: &str,
+        signature: Option<&str>,
+        body: &'static str,
+    ) -> Request<Body> {
+        let mut builder = Request::post(uri)
+            .header(EVENT_HEADER, event_type)
+            .header(DELIVERY_HEADER, delivery_id);
+        if let Some(signature) = signature {
+            builder = builder.header(SIGNATURE_HEADER, signature);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_webhook_accepts_signed_delivery() {
+        let body = r#"{"zen":"Design for failure."}"#;
+        let signature = sign_payload("secret", body.as_bytes());
+
+        let response = create_app(test_state().await)
+            .oneshot(webhook_request(Some(&signature), body))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_unsigned_delivery() {
+        let response = create_app(test_state().await)
+            .oneshot(webhook_request(None, "{}"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rejects_bad_signature() {
+        let signature = sign_payload("wrong-secret", b"{}");
+
+        let response = create_app(test_state().await)
+            .oneshot(webhook_request(Some(&signature), "{}"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_requires_delivery_id() {
+        let body = r#"{"zen":"Design for failure."}"#;
+        let signature = sign_payload("secret", body.as_bytes());
+        let request = Request::post("/webhooks/github")
+            .header(EVENT_HEADER, "ping")
+            .header(SIGNATURE_HEADER, signature)
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = create_app(test_state().await)
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_webhook_records_deliveries_once(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let body = r#"{"zen":"Design for failure."}"#;
+        let signature = sign_payload("secret", body.as_bytes());
+
+        for expected in [StatusCode::ACCEPTED, StatusCode::OK] {
+            let response = create_app(crate::web::test_state(database.clone()).await)
+                .oneshot(signed_request(
+                    "/webhooks/github",
+                    "ping",
+                    "delivery-1",
+                    Some(&signature),
+                    body,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+
+        let deliveries = database.list_webhook_deliveries(None, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event_type, "ping");
+        assert_eq!(deliveries[0].payload, body);
+        assert_eq!(deliveries[0].outcome, "received");
+
+        let jobs = database.get_active_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_type, "webhook_delivery");
+
+        let worker = Worker::new(
+            crate::web::test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+        worker.run_next().await.unwrap().unwrap();
+
+        let delivery = database.get_webhook_delivery("delivery-1").await.unwrap();
+        assert_eq!(delivery.outcome, "processed");
+        assert_eq!(delivery.outcome_message.as_deref(), Some("pong"));
+    }
+
+    #[sqlx::test]
+    async fn test_webhook_ignores_push_to_other_branch(pool: PgPool) {
+        let database = Database::from_pool(pool);
+        let body = r#"{
+            "ref": "refs/heads/feature",
+            "repository": {
+                "id": 42,
+                "name": "test-repo",
+                "full_name": "test-org/test-repo",
+                "description": null,
+                "default_branch": "main"
+            },
+            "commits": [{"added": ["docs/a.md"], "removed": [], "modified": []}]
+        }"#;
+        let signature = sign_payload("secret", body.as_bytes());
+
+        let response = create_app(crate::web::test_state(database.clone()).await)
+            .oneshot(signed_request(
+                "/webhooks/github",
+                "push",
+                "delivery-2",
+                Some(&signature),
+                body,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let worker = Worker::new(
+            crate::web::test_state(database.clone()).await,
+            Duration::from_millis(10),
+        );
+        worker.run_next().await.unwrap().unwrap();
+
+        let delivery = database.get_webhook_delivery("delivery-2").await.unwrap();
+        assert_eq!(delivery.outcome, "ignored");
+    }
+
+    #[tokio::test]
+    async fn test_handle_event_ignores_unsupported_events() {
+        let outcome = handle_event(&test_state().await, "star", b"{}")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            WebhookOutcome::Ignored("Unsupported event type: star".to_string())
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use crate::ProjectConfig;
+use crate::github::{Client, GitHubClient, GitHubError};
+use crate::processing::persistence::{process_repository, save_processing_result};
+use crate::processing::{ProcessingError, RepositoryProcessor};
+use crate::web::webhook::WebhookOutcome;
+use crate::web::webhook::repository::{RepositoryPayload, find_repository_row, save_repository};
+use crate::web::{AppError, AppState};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use tracing::{info, warn};
+
+/// Name of the configuration file at the root of every documented repository.
+pub const CONFIG_FILE: &str = "documents.toml";
+
+/// GitHub lists at most this many commits in the payload of a push, so the changes of a
+/// push listing this many may be incomplete.
+pub const PUSH_COMMIT_LIMIT: usize = 20;
+
+/// The parts of a GitHub `push` event payload that are needed to update documents.
+#[derive(Debug, Deserialize)]
+pub struct PushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// The commit the ref points at after the push
+    #[serde(default)]
+    pub after: String,
+    #[serde(default)]
+    pub deleted: bool,
+    pub repository: RepositoryPayload,
+    #[serde(default)]
+    pub commits: Vec<PushCommit>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PushCommit {
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+/// Net file changes of a push, after applying its commits in order.
+#[derive(Debug, Default, PartialEq)]
+pub struct ChangedFiles {
+    /// Files that were added or modified and still exist after the push
+    pub updated: BTreeSet<String>,
+    /// Files that no longer exist after the push
+    pub removed: BTreeSet<String>,
+}
+
+impl ChangedFiles {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What a push means for the documents of a repository.
+#[derive(Debug, PartialEq)]
+pub enum PushPlan {
+    /// `documents.toml` changed, or the changes are not all known, so the whole repository
+    /// is validated and reprocessed
+    FullReprocess,
+    /// Only some referenced files changed
+    Incremental {
+        process: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// None of the changed files are referenced by `documents.toml`
+    NoReferencedChanges,
+}
+
+impl PushEvent {
+    pub fn is_default_branch(&self) -> bool {
+        self.git_ref == format!("refs/heads/{}", self.repository.default_branch)
+    }
+
+    /// Whether the payload may leave out commits of the push, and so their changed files.
+    pub fn may_be_truncated(&self) -> bool {
+        self.commits.len() >= PUSH_COMMIT_LIMIT
+    }
+
+    pub fn changed_files(&self) -> ChangedFiles {
+        let mut changes = ChangedFiles::default();
+
+        for commit in &self.commits {
+            for path in commit.added.iter().chain(&commit.modified) {
+                changes.removed.remove(path);
+                changes.updated.insert(path.clone());
+            }
+            for path in &commit.removed {
+                changes.updated.remove(path);
+                changes.removed.insert(path.clone());
+            }
+        }
+
+        changes
+    }
+}
+
+/// Decide what to reprocess given the changes of a push and the paths referenced by the
+/// repository's `documents.toml`.
+pub fn plan_push(changes: &ChangedFiles, referenced_paths: &[String]) -> PushPlan {
+    if changes.updated.contains(CONFIG_FILE) {
+        return PushPlan::FullReprocess;
+    }
+
+    let referenced: BTreeSet<&str> = referenced_paths
+        .iter()
+        .map(|path| normalize_path(path))
+        .collect();
+
+    let process: Vec<String> = changes
+        .updated
+        .iter()
+        .filter(|path| referenced.contains(normalize_path(path)))
+        .cloned()
+        .collect();
+    let remove: Vec<String> = changes
+        .removed
+        .iter()
+        .filter(|path| referenced.contains(normalize_path(path)))
+        .cloned()
+        .collect();
+
+    if process.is_empty() && remove.is_empty() {
+        PushPlan::NoReferencedChanges
+    } else {
+        PushPlan::Incremental { process, remove }
+    }
+}
+
+fn normalize_path(path: &str) -> &str {
+    path.trim_start_matches("./").trim_start_matches('/')
+}
+
+/// Handle a `push` delivery: reprocess the referenced files that changed on the default branch.
+pub async fn handle_push(state: &AppState, payload: &[u8]) -> Result<WebhookOutcome, AppError> {
+    let event: PushEvent = serde_json::from_slice(payload)
+        .map_err(|e| AppError::BadRequest(format!("Invalid push payload: {}", e)))?;
+    let repository = &event.repository;
+
+    if event.deleted {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "{} was deleted in {}",
+            event.git_ref, repository.full_name
+        )));
+    }
+
+    if !event.is_default_branch() {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "{} is not the default branch of {}",
+            event.git_ref, repository.full_name
+        )));
+    }
+
+    let truncated = event.may_be_truncated();
+    let changes = event.changed_files();
+    if changes.is_empty() && !truncated {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "Push to {} changed no files",
+            repository.full_name
+        )));
+    }
+
+    if changes.removed.contains(CONFIG_FILE) && !truncated {
+        return remove_repository_documents(state, repository).await;
+    }
+
+    let Some(client) = state.github.get(repository.owner()) else {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "{} is not in a configured organization",
+            repository.full_name
+        )));
+    };
+
+    // Everything is read at the pushed commit, which later pushes do not move
+    let after = Some(event.after.as_str()).filter(|after| !after.is_empty());
+    let config = match client.get_project_config(&repository.name, after).await {
+        Ok(config) => config,
+        Err(GitHubError::ConfigFileNotFound(_)) if truncated => {
+            return remove_repository_documents(state, repository).await;
+        }
+        Err(GitHubError::ConfigFileNotFound(_)) => {
+            return Ok(WebhookOutcome::Ignored(format!(
+                "{} has no {}",
+                repository.full_name, CONFIG_FILE
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let plan = if truncated {
+        PushPlan::FullReprocess
+    } else {
+        plan_push(&changes, &config.document_paths())
+    };
+    match plan {
+        PushPlan::NoReferencedChanges => Ok(WebhookOutcome::Ignored(format!(
+            "Push to {} changed no documents",
+            repository.full_name
+        ))),
+        PushPlan::FullReprocess => {
+            reprocess_repository(state, client, repository, config, after).await
+        }
+        PushPlan::Incremental { process, remove } => {
+            reprocess_files(state, client, repository, config, after, &process, &remove).await
+        }
+    }
+}
+
+async fn reprocess_repository(
+    state: &AppState,
+    client: &GitHubClient,
+    repository: &RepositoryPayload,
+    config: ProjectConfig,
+    git_ref: Option<&str>,
+) -> Result<WebhookOutcome, AppError> {
+    let processor = RepositoryProcessor::new(client.clone(), config, repository.name.clone())
+        .with_ref(git_ref.map(str::to_string));
+    let row = find_repository_row(state, repository).await?;
+    let (saved, removed) = match process_repository(&state.database, row, &processor).await {
+        Ok((_, saved, removed)) => (saved, removed),
+        Err(ProcessingError::InvalidConfig(reason)) => {
+            warn!("{}", reason);
+            return Ok(WebhookOutcome::Ignored(reason));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    info!(
+        "Reprocessed {}: {} documents saved, {} removed",
+        repository.full_name, saved, removed
+    );
+    Ok(WebhookOutcome::Processed(format!(
+        "Reprocessed {}: {} documents saved, {} removed",
+        repository.full_name, saved, removed
+    )))
+}
+
+async fn reprocess_files(
+    state: &AppState,
+    client: &GitHubClient,
+    repository: &RepositoryPayload,
+    config: ProjectConfig,
+    git_ref: Option<&str>,
+    process: &[String],
+    remove: &[String],
+) -> Result<WebhookOutcome, AppError> {
+    let row = save_repository(state, repository, Some(&config)).await?;
+
+    let mut saved = 0;
+    if !process.is_empty() {
+        let processor = RepositoryProcessor::new(client.clone(), config, repository.name.clone())
+            .with_ref(git_ref.map(str::to_string));
+        let result = processor.process_files(process, false).await?;
+        saved = save_processing_result(&state.database, row.id, &result).await?;
+    }
+
+    let mut removed = 0;
+    for path in remove {
+        if state.database.delete_document_by_path(row.id, path).await? {
+            removed += 1;
+        }
+    }
+
+    info!(
+        "Updated {}: {} documents saved, {} removed",
+        repository.full_name, saved, removed
+    );
+    Ok(WebhookOutcome::Processed(format!(
+        "Updated {}: {} documents saved, {} removed",
+        repository.full_name, saved, removed
+    )))
+}
+
+async fn remove_repository_documents(
+    state: &AppState,
+    repository: &RepositoryPayload,
+) -> Result<WebhookOutcome, AppError> {
+    if state
+        .database
+        .find_repository_by_github_id_or_full_name(Some(repository.id), &repository.full_name)
+        .await?
+        .is_none()
+    {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "{} was removed from untracked repository {}",
+            CONFIG_FILE, repository.full_name
+        )));
+    }
+
+    let row = save_repository(state, repository, None).await?;
+    let removed = state.database.delete_documents_except(row.id, &[]).await?;
+
+    info!(
+        "{} removed from {}, deleted {} documents",
+        CONFIG_FILE, repository.full_name, removed
+    );
+    Ok(WebhookOutcome::Processed(format!(
+        "{} removed from {}, deleted {} documents",
+        CONFIG_FILE, repository.full_name, removed
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_event(git_ref: &str, commits: Vec<PushCommit>) -> PushEvent {
+        PushEvent {
+            git_ref: git_ref.to_string(),
+            after: "def".to_string(),
+            deleted: false,
+            repository: RepositoryPayload {
+                id: 42,
+                name: "test-repo".to_string(),
+                full_name: "test-org/test-repo".to_string(),
+                description: None,
+                default_branch: "main".to_string(),
+                private: false,
+                fork: false,
+                archived: false,
+            },
+            commits,
+        }
+    }
+
+    fn commit(added: &[&str], modified: &[&str], removed: &[&str]) -> PushCommit {
+        let to_vec = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect();
+        PushCommit {
+            added: to_vec(added),
+            modified: to_vec(modified),
+            removed: to_vec(removed),
+        }
+    }
+
+    fn paths(values: &[&str]) -> Vec<String> {
+        values.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_push_payload() {
+        let payload = r#"{
+            "ref": "refs/heads/main",
+            "before": "abc",
+            "after": "def",
+            "repository": {
+                "id": 1,
+                "name": "test-repo",
+                "full_name": "test-org/test-repo",
+                "description": "A test repository",
+                "default_branch": "main",
+                "private": true
+            },
+            "commits": [
+                {"id": "def", "added": ["docs/new.md"], "removed": [], "modified": ["README.md"]}
+            ]
+        }"#;
+
+        let event: PushEvent = serde_json::from_str(payload).unwrap();
+
+        assert!(event.is_default_branch());
+        assert_eq!(event.after, "def");
+        assert!(event.repository.private);
+        assert!(!event.repository.archived);
+        assert_eq!(event.commits.len(), 1);
+        assert_eq!(event.commits[0].added, vec!["docs/new.md"]);
+    }
+
+    #[test]
+    fn test_is_default_branch() {
+        assert!(push_event("refs/heads/main", vec![]).is_default_branch());
+        assert!(!push_event("refs/heads/feature", vec![]).is_default_branch());
+        assert!(!push_event("refs/tags/main", vec![]).is_default_branch());
+    }
+
+    #[test]
+    fn test_changed_files_applies_commits_in_order() {
+        let event = push_event(
+            "refs/heads/main",
+            vec![
+                commit(&["docs/a.md"], &["docs/b.md"], &["docs/c.md"]),
+                commit(&["docs/c.md"], &[], &["docs/a.md"]),
+            ],
+        );
+
+        let changes = event.changed_files();
+
+        assert_eq!(
+            changes.updated,
+            ["docs/b.md", "docs/c.md"].map(String::from).into()
+        );
+        assert_eq!(changes.removed, ["docs/a.md"].map(String::from).into());
+    }
+
+    #[test]
+    fn test_plan_push_config_change_reprocesses_everything() {
+        let event = push_event("refs/heads/main", vec![commit(&[], &[CONFIG_FILE], &[])]);
+
+        let plan = plan_push(&event.changed_files(), &paths(&["docs/a.md"]));
+
+        assert_eq!(plan, PushPlan::FullReprocess);
+    }
+
+    #[test]
+    fn test_plan_push_only_referenced_files() {
+        let event = push_event(
+            "refs/heads/main",
+            vec![commit(
+                &["docs/new.md"],
+                &["docs/a.md", "src/main.rs"],
+                &["docs/b.md", "docs/unreferenced.md"],
+            )],
+        );
+
+        let plan = plan_push(
+            &event.changed_files(),
+            &paths(&["./docs/a.md", "docs/b.md", "docs/c.md"]),
+        );
+
+        assert_eq!(
+            plan,
+            PushPlan::Incremental {
+                process: paths(&["docs/a.md"]),
+                remove: paths(&["docs/b.md"]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_large_pushes_may_be_truncated() {
+        let event = push_event("refs/heads/main", vec![commit(&[], &["docs/a.md"], &[])]);
+        assert!(!event.may_be_truncated());
+
+        let commits = (0..PUSH_COMMIT_LIMIT)
+            .map(|_| commit(&[], &["src/main.rs"], &[]))
+            .collect();
+        assert!(push_event("refs/heads/main", commits).may_be_truncated());
+    }
+
+    #[sqlx::test]
+    async fn test_push_is_read_at_the_pushed_commit(pool: sqlx::PgPool) {
+        use crate::github::{GitHubClient, GitHubClients};
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let config = server
+            .mock("GET", "/repos/test-org/test-repo/contents/documents.toml")
+            .match_query(Matcher::UrlEncoded("ref".into(), "def".into()))
+            .with_status(404)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message": "Not Found"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let mut state = crate::web::test_state(crate::Database::from_pool(pool)).await;
+        state.github = GitHubClients::from_clients(vec![GitHubClient::with_base_uri(
+            "test-org",
+            &server.url(),
+        )]);
+        let payload = |event: &PushEvent| {
+            serde_json::json!({
+                "ref": event.git_ref,
+                "after": event.after,
+                "repository": {
+                    "id": 42,
+                    "name": "test-repo",
+                    "full_name": "test-org/test-repo",
+                    "description": null,
+                    "default_branch": "main"
+                },
+                "commits": event.commits.iter().map(|commit| serde_json::json!({
+                    "added": commit.added,
+                    "removed": commit.removed,
+                    "modified": commit.modified,
+                })).collect::<Vec<_>>(),
+            })
+            .to_string()
+        };
+
+        let event = push_event("refs/heads/main", vec![commit(&[], &["docs/a.md"], &[])]);
+        let outcome = handle_push(&state, payload(&event).as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            WebhookOutcome::Ignored("test-org/test-repo has no documents.toml".to_string())
+        );
+
+        // A push that may list only some of its commits is not ignored for what it lists
+        let commits = (0..PUSH_COMMIT_LIMIT)
+            .map(|_| commit(&[], &["src/main.rs"], &[]))
+            .collect();
+        let event = push_event("refs/heads/main", commits);
+        let outcome = handle_push(&state, payload(&event).as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            WebhookOutcome::Ignored(
+                "documents.toml was removed from untracked repository test-org/test-repo"
+                    .to_string()
+            )
+        );
+
+        config.assert_async().await;
+    }
+
+    #[test]
+    fn test_plan_push_without_referenced_changes() {
+        let event = push_event("refs/heads/main", vec![commit(&[], &["src/main.rs"], &[])]);
+
+        let plan = plan_push(&event.changed_files(), &paths(&["docs/a.md"]));
+
+        assert_eq!(plan, PushPlan::NoReferencedChanges);
+    }
+}
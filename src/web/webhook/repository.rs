@@ -0,0 +1,390 @@
+use crate::ProjectConfig;
+use crate::database::Repository;
+use crate::processing::persistence::with_config;
+use crate::web::webhook::WebhookOutcome;
+use crate::web::{AppError, AppState};
+use chrono::Utc;
+use serde::Deserialize;
+use tracing::info;
+
+/// The repository object included in GitHub webhook payloads.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepositoryPayload {
+    pub id: i64,
+    pub name: String,
+    pub full_name: String,
+    pub description: Option<String>,
+    pub default_branch: String,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub fork: bool,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+impl RepositoryPayload {
+    /// Login of the account that owns the repository.
+    pub fn owner(&self) -> &str {
+        self.full_name
+            .split_once('/')
+            .map(|(owner, _)| owner)
+            .unwrap_or_default()
+    }
+}
+
+/// A GitHub `repository` event.
+#[derive(Debug, Deserialize)]
+pub struct RepositoryEvent {
+    pub action: String,
+    pub repository: RepositoryPayload,
+    #[serde(default)]
+    pub changes: Option<RepositoryChanges>,
+}
+
+/// The `changes` object of `renamed` and `transferred` events.
+#[derive(Debug, Default, Deserialize)]
+pub struct RepositoryChanges {
+    pub repository: Option<NameChange>,
+    pub owner: Option<OwnerChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NameChange {
+    pub name: ChangedFrom,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangedFrom {
+    pub from: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OwnerChange {
+    pub from: PreviousOwner,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviousOwner {
+    pub user: Option<Account>,
+    pub organization: Option<Account>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Account {
+    pub login: String,
+}
+
+impl RepositoryEvent {
+    /// The full name the repository had before this event, if the event changed it.
+    pub fn previous_full_name(&self) -> Option<String> {
+        let changes = self.changes.as_ref()?;
+        let (owner, name) = self.repository.full_name.split_once('/')?;
+
+        if let Some(change) = &changes.repository {
+            return Some(format!("{}/{}", owner, change.name.from));
+        }
+
+        let previous_owner = changes.owner.as_ref().and_then(|change| {
+            change
+                .from
+                .organization
+                .as_ref()
+                .or(change.from.user.as_ref())
+        })?;
+        Some(format!("{}/{}", previous_owner.login, name))
+    }
+}
+
+/// Handle a `repository` delivery by keeping the `repositories` row in sync with GitHub.
+pub async fn handle_repository(
+    state: &AppState,
+    payload: &[u8],
+) -> Result<WebhookOutcome, AppError> {
+    let event: RepositoryEvent = serde_json::from_slice(payload)
+        .map_err(|e| AppError::BadRequest(format!("Invalid repository payload: {}", e)))?;
+    let repository = &event.repository;
+
+    let lookup_name = event
+        .previous_full_name()
+        .unwrap_or_else(|| repository.full_name.clone());
+    let existing = state
+        .database
+        .find_repository_by_github_id_or_full_name(Some(repository.id), &lookup_name)
+        .await?;
+
+    let transferred_out =
+        event.action == "transferred" && !state.config.has_organization(repository.owner());
+
+    match event.action.as_str() {
+        "deleted" => tombstone(state, existing, &repository.full_name, "deleted").await,
+        "transferred" if transferred_out => {
+            tombstone(
+                state,
+                existing,
+                &repository.full_name,
+                "transferred out of the configured organizations",
+            )
+            .await
+        }
+        _ if !state.config.has_organization(repository.owner()) => {
+            Ok(WebhookOutcome::Ignored(format!(
+                "Repository {} belongs to {}",
+                repository.full_name,
+                repository.owner()
+            )))
+        }
+        "created" | "renamed" | "transferred" | "archived" | "unarchived" | "privatized"
+        | "publicized" | "edited" => {
+            let row = repository_row(repository, existing);
+            let saved = state.database.upsert_repository(&row).await?;
+
+            info!(
+                "Repository {} {}; synced row {}",
+                lookup_name, event.action, saved.id
+            );
+            Ok(WebhookOutcome::Processed(format!(
+                "Repository {} {}",
+                saved.full_name, event.action
+            )))
+        }
+        other => Ok(WebhookOutcome::Ignored(format!(
+            "Unsupported repository action: {}",
+            other
+        ))),
+    }
+}
+
+async fn tombstone(
+    state: &AppState,
+    existing: Option<Repository>,
+    full_name: &str,
+    reason: &str,
+) -> Result<WebhookOutcome, AppError> {
+    let Some(existing) = existing else {
+        return Ok(WebhookOutcome::Ignored(format!(
+            "Untracked repository {} was {}",
+            full_name, reason
+        )));
+    };
+
+    let tombstoned = state.database.tombstone_repository(existing.id).await?;
+
+    info!(
+        "Repository {} was {}; tombstoned {} documents",
+        full_name, reason, tombstoned
+    );
+    Ok(WebhookOutcome::Processed(format!(
+        "Repository {} was {}; tombstoned {} documents",
+        full_name, reason, tombstoned
+    )))
+}
+
+/// Insert or update the `repositories` row for a repository from a webhook payload.
+pub(crate) async fn save_repository(
+    state: &AppState,
+    repository: &RepositoryPayload,
+    config: Option<&ProjectConfig>,
+) -> Result<Repository, AppError> {
+    let mut row = with_config(find_repository_row(state, repository).await?, config);
+    row.last_processed_at = Some(row.updated_at);
+
+    Ok(state.database.upsert_repository(&row).await?)
+}
+
+/// The row for a repository from a webhook payload, built on the existing row if it is
+/// tracked already.
+pub(crate) async fn find_repository_row(
+    state: &AppState,
+    repository: &RepositoryPayload,
+) -> Result<Repository, AppError> {
+    let existing = state
+        .database
+        .find_repository_by_github_id_or_full_name(Some(repository.id), &repository.full_name)
+        .await?;
+    Ok(repository_row(repository, existing))
+}
+
+/// Build the row for a repository from its webhook payload, keeping what GitHub does not
+/// send (configuration, scan times, id) from the existing row. Any event other than a
+/// deletion means the repository exists, so the tombstone is cleared.
+fn repository_row(repository: &RepositoryPayload, existing: Option<Repository>) -> Repository {
+    let base = existing.unwrap_or_else(|| Repository::new(repository.owner(), &repository.name));
+
+    Repository {
+        name: repository.name.clone(),
+        full_name: repository.full_name.clone(),
+        owner: repository.owner().to_string(),
+        description: repository.description.clone(),
+        default_branch: repository.default_branch.clone(),
+        is_private: repository.private,
+        is_archived: repository.archived,
+        is_fork: repository.fork,
+        updated_at: Utc::now(),
+        github_id: Some(repository.id),
+        deleted_at: None,
+        ..base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn event(payload: &str) -> RepositoryEvent {
+        serde_json::from_str(payload).unwrap()
+    }
+
+    #[test]
+    fn test_previous_full_name_after_rename() {
+        let event = event(
+            r#"{
+                "action": "renamed",
+                "changes": {"repository": {"name": {"from": "old-name"}}},
+                "repository": {
+                    "id": 42,
+                    "name": "new-name",
+                    "full_name": "test-org/new-name",
+                    "description": null,
+                    "default_branch": "main"
+                }
+            }"#,
+        );
+
+        assert_eq!(
+            event.previous_full_name().as_deref(),
+            Some("test-org/old-name")
+        );
+    }
+
+    #[test]
+    fn test_previous_full_name_after_transfer() {
+        let event = event(
+            r#"{
+                "action": "transferred",
+                "changes": {"owner": {"from": {"user": {"login": "someone"}}}},
+                "repository": {
+                    "id": 42,
+                    "name": "repo",
+                    "full_name": "test-org/repo",
+                    "description": null,
+                    "default_branch": "main",
+                    "private": true
+                }
+            }"#,
+        );
+
+        assert_eq!(event.repository.owner(), "test-org");
+        assert_eq!(event.previous_full_name().as_deref(), Some("someone/repo"));
+    }
+
+    #[test]
+    fn test_repository_row_keeps_existing_fields() {
+        let payload = RepositoryPayload {
+            id: 42,
+            name: "new-name".to_string(),
+            full_name: "test-org/new-name".to_string(),
+            description: Some("Renamed".to_string()),
+            default_branch: "trunk".to_string(),
+            private: true,
+            fork: false,
+            archived: true,
+        };
+        let existing_id = Uuid::new_v4();
+        let existing = Repository {
+            id: existing_id,
+            name: "old-name".to_string(),
+            full_name: "test-org/old-name".to_string(),
+            owner: "test-org".to_string(),
+            description: None,
+            default_branch: "main".to_string(),
+            is_private: false,
+            is_archived: false,
+            is_fork: false,
+            has_documents_config: true,
+            documents_config: Some("{}".to_string()),
+            last_scanned_at: None,
+            last_processed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            github_id: None,
+            deleted_at: None,
+        };
+
+        let row = repository_row(&payload, Some(existing));
+
+        assert_eq!(row.id, existing_id);
+        assert_eq!(row.full_name, "test-org/new-name");
+        assert_eq!(row.default_branch, "trunk");
+        assert!(row.is_private && row.is_archived);
+        assert!(row.has_documents_config);
+        assert_eq!(row.documents_config.as_deref(), Some("{}"));
+        assert_eq!(row.github_id, Some(42));
+    }
+
+    #[sqlx::test]
+    async fn test_repository_events_keep_row_and_tombstone(pool: sqlx::PgPool) {
+        let database = crate::database::Database::from_pool(pool);
+        let state = crate::web::test_state(database.clone()).await;
+        let repository = r#"{
+            "id": 42,
+            "name": "NAME",
+            "full_name": "test-org/NAME",
+            "description": null,
+            "default_branch": "main"
+        }"#;
+        let created = format!(r#"{{"action": "created", "repository": {}}}"#, repository)
+            .replace("NAME", "old-name");
+        let renamed = format!(
+            r#"{{"action": "renamed", "changes": {{"repository": {{"name": {{"from": "old-name"}}}}}}, "repository": {}}}"#,
+            repository
+        )
+        .replace("NAME", "new-name");
+        let deleted = format!(r#"{{"action": "deleted", "repository": {}}}"#, repository)
+            .replace("NAME", "new-name");
+
+        handle_repository(&state, created.as_bytes()).await.unwrap();
+        let original = database
+            .find_repository_by_full_name("test-org/old-name")
+            .await
+            .unwrap()
+            .unwrap();
+
+        handle_repository(&state, renamed.as_bytes()).await.unwrap();
+        let renamed_row = database
+            .find_repository_by_full_name("test-org/new-name")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(renamed_row.id, original.id);
+        assert!(
+            database
+                .find_repository_by_full_name("test-org/old-name")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let outcome = handle_repository(&state, deleted.as_bytes()).await.unwrap();
+        assert!(matches!(outcome, WebhookOutcome::Processed(_)));
+        let deleted_row = database
+            .find_repository_by_full_name("test-org/new-name")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(deleted_row.deleted_at.is_some());
+
+        // Repositories of other owners are not tracked
+        let other = created.replace("test-org/old-name", "someone-else/old-name");
+        let outcome = handle_repository(&state, other.as_bytes()).await.unwrap();
+        assert!(matches!(outcome, WebhookOutcome::Ignored(_)));
+        assert!(
+            database
+                .find_repository_by_full_name("someone-else/old-name")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
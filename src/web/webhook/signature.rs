@@ -0,0 +1,141 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header GitHub uses to send the HMAC-SHA256 signature of a delivery.
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum SignatureError {
+    #[error("No webhook secret is configured")]
+    NoSecretConfigured,
+    #[error("Missing {SIGNATURE_HEADER} header")]
+    Missing,
+    #[error("Malformed {SIGNATURE_HEADER} header")]
+    Malformed,
+    #[error("Signature does not match any configured secret")]
+    Mismatch,
+}
+
+/// Verify a GitHub webhook signature against every configured secret.
+///
+/// The payload is accepted if it was signed with any of the secrets, which lets an old and a
+/// new secret be active at the same time while the secret is rotated in GitHub.
+pub fn verify_signature(
+    secrets: &[String],
+    payload: &[u8],
+    signature: Option<&str>,
+) -> Result<(), SignatureError> {
+    if secrets.is_empty() {
+        return Err(SignatureError::NoSecretConfigured);
+    }
+
+    let signature = signature.ok_or(SignatureError::Missing)?;
+    let digest = signature
+        .trim()
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        .ok_or(SignatureError::Malformed)?;
+
+    let matches = secrets.iter().any(|secret| {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(payload);
+        // `verify_slice` compares in constant time
+        mac.verify_slice(&digest).is_ok()
+    });
+
+    if matches {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+/// Compute the `X-Hub-Signature-256` header value for a payload.
+pub fn sign_payload(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(payload);
+    format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &[u8] = br#"{"zen":"Keep it logically awesome."}"#;
+
+    fn secrets(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_sign_payload_matches_github_example() {
+        // Example from GitHub's "Validating webhook deliveries" documentation
+        let signature = sign_payload("It's a Secret to Everybody", b"Hello, World!");
+        assert_eq!(
+            signature,
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+
+    #[test]
+    fn test_verify_valid_signature() {
+        let signature = sign_payload("secret", PAYLOAD);
+        let result = verify_signature(&secrets(&["secret"]), PAYLOAD, Some(&signature));
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_verify_with_rotated_secrets() {
+        let signature = sign_payload("old-secret", PAYLOAD);
+        let result = verify_signature(
+            &secrets(&["new-secret", "old-secret"]),
+            PAYLOAD,
+            Some(&signature),
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_secret() {
+        let signature = sign_payload("other-secret", PAYLOAD);
+        let result = verify_signature(&secrets(&["secret"]), PAYLOAD, Some(&signature));
+        assert_eq!(result, Err(SignatureError::Mismatch));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_payload() {
+        let signature = sign_payload("secret", PAYLOAD);
+        let result = verify_signature(&secrets(&["secret"]), b"{}", Some(&signature));
+        assert_eq!(result, Err(SignatureError::Mismatch));
+    }
+
+    #[test]
+    fn test_verify_rejects_missing_or_malformed_signature() {
+        let configured = secrets(&["secret"]);
+        assert_eq!(
+            verify_signature(&configured, PAYLOAD, None),
+            Err(SignatureError::Missing)
+        );
+        assert_eq!(
+            verify_signature(&configured, PAYLOAD, Some("sha1=abcdef")),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            verify_signature(&configured, PAYLOAD, Some("sha256=not-hex")),
+            Err(SignatureError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_verify_without_configured_secret() {
+        let signature = sign_payload("secret", PAYLOAD);
+        let result = verify_signature(&[], PAYLOAD, Some(&signature));
+        assert_eq!(result, Err(SignatureError::NoSecretConfigured));
+    }
+}